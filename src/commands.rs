@@ -0,0 +1,188 @@
+use crate::types::type_error::Error;
+use crate::Environment;
+
+pub type Handler = fn(&[&str], &mut Environment) -> Result<String, Error>;
+
+/// What a single argument token is expected to look like.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum TokenKind {
+    /// An identifier such as `x` or `add_one`.
+    Name,
+    /// A comma-separated list of identifiers such as `x,y,z`.
+    NameList,
+    /// A type name such as `Int`.
+    Type,
+    /// Exactly this word.
+    Keyword(&'static str),
+    Any,
+}
+
+impl TokenKind {
+    fn matches(&self, token: &str) -> bool {
+        match self {
+            TokenKind::Name => is_name(token),
+            TokenKind::NameList => token.split(',').all(is_name),
+            TokenKind::Type => is_type(token),
+            TokenKind::Keyword(word) => token == *word,
+            TokenKind::Any => true,
+        }
+    }
+
+    fn describe(&self) -> String {
+        match self {
+            TokenKind::Name => "a name".to_string(),
+            TokenKind::NameList => "a comma-separated list of names".to_string(),
+            TokenKind::Type => "a type".to_string(),
+            TokenKind::Keyword(word) => format!("`{}`", word),
+            TokenKind::Any => "anything".to_string(),
+        }
+    }
+}
+
+fn is_name(token: &str) -> bool {
+    token.starts_with(|c: char| c.is_alphabetic() || c == '_')
+        && token.chars().all(|c| c.is_alphanumeric() || c == '_')
+}
+
+/// A type name or a piece of a type such as `[Int]`, `(Either`, `(a,` or
+/// `->`; whether the pieces fit together is left to the type parser.
+fn is_type(token: &str) -> bool {
+    !token.is_empty()
+        && token
+            .chars()
+            .all(|c| c.is_alphanumeric() || "_[](),->".contains(c))
+}
+
+/// The arguments a command accepts: how many, and the kind of each one.
+/// Tokens past the end of `kinds` must match `rest`.
+#[derive(Debug, Clone, Copy)]
+pub struct ArgSpec {
+    pub min: usize,
+    pub max: Option<usize>,
+    pub kinds: &'static [TokenKind],
+    pub rest: TokenKind,
+}
+
+impl ArgSpec {
+    pub fn exactly(kinds: &'static [TokenKind]) -> Self {
+        ArgSpec {
+            min: kinds.len(),
+            max: Some(kinds.len()),
+            kinds,
+            rest: TokenKind::Any,
+        }
+    }
+
+    pub fn at_least(min: usize, kinds: &'static [TokenKind], rest: TokenKind) -> Self {
+        ArgSpec {
+            min,
+            max: None,
+            kinds,
+            rest,
+        }
+    }
+
+    pub fn between(min: usize, max: usize, kinds: &'static [TokenKind]) -> Self {
+        ArgSpec {
+            min,
+            max: Some(max),
+            kinds,
+            rest: TokenKind::Any,
+        }
+    }
+}
+
+/// A REPL command: everything `process_input` needs to dispatch it and
+/// everything `help` needs to describe it.
+#[derive(Clone)]
+pub struct Command {
+    pub name: &'static str,
+    pub aliases: &'static [&'static str],
+    pub usage: &'static str,
+    pub help: &'static str,
+    pub args: ArgSpec,
+    pub handler: Handler,
+}
+
+impl Command {
+    /// Checks `args` against the command's [`ArgSpec`] so that handlers only
+    /// ever see well-formed input.
+    pub fn validate(&self, args: &[&str]) -> Result<(), Error> {
+        let spec = &self.args;
+        if args.len() < spec.min || spec.max.is_some_and(|max| args.len() > max) {
+            return Err(self.usage_error(format!("got {} token(s)", args.len())));
+        }
+
+        for (i, token) in args.iter().enumerate() {
+            let kind = spec.kinds.get(i).unwrap_or(&spec.rest);
+            if !kind.matches(token) {
+                return Err(self.usage_error(format!(
+                    "got `{}` where {} was expected",
+                    token,
+                    kind.describe()
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn usage_error(&self, problem: String) -> Error {
+        let expected = self
+            .usage
+            .strip_prefix(self.name)
+            .unwrap_or(self.usage)
+            .trim();
+        Error::Usage {
+            command: self.name.to_string(),
+            expected: if expected.is_empty() {
+                "no arguments".to_string()
+            } else {
+                expected.to_string()
+            },
+            problem,
+        }
+    }
+}
+
+#[derive(Clone, Default)]
+pub struct Registry {
+    commands: Vec<Command>,
+}
+
+impl Registry {
+    pub fn new() -> Self {
+        Registry {
+            commands: Vec::new(),
+        }
+    }
+
+    /// Adds a command, replacing any registered command with the same name.
+    pub fn register(&mut self, command: Command) {
+        self.commands
+            .retain(|existing| existing.name != command.name);
+        self.commands.push(command);
+    }
+
+    pub fn find(&self, name: &str) -> Option<&Command> {
+        self.commands
+            .iter()
+            .find(|command| command.name == name || command.aliases.contains(&name))
+    }
+
+    pub fn commands(&self) -> impl Iterator<Item = &Command> {
+        self.commands.iter()
+    }
+
+    /// Every name a command can be invoked by, for completion.
+    pub fn names(&self) -> Vec<&'static str> {
+        let mut names: Vec<&'static str> = self
+            .commands
+            .iter()
+            .flat_map(|command| {
+                std::iter::once(command.name).chain(command.aliases.iter().copied())
+            })
+            .collect();
+        names.sort_unstable();
+        names
+    }
+}
@@ -0,0 +1,373 @@
+use crate::diff::{self, Segment};
+use crate::templates::BUILTIN_TEMPLATES;
+use crate::types::type_enum::Type;
+use crate::types::type_error::Error;
+use std::fmt;
+
+/// An error ready to be shown to the user, with optional `note:` and `help:`
+/// lines attached underneath the main message.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Diagnostic {
+    /// 1-based source line, when the error came from a script.
+    pub line: Option<usize>,
+    /// The error's [`Error::code`], when it came from one.
+    pub code: Option<&'static str>,
+    pub message: String,
+    pub notes: Vec<String>,
+    pub help: Vec<String>,
+    /// `(expected, provided)` types of a mismatch worth diffing part by part.
+    pub diff: Option<(Type, Type)>,
+}
+
+impl Diagnostic {
+    pub fn new(message: impl Into<String>) -> Self {
+        Diagnostic {
+            line: None,
+            code: None,
+            message: message.into(),
+            notes: Vec::new(),
+            help: Vec::new(),
+            diff: None,
+        }
+    }
+
+    pub fn with_note(mut self, note: impl Into<String>) -> Self {
+        self.notes.push(note.into());
+        self
+    }
+
+    pub fn with_help(mut self, help: impl Into<String>) -> Self {
+        self.help.push(help.into());
+        self
+    }
+
+    pub fn with_diff(mut self, expected: Type, provided: Type) -> Self {
+        self.diff = Some((expected, provided));
+        self
+    }
+
+    /// Renders the diagnostic, marking the differing parts of a type diff
+    /// in color when `color` is set and with `^` underneath otherwise.
+    pub fn render(&self, color: bool) -> String {
+        let mut out = String::new();
+        if let Some(line) = self.line {
+            out.push_str(&format!("line {}: ", line));
+        }
+        out.push_str(&format!("Error: {}", self.message));
+        if let Some((expected, provided)) = &self.diff {
+            let (left, right) = diff::diff(expected, provided);
+            out.push_str(&render_segments("expected", &left, GREEN, color));
+            out.push_str(&render_segments("provided", &right, RED, color));
+        }
+        for note in &self.notes {
+            out.push_str(&format!("\n  note: {}", note));
+        }
+        for help in &self.help {
+            out.push_str(&format!("\n  help: {}", help));
+        }
+        out
+    }
+
+    pub fn to_json(&self) -> String {
+        let line = match self.line {
+            Some(line) => line.to_string(),
+            None => "null".to_string(),
+        };
+        let code = match self.code {
+            Some(code) => json_string(code),
+            None => "null".to_string(),
+        };
+        format!(
+            "{{\"line\":{},\"code\":{},\"message\":{},\"notes\":{},\"help\":{}}}",
+            line,
+            code,
+            json_string(&self.message),
+            json_array(&self.notes),
+            json_array(&self.help)
+        )
+    }
+}
+
+impl From<&Error> for Diagnostic {
+    fn from(err: &Error) -> Self {
+        let mut diagnostic = Diagnostic::new(err.to_string());
+        diagnostic.code = Some(err.code());
+        match err {
+            Error::UndeclaredFunction(_) => {
+                diagnostic.with_help("declare it first with `declare_func NAME TYPE... RETURN`")
+            }
+            Error::UndeclaredVariable(_) => {
+                diagnostic.with_help("declare it first with `declare_var NAME TYPE`")
+            }
+            Error::ArityMismatch { function, .. } => {
+                diagnostic.with_help(format!("see the signature with `show {}`", function))
+            }
+            Error::UnknownCommand { suggestions, .. } => match suggestions.first() {
+                Some(suggestion) => diagnostic.with_help(format!("did you mean `{}`?", suggestion)),
+                None => diagnostic.with_help("run `help` to list the commands"),
+            },
+            Error::Syntax(_) => diagnostic,
+            Error::InvalidName(_) => {
+                diagnostic.with_note("names may not be empty, start with `.` or contain slashes")
+            }
+            Error::ArgumentMismatch {
+                function,
+                input_types,
+                position,
+                provided,
+                ..
+            } => {
+                let expected = &input_types[*position];
+                let diagnostic = if diff::is_structural(expected, provided) {
+                    diagnostic.with_diff(expected.clone(), provided.clone())
+                } else {
+                    diagnostic
+                };
+                let diagnostic = diagnostic
+                    .with_note(format!(
+                        "`{}` takes {} argument(s), checked left to right",
+                        function,
+                        input_types.len()
+                    ))
+                    .with_help(format!(
+                        "position {} expects a `{}`, got a `{}`",
+                        position + 1,
+                        expected,
+                        provided
+                    ));
+                if *provided == Type::Int && *expected == Type::Float {
+                    diagnostic.with_help("an `Int` can be widened with `(NAME : Float)`")
+                } else {
+                    diagnostic
+                }
+            }
+            Error::AscriptionMismatch {
+                actual, ascribed, ..
+            } => {
+                let diagnostic = if diff::is_structural(ascribed, actual) {
+                    diagnostic.with_diff(ascribed.clone(), actual.clone())
+                } else {
+                    diagnostic
+                };
+                diagnostic.with_note("an ascription may only widen `Int` to `Float`")
+            }
+            Error::UnknownType { suggestions, .. } => match suggestions.as_slice() {
+                [] => diagnostic.with_help("run `types` to list the known types"),
+                [only] => diagnostic.with_help(format!("did you mean `{}`?", only)),
+                many => diagnostic.with_help(format!(
+                    "did you mean one of {}?",
+                    many.iter()
+                        .map(|name| format!("`{}`", name))
+                        .collect::<Vec<String>>()
+                        .join(", ")
+                )),
+            },
+            Error::Usage { command, .. } => diagnostic.with_help(format!("see `help {}`", command)),
+            Error::UnknownOperator(_) => diagnostic.with_help(
+                "run `ops` to list operators or add one with `declare_op`",
+            ),
+            Error::AmbiguousOperators { left, right, .. } => diagnostic
+                .with_note("operators of equal precedence only chain when they associate the same way")
+                .with_help(format!(
+                    "write `(a {} b) {} c` or `a {} (b {} c)`",
+                    left, right, left, right
+                )),
+            Error::UnknownField {
+                type_name,
+                suggestions,
+                ..
+            } => match suggestions.first() {
+                Some(suggestion) => diagnostic.with_help(format!("did you mean `{}`?", suggestion)),
+                None => diagnostic.with_help(format!("see its fields with `show {}`", type_name)),
+            },
+            Error::NotARecord(_) => diagnostic
+                .with_note("only types declared as `{ name: Type, ... }` have fields"),
+            Error::CompositionMismatch {
+                outer,
+                inner,
+                produced,
+                expected,
+            } => {
+                let diagnostic = if diff::is_structural(expected, produced) {
+                    diagnostic.with_diff(expected.clone(), produced.clone())
+                } else {
+                    diagnostic
+                };
+                diagnostic.with_note(format!(
+                    "`compose {} {}` passes the result of `{}` to the first parameter of `{}`",
+                    outer, inner, inner, outer
+                ))
+            }
+            Error::TypeMismatch { expected, actual } => {
+                if diff::is_structural(expected, actual) {
+                    diagnostic.with_diff(expected.clone(), actual.clone())
+                } else {
+                    diagnostic
+                }
+            }
+            Error::BranchMismatch {
+                then_branch,
+                else_branch,
+            } => {
+                let diagnostic = if diff::is_structural(then_branch, else_branch) {
+                    diagnostic.with_diff(then_branch.clone(), else_branch.clone())
+                } else {
+                    diagnostic
+                };
+                diagnostic.with_note("both branches of an `if` must have the same type")
+            }
+            Error::InfiniteType { .. } => diagnostic
+                .with_note("a type cannot contain itself, as `a = [a]` would require")
+                .with_help("this usually means a function was applied to itself"),
+            Error::CorruptSnapshot {
+                expected, actual, ..
+            } => diagnostic
+                .with_note(format!("recorded checksum {}, contents hash to {}", expected, actual))
+                .with_note("the file was truncated or edited after it was saved, nothing was loaded"),
+            Error::MissingChecksum(_) => diagnostic
+                .with_note("without one, an edited file cannot be told from a saved one")
+                .with_help("load it anyway with `--force`"),
+            Error::UnknownModule { suggestions, .. } => match suggestions.first() {
+                Some(suggestion) => diagnostic.with_help(format!("did you mean `{}`?", suggestion)),
+                None => diagnostic.with_help("list the module's file under `sources` in typecalc.toml"),
+            },
+            Error::UnknownSymbol { suggestions, .. } => match suggestions.first() {
+                Some(suggestion) => diagnostic.with_help(format!("did you mean `{}`?", suggestion)),
+                None => diagnostic,
+            },
+            Error::ImportCycle(_) => diagnostic
+                .with_note("modules are checked after the modules they import, so imports cannot loop")
+                .with_help("move the shared declarations into a module both can import"),
+            Error::InvalidManifest { .. } => diagnostic.with_note(
+                "manifests have `[project]`, `[check]` and `[export]` tables of `key = value` lines",
+            ),
+            Error::PreludeMismatch { .. } => diagnostic
+                .with_note("builtin signatures may differ between prelude versions")
+                .with_help("load it anyway with `session load NAME --force`"),
+            Error::NoInferenceCandidates(_) => diagnostic.with_help(
+                "call the function first, or declare it with `declare_func NAME TYPE... RETURN`",
+            ),
+            Error::InconsistentCalls { function, .. } => diagnostic.with_help(format!(
+                "`declare_func {} RETURN` lists every variant that was used",
+                function
+            )),
+            Error::ConfirmationRequired { .. } => {
+                diagnostic.with_help("repeat the command with `--yes` to go ahead")
+            }
+            Error::NoPendingCandidates => diagnostic.with_note(
+                "`accept` picks from the list shown by the last `declare_func` or `infer_from_calls`",
+            ),
+            Error::NoOpenScope => diagnostic.with_help("open one with `begin`"),
+            Error::NameTaken(name) => {
+                diagnostic.with_help(format!("pick another name, or `delete {}` first", name))
+            }
+            Error::Redeclaration { .. } => diagnostic
+                .with_note("strict mode forbids redeclaring a name in the same scope")
+                .with_help("shadow it inside `begin` ... `end`, or turn strict mode off with `strict off`"),
+            Error::EnvironmentFrozen => {
+                diagnostic.with_note("declarations were locked with `freeze`")
+            }
+            Error::LimitExceeded { .. } => diagnostic,
+            Error::Io { .. } => diagnostic,
+            Error::UnknownTemplate(_) => {
+                let names = BUILTIN_TEMPLATES
+                    .iter()
+                    .map(|(name, _)| *name)
+                    .collect::<Vec<&str>>()
+                    .join(", ");
+                diagnostic.with_help(format!("builtin templates are: {}", names))
+            }
+            Error::AssertionFailed {
+                expected, actual, ..
+            } => {
+                if diff::is_structural(expected, actual) {
+                    diagnostic.with_diff(expected.clone(), actual.clone())
+                } else {
+                    diagnostic
+                }
+            }
+            Error::ExpectedError { .. } => diagnostic
+                .with_note("error codes are the names shown as `code` in `--json` diagnostics"),
+            Error::VerificationFailed { .. } => {
+                diagnostic.with_note("packs are verified against the current environment")
+            }
+            Error::ImportFailed { .. } => diagnostic
+                .with_note("an import is all-or-nothing, fix every listed line and retry")
+                .with_help("each line should look like `name :: Int -> Float`"),
+            Error::ValueMismatch { .. } => diagnostic
+                .with_note("values are checked against the type declared for them"),
+            Error::UnboundTypeVariable { .. } => diagnostic
+                .with_note("lowercase type names are type variables, bound by a function's parameters")
+                .with_help("type names start with an uppercase letter, as in `Int`"),
+            Error::InSubexpression {
+                subexpression,
+                error,
+            } => Diagnostic::from(&**error).with_note(format!(
+                "the smallest part that fails on its own is `{}`",
+                subexpression
+            )),
+        }
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.render(false))
+    }
+}
+
+const GREEN: &str = "\x1b[1;32m";
+const RED: &str = "\x1b[1;31m";
+const RESET: &str = "\x1b[0m";
+
+fn render_segments(label: &str, segments: &[Segment], highlight: &str, color: bool) -> String {
+    let prefix = format!("  {}: ", label);
+    let mut text = String::new();
+    let mut marks = String::new();
+    for segment in segments {
+        let width = segment.text.chars().count();
+        if color && segment.differs {
+            text.push_str(&format!("{}{}{}", highlight, segment.text, RESET));
+        } else {
+            text.push_str(&segment.text);
+        }
+        marks.push_str(&if segment.differs { "^" } else { " " }.repeat(width));
+    }
+    if color {
+        format!("\n{}{}", prefix, text)
+    } else {
+        format!(
+            "\n{}{}\n{}{}",
+            prefix,
+            text,
+            " ".repeat(prefix.len()),
+            marks.trim_end()
+        )
+    }
+}
+
+pub(crate) fn json_string(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+fn json_array(items: &[String]) -> String {
+    let items = items
+        .iter()
+        .map(|item| json_string(item))
+        .collect::<Vec<String>>()
+        .join(",");
+    format!("[{}]", items)
+}
@@ -0,0 +1,115 @@
+use crate::types::type_enum::Type;
+
+/// A piece of a rendered type, flagged when it differs from the type it was
+/// compared against.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Segment {
+    pub text: String,
+    pub differs: bool,
+}
+
+fn push(segments: &mut Vec<Segment>, text: String, differs: bool) {
+    match segments.last_mut() {
+        Some(last) if last.differs == differs => last.text.push_str(&text),
+        _ => segments.push(Segment { text, differs }),
+    }
+}
+
+/// Walks two types side by side and splits each into segments, so only the
+/// sub-components that actually disagree are flagged: `[[Int]]` against
+/// `[[Float]]` flags just `Int` and `Float`.
+pub fn diff(expected: &Type, provided: &Type) -> (Vec<Segment>, Vec<Segment>) {
+    let mut left = Vec::new();
+    let mut right = Vec::new();
+    walk(expected, provided, &mut left, &mut right);
+    (left, right)
+}
+
+fn walk(expected: &Type, provided: &Type, left: &mut Vec<Segment>, right: &mut Vec<Segment>) {
+    match (expected, provided) {
+        (Type::List(a), Type::List(b)) => {
+            push(left, "[".to_string(), false);
+            push(right, "[".to_string(), false);
+            walk(a, b, left, right);
+            push(left, "]".to_string(), false);
+            push(right, "]".to_string(), false);
+        }
+        (Type::Either(a1, a2), Type::Either(b1, b2)) => {
+            push(left, "Either ".to_string(), false);
+            push(right, "Either ".to_string(), false);
+            argument(a1, b1, left, right);
+            push(left, " ".to_string(), false);
+            push(right, " ".to_string(), false);
+            argument(a2, b2, left, right);
+        }
+        (Type::Tuple(a), Type::Tuple(b)) if a.len() == b.len() => {
+            push(left, "(".to_string(), false);
+            push(right, "(".to_string(), false);
+            for (i, (a, b)) in a.iter().zip(b).enumerate() {
+                if i > 0 {
+                    push(left, ", ".to_string(), false);
+                    push(right, ", ".to_string(), false);
+                }
+                walk(a, b, left, right);
+            }
+            push(left, ")".to_string(), false);
+            push(right, ")".to_string(), false);
+        }
+        (Type::Function(a1, a2), Type::Function(b1, b2)) => {
+            parenthesized(a1, b1, |t| matches!(t, Type::Function(..)), left, right);
+            push(left, " -> ".to_string(), false);
+            push(right, " -> ".to_string(), false);
+            walk(a2, b2, left, right);
+        }
+        (a, b) => {
+            push(left, a.to_string(), a != b);
+            push(right, b.to_string(), a != b);
+        }
+    }
+}
+
+/// Diffs a constructor argument, parenthesized the way `Display` does it.
+fn argument(expected: &Type, provided: &Type, left: &mut Vec<Segment>, right: &mut Vec<Segment>) {
+    parenthesized(
+        expected,
+        provided,
+        |t| matches!(t, Type::Either(..) | Type::Function(..)),
+        left,
+        right,
+    );
+}
+
+/// Diffs two types, each wrapped in parentheses when `nested` says so.
+fn parenthesized(
+    expected: &Type,
+    provided: &Type,
+    nested: impl Fn(&Type) -> bool,
+    left: &mut Vec<Segment>,
+    right: &mut Vec<Segment>,
+) {
+    if nested(expected) {
+        push(left, "(".to_string(), false);
+    }
+    if nested(provided) {
+        push(right, "(".to_string(), false);
+    }
+    walk(expected, provided, left, right);
+    if nested(expected) {
+        push(left, ")".to_string(), false);
+    }
+    if nested(provided) {
+        push(right, ")".to_string(), false);
+    }
+}
+
+/// Whether a diff says more than the two whole types would: only when both
+/// sides share some structure.
+pub fn is_structural(expected: &Type, provided: &Type) -> bool {
+    matches!(
+        (expected, provided),
+        (Type::List(_), Type::List(_))
+            | (Type::Either(..), Type::Either(..))
+            | (Type::Tuple(_), Type::Tuple(_))
+            | (Type::Function(..), Type::Function(..))
+    )
+}
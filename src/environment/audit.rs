@@ -0,0 +1,22 @@
+use crate::types::type_enum::Type;
+
+/// A record of how the environment was used, kept so later commands can
+/// learn from it.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum AuditEntry {
+    /// A call to a function that was not declared at the time.
+    UndeclaredCall { function: String, args: Vec<Type> },
+    /// A call that typechecked: the variables passed as `args`, and the
+    /// variable the result was bound to with `bind`, if any.
+    Call {
+        function: String,
+        args: Vec<String>,
+        result: Option<String>,
+    },
+    /// An inferred signature the user accepted with `accept`.
+    AcceptedInference {
+        function: String,
+        input_types: Vec<Type>,
+        output_type: Type,
+    },
+}
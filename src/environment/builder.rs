@@ -0,0 +1,89 @@
+use crate::environment::Environment;
+use crate::prelude::{BuiltinSet, Prelude};
+use crate::types::type_definition::TypeDefinition;
+use crate::types::type_enum::Type;
+use crate::types::type_error::Error;
+
+/// Builds an [`Environment`] from a list of declarations, for embedding the
+/// crate in tests and tools without touching its maps directly, e.g.
+/// `EnvironmentBuilder::new().variable("x", Type::Int).build()`.
+///
+/// Declarations go through the same checks as the matching commands, so
+/// `build` fails where `declare_type` or `alias` would.
+#[derive(Debug, Clone)]
+pub struct EnvironmentBuilder {
+    builtins: BuiltinSet,
+    types: Vec<(String, TypeDefinition)>,
+    aliases: Vec<(String, Type)>,
+    variables: Vec<(String, Type)>,
+    functions: Vec<(String, Vec<Type>, Type)>,
+}
+
+impl Default for EnvironmentBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl EnvironmentBuilder {
+    /// A builder on the standard prelude with nothing declared yet.
+    pub fn new() -> Self {
+        EnvironmentBuilder {
+            builtins: BuiltinSet::core(),
+            types: Vec::new(),
+            aliases: Vec::new(),
+            variables: Vec::new(),
+            functions: Vec::new(),
+        }
+    }
+
+    pub fn prelude(self, prelude: Prelude) -> Self {
+        self.builtins(prelude.builtins())
+    }
+
+    /// Starts from a hand-built set of builtins instead of a named prelude.
+    pub fn builtins(mut self, builtins: BuiltinSet) -> Self {
+        self.builtins = builtins;
+        self
+    }
+
+    pub fn variable(mut self, name: &str, var_type: Type) -> Self {
+        self.variables.push((name.to_string(), var_type));
+        self
+    }
+
+    pub fn function(mut self, name: &str, input_types: &[Type], output_type: Type) -> Self {
+        self.functions
+            .push((name.to_string(), input_types.to_vec(), output_type));
+        self
+    }
+
+    pub fn type_definition(mut self, name: &str, definition: TypeDefinition) -> Self {
+        self.types.push((name.to_string(), definition));
+        self
+    }
+
+    pub fn alias(mut self, name: &str, target: Type) -> Self {
+        self.aliases.push((name.to_string(), target));
+        self
+    }
+
+    /// Declares types first, then aliases, variables and functions, each in
+    /// the order given, so values may refer to any type added.
+    pub fn build(self) -> Result<Environment, Error> {
+        let mut env = Environment::with_builtins(self.builtins);
+        for (name, definition) in self.types {
+            env.declare_type(&name, definition)?;
+        }
+        for (name, target) in self.aliases {
+            env.declare_alias(&name, target)?;
+        }
+        for (name, var_type) in self.variables {
+            env.declare_variable(&name, var_type)?;
+        }
+        for (name, input_types, output_type) in self.functions {
+            env.declare_function(&name, input_types, output_type)?;
+        }
+        Ok(env)
+    }
+}
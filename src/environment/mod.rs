@@ -1,53 +1,777 @@
+use crate::commands::Registry;
+use crate::inference::unify::{self, curried, uncurried};
+use crate::inference::Candidate;
+use crate::limits::Limits;
+use crate::operators::Operator;
+use crate::parser;
+use crate::prelude::{BuiltinSet, BUILTIN_TYPES};
+use crate::pretty;
+use crate::types::instantiate;
+use crate::types::type_definition::TypeDefinition;
 use crate::types::type_enum::Type;
 use crate::types::type_error::Error;
-use std::collections::HashMap;
+use crate::value::Value;
+use std::collections::{BTreeSet, HashMap};
+use std::sync::Arc;
+use std::time::Duration;
 
+mod audit;
+mod builder;
+mod view;
+mod warning;
+
+pub use audit::AuditEntry;
+pub use builder::EnvironmentBuilder;
+pub use view::EnvironmentView;
+pub use warning::Warning;
+
+#[derive(Clone)]
 pub struct Environment {
     pub variables: HashMap<String, Type>,
     pub functions: HashMap<String, (Type, Vec<Type>)>,
+    /// User-declared nominal types, by name.
+    pub types: HashMap<String, TypeDefinition>,
+    /// Type aliases, by name, with the type each expands to.
+    pub aliases: HashMap<String, Type>,
+    /// Name of the variable that holds the result type of the last
+    /// successful call, like GHCi's `it`.
+    pub result_variable: String,
+    /// Whether `process_input` appends each command's execution time.
+    pub timing: bool,
+    /// List nesting past which printed types are elided, `None` for never.
+    pub display_depth: Option<usize>,
+    /// Column past which long declarations are wrapped across lines,
+    /// `None` for never.
+    pub display_width: Option<usize>,
+    /// Whether printed types write parts matching an alias as the alias,
+    /// since aliases are expanded when a type is parsed.
+    pub fold_aliases: bool,
+    /// Cumulative `(runs, time)` per command kind, for `profile`.
+    pub profile: HashMap<String, (u32, Duration)>,
+    pub commands: Registry,
+    /// Infix operators, builtin and user-declared.
+    pub operators: Vec<Operator>,
+    pub limits: Limits,
+    pub audit: Vec<AuditEntry>,
+    /// Whether calls to undeclared functions are recorded as obligations
+    /// rather than rejected.
+    pub deferred: bool,
+    /// Signatures offered by the last inferring `declare_func`, waiting for
+    /// `accept`.
+    pub pending_candidates: Option<(String, Vec<Candidate>)>,
+    /// Tags attached to declarations with `tag`, by declaration name.
+    pub tags: HashMap<String, BTreeSet<String>>,
+    /// Values given to variables with `declare_var x T = value`, by name.
+    /// Variables declared with a type alone have none.
+    pub values: HashMap<String, Value>,
+    /// Whether declaring a name already declared in the same scope is an
+    /// error rather than an overwrite.
+    pub strict_redeclaration: bool,
+    /// Localized words and the keyword or type name each stands for, see
+    /// `locale::translate`.
+    pub keywords: HashMap<String, String>,
+    /// Warnings raised since they were last taken.
+    pub warnings: Vec<Warning>,
+    /// Whether destructive commands ask before discarding declarations,
+    /// which only makes sense when someone is there to answer.
+    pub confirm_destructive: bool,
+    frozen: bool,
+    /// What the environment started with, for `reset`.
+    builtins: BuiltinSet,
+    /// The declarations as they were when each open scope began, innermost
+    /// last.
+    scopes: Vec<Scope>,
+    /// Shared declarations this environment layers its own on top of.
+    base: Option<Arc<Environment>>,
+}
+
+/// Everything a scope can declare, saved by `push_scope` and put back by
+/// `pop_scope`.
+#[derive(Clone)]
+struct Scope {
+    variables: HashMap<String, Type>,
+    functions: HashMap<String, (Type, Vec<Type>)>,
+    types: HashMap<String, TypeDefinition>,
+    aliases: HashMap<String, Type>,
+    operators: Vec<Operator>,
+    tags: HashMap<String, BTreeSet<String>>,
+    values: HashMap<String, Value>,
+}
+
+impl Default for Environment {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Environment {
+    /// An environment with the `core` prelude.
     pub fn new() -> Self {
-        let mut functions = HashMap::new();
-        functions.insert("add".to_string(), (Type::Int, vec![Type::Int]));
-        functions.insert("sub".to_string(), (Type::Int, vec![Type::Int]));
-        functions.insert("mul".to_string(), (Type::Int, vec![Type::Int]));
-        functions.insert("div".to_string(), (Type::Float, vec![Type::Int]));
+        Self::with_builtins(BuiltinSet::core())
+    }
 
-        // Now and Bool function is added
-        functions.insert("and".to_string(), (Type::Bool, vec![Type::Bool]));
+    /// Starts an [`EnvironmentBuilder`] on the standard prelude.
+    pub fn builder() -> EnvironmentBuilder {
+        EnvironmentBuilder::new()
+    }
+
+    /// An environment with no builtin functions or operators at all.
+    pub fn empty() -> Self {
+        Self::with_builtins(BuiltinSet::default())
+    }
+
+    /// An environment starting from `builtins` instead of the `core`
+    /// prelude. `reset` goes back to these.
+    pub fn with_builtins(builtins: BuiltinSet) -> Self {
+        let functions = builtins
+            .functions
+            .iter()
+            .map(|(name, input_types, output_type)| {
+                (name.clone(), (output_type.clone(), input_types.clone()))
+            })
+            .collect();
 
         Environment {
             variables: HashMap::new(),
             functions,
+            types: HashMap::new(),
+            aliases: HashMap::new(),
+            result_variable: "it".to_string(),
+            timing: false,
+            display_depth: Some(4),
+            display_width: Some(pretty::DEFAULT_WIDTH),
+            fold_aliases: false,
+            profile: HashMap::new(),
+            commands: crate::builtin_commands(),
+            operators: builtins.operators.clone(),
+            limits: Limits::new(),
+            audit: Vec::new(),
+            deferred: false,
+            pending_candidates: None,
+            tags: HashMap::new(),
+            values: HashMap::new(),
+            strict_redeclaration: false,
+            keywords: HashMap::new(),
+            warnings: Vec::new(),
+            confirm_destructive: false,
+            frozen: false,
+            builtins,
+            scopes: Vec::new(),
+            base: None,
+        }
+    }
+
+    /// An empty scratch environment layered over a shared `base`. Lookups
+    /// fall through to the base, declarations only ever land in the overlay,
+    /// so any number of overlays can share one base without seeing each
+    /// other's changes.
+    pub fn overlay(base: Arc<Environment>) -> Self {
+        Environment {
+            variables: HashMap::new(),
+            functions: HashMap::new(),
+            types: HashMap::new(),
+            aliases: HashMap::new(),
+            result_variable: base.result_variable.clone(),
+            timing: false,
+            display_depth: base.display_depth,
+            display_width: base.display_width,
+            fold_aliases: base.fold_aliases,
+            profile: HashMap::new(),
+            commands: base.commands.clone(),
+            operators: base.operators.clone(),
+            limits: base.limits.clone(),
+            audit: Vec::new(),
+            deferred: false,
+            pending_candidates: None,
+            tags: HashMap::new(),
+            values: HashMap::new(),
+            strict_redeclaration: false,
+            keywords: base.keywords.clone(),
+            warnings: Vec::new(),
+            confirm_destructive: false,
+            frozen: false,
+            // The base's functions stay visible through it, but its
+            // operators are copied, so a reset must restore them.
+            builtins: BuiltinSet {
+                functions: Vec::new(),
+                operators: base.operators.clone(),
+            },
+            scopes: Vec::new(),
+            base: Some(base),
         }
     }
 
-    pub fn declare_variable(&mut self, name: &str, var_type: Type) {
+    pub fn operator(&self, symbol: &str) -> Option<&Operator> {
+        self.operators.iter().find(|op| op.symbol == symbol)
+    }
+
+    /// Adds an infix operator, replacing any operator with the same symbol.
+    pub fn declare_operator(&mut self, operator: Operator) -> Result<(), Error> {
+        self.ensure_mutable()?;
+        self.operators.retain(|op| op.symbol != operator.symbol);
+        self.operators.push(operator);
+        Ok(())
+    }
+
+    pub fn base(&self) -> Option<&Arc<Environment>> {
+        self.base.as_ref()
+    }
+
+    /// Looks a variable up here, then in the base.
+    pub fn variable(&self, name: &str) -> Option<Type> {
+        self.variables
+            .get(name)
+            .cloned()
+            .or_else(|| self.base.as_ref().and_then(|base| base.variable(name)))
+    }
+
+    /// Looks a function's `(output type, input types)` up here, then in the
+    /// base.
+    pub fn function(&self, name: &str) -> Option<&(Type, Vec<Type>)> {
+        self.functions
+            .get(name)
+            .or_else(|| self.base.as_ref().and_then(|base| base.function(name)))
+    }
+
+    pub fn declare_variable(&mut self, name: &str, var_type: Type) -> Result<(), Error> {
+        self.ensure_mutable()?;
+        // The result variable is rebound by every call, so only other
+        // names are worth a warning, and it may hold a type that is still
+        // open, such as the type inferred for `\x -> x`.
+        if name != self.result_variable {
+            check_type_variables(name, &var_type)?;
+            if let Some(previous) = self.variable(name) {
+                let shadows = self
+                    .scopes
+                    .last()
+                    .is_some_and(|scope| scope.variables.get(name) == self.variables.get(name));
+                self.check_redeclaration(name, previous, &var_type, shadows)?;
+            }
+        }
         self.variables.insert(name.to_string(), var_type);
+        self.values.remove(name);
+        Ok(())
+    }
+
+    /// Declares a variable holding `value`, which must fit `var_type`. An
+    /// `Int` given for a `Float` is stored widened.
+    pub fn define_variable(
+        &mut self,
+        name: &str,
+        var_type: Type,
+        value: Value,
+    ) -> Result<(), Error> {
+        value.check(&var_type, self)?;
+        let value = match (value, &var_type) {
+            (Value::Int(n), Type::Float) => Value::Float(n as f64),
+            (value, _) => value,
+        };
+        self.declare_variable(name, var_type)?;
+        self.values.insert(name.to_string(), value);
+        Ok(())
     }
 
+    /// Looks a variable's value up here, then in the base.
+    pub fn value(&self, name: &str) -> Option<&Value> {
+        if self.variables.contains_key(name) {
+            return self.values.get(name);
+        }
+        self.base.as_ref().and_then(|base| base.value(name))
+    }
+
+    /// Removes a variable declared here, returning its type. Variables of a
+    /// base stay visible, since the base is shared.
+    pub fn remove_variable(&mut self, name: &str) -> Result<Type, Error> {
+        self.ensure_mutable()?;
+        let removed = self
+            .variables
+            .remove(name)
+            .ok_or_else(|| Error::UndeclaredVariable(name.to_string()))?;
+        self.values.remove(name);
+        if self.function(name).is_none() {
+            self.tags.remove(name);
+        }
+        Ok(removed)
+    }
+
+    /// Removes a function declared here, returning its `(output type, input
+    /// types)`.
+    pub fn remove_function(&mut self, name: &str) -> Result<(Type, Vec<Type>), Error> {
+        self.ensure_mutable()?;
+        let removed = self
+            .functions
+            .remove(name)
+            .ok_or_else(|| Error::UndeclaredFunction(name.to_string()))?;
+        if self.variable(name).is_none() {
+            self.tags.remove(name);
+        }
+        Ok(removed)
+    }
+
+    /// Moves the variable and function declarations of `old` here to `new`,
+    /// tags included, all or nothing. Fails if `new` is already declared or
+    /// is not a lowercase name: capitalized names stand for types, and
+    /// keywords cannot name anything.
+    pub fn rename(&mut self, old: &str, new: &str) -> Result<(), Error> {
+        self.ensure_mutable()?;
+        let lowercase = new.starts_with(|c: char| c.is_lowercase() || c == '_')
+            && new.chars().all(|c| c.is_alphanumeric() || c == '_');
+        if !lowercase || parser::KEYWORDS.contains(&new) {
+            return Err(Error::Syntax(format!(
+                "variables and functions need a lowercase name that is not a keyword, got `{}`",
+                new
+            )));
+        }
+        if self.is_declared(new) {
+            return Err(Error::NameTaken(new.to_string()));
+        }
+        if !self.variables.contains_key(old) && !self.functions.contains_key(old) {
+            return Err(Error::UnknownSymbol {
+                name: old.to_string(),
+                suggestions: Vec::new(),
+            });
+        }
+        if let Some(var_type) = self.variables.remove(old) {
+            self.variables.insert(new.to_string(), var_type);
+        }
+        if let Some(value) = self.values.remove(old) {
+            self.values.insert(new.to_string(), value);
+        }
+        if let Some(signature) = self.functions.remove(old) {
+            self.functions.insert(new.to_string(), signature);
+        }
+        if let Some(tags) = self.tags.remove(old) {
+            self.tags.insert(new.to_string(), tags);
+        }
+        Ok(())
+    }
+
+    /// Wipes the declarations made here back to the builtin functions and
+    /// operators it started with, forgetting the recorded calls and open
+    /// scopes with them. Settings such as the result variable and limits
+    /// are kept.
+    pub fn reset(&mut self) -> Result<(), Error> {
+        self.ensure_mutable()?;
+        let fresh = Environment::with_builtins(self.builtins.clone());
+        self.variables = fresh.variables;
+        self.functions = fresh.functions;
+        self.types = fresh.types;
+        self.aliases = fresh.aliases;
+        self.operators = fresh.operators;
+        self.tags.clear();
+        self.values.clear();
+        self.audit.clear();
+        self.pending_candidates = None;
+        self.scopes.clear();
+        Ok(())
+    }
+
+    /// Like `reset`, but drops the builtin functions and operators too.
+    pub fn clear_all(&mut self) -> Result<(), Error> {
+        self.reset()?;
+        self.functions.clear();
+        self.operators.clear();
+        Ok(())
+    }
+
+    /// Rejects a redeclaration in strict mode, unless it `shadows` a name
+    /// from an outer scope, and otherwise warns when the type changed.
+    fn check_redeclaration(
+        &mut self,
+        name: &str,
+        previous: Type,
+        current: &Type,
+        shadows: bool,
+    ) -> Result<(), Error> {
+        if self.strict_redeclaration && !shadows {
+            return Err(Error::Redeclaration {
+                name: name.to_string(),
+                previous,
+            });
+        }
+        if previous != *current {
+            self.warnings.push(Warning::Redeclared {
+                name: name.to_string(),
+                previous,
+                current: current.clone(),
+            });
+        }
+        Ok(())
+    }
+
+    /// Removes and returns the warnings raised so far.
+    pub fn take_warnings(&mut self) -> Vec<Warning> {
+        std::mem::take(&mut self.warnings)
+    }
+
+    /// Checks a call and returns its result type. Type variables in the
+    /// signature are instantiated from the arguments left to right, so
+    /// `id :: a -> a` applied to an `Int` returns an `Int`.
     pub fn call_function(&self, name: &str, args: &[Type]) -> Result<Type, Error> {
-        if let Some((return_type, input_types)) = self.functions.get(name) {
+        if let Some((return_type, input_types)) = self.function(name) {
             if input_types.len() != args.len() {
-                return Err(Error::TypeError);
+                return Err(Error::ArityMismatch {
+                    function: name.to_string(),
+                    expected: input_types.len(),
+                    got: args.len(),
+                });
             }
 
-            for (i, arg) in args.iter().enumerate() {
-                if *arg != input_types[i] {
-                    return Err(Error::TypeError);
-                }
+            let bindings = match_arguments(name, input_types, return_type, args)?;
+            Ok(instantiate::substitute(return_type, &bindings))
+        } else {
+            Err(Error::UndeclaredFunction(name.to_string()))
+        }
+    }
+
+    /// Like `call_function`, but with curried semantics: given fewer
+    /// arguments than parameters it returns the function still waiting for
+    /// the rest, so applying `f :: Int -> Char -> Bool` to an `Int` gives
+    /// `Char -> Bool`. Variables holding a function can be applied too.
+    pub fn apply_function(&self, name: &str, args: &[Type]) -> Result<Type, Error> {
+        let (return_type, input_types) = self
+            .callable(name)
+            .ok_or_else(|| Error::UndeclaredFunction(name.to_string()))?;
+        if args.len() > input_types.len() {
+            return Err(Error::ArityMismatch {
+                function: name.to_string(),
+                expected: input_types.len(),
+                got: args.len(),
+            });
+        }
+
+        let bindings = match_arguments(name, &input_types, &return_type, args)?;
+        let remaining: Vec<Type> = input_types[args.len()..]
+            .iter()
+            .map(|t| instantiate::substitute(t, &bindings))
+            .collect();
+        Ok(curried(
+            &remaining,
+            &instantiate::substitute(&return_type, &bindings),
+        ))
+    }
+
+    /// The `(output type, input types)` of a declared function, or of a
+    /// variable holding one.
+    pub fn callable(&self, name: &str) -> Option<(Type, Vec<Type>)> {
+        match (self.function(name), self.variable(name)) {
+            (Some(signature), _) => Some(signature.clone()),
+            (None, Some(function @ Type::Function(..))) => Some(uncurried(&function)),
+            _ => None,
+        }
+    }
+
+    pub fn view(&self) -> EnvironmentView<'_> {
+        EnvironmentView::new(self)
+    }
+
+    /// Looks a user-declared type up here, then in the base.
+    pub fn type_definition(&self, name: &str) -> Option<&TypeDefinition> {
+        self.types.get(name).or_else(|| {
+            self.base
+                .as_ref()
+                .and_then(|base| base.type_definition(name))
+        })
+    }
+
+    /// Looks an alias up here, then in the base.
+    pub fn alias(&self, name: &str) -> Option<&Type> {
+        self.aliases
+            .get(name)
+            .or_else(|| self.base.as_ref().and_then(|base| base.alias(name)))
+    }
+
+    /// Declares `name` as another name for `target`. Unlike a declared type
+    /// it is not a new type: it expands wherever it is used.
+    pub fn declare_alias(&mut self, name: &str, target: Type) -> Result<(), Error> {
+        self.ensure_mutable()?;
+        if self.type_names().contains(&name) && self.alias(name).is_none() {
+            return Err(Error::Syntax(format!(
+                "`{}` is already a type and cannot become an alias",
+                name
+            )));
+        }
+        self.aliases.insert(name.to_string(), target);
+        Ok(())
+    }
+
+    /// Registers a nominal type and declares its constructors: those without
+    /// fields as variables of the type, the others as functions returning it.
+    pub fn declare_type(&mut self, name: &str, definition: TypeDefinition) -> Result<(), Error> {
+        self.ensure_mutable()?;
+        if BUILTIN_TYPES.iter().any(|(builtin, _)| *builtin == name) || name == "Either" {
+            return Err(Error::Syntax(format!(
+                "`{}` is a builtin type and cannot be redeclared",
+                name
+            )));
+        }
+        if self.alias(name).is_some() {
+            return Err(Error::Syntax(format!(
+                "`{}` is already an alias and cannot become a type",
+                name
+            )));
+        }
+        // Constructors are declared like any variable or function, so
+        // strict mode and redeclaration warnings apply to them, all or
+        // nothing. Those of an earlier definition of the type go first.
+        let saved = (self.variables.clone(), self.functions.clone());
+        let warnings = self.warnings.len();
+        let previous = self.types.get(name).map(|previous| {
+            previous
+                .constructors()
+                .into_iter()
+                .map(|(constructor, _)| constructor.to_string())
+                .collect::<Vec<String>>()
+        });
+        for constructor in previous.iter().flatten() {
+            self.variables.remove(constructor);
+            self.functions.remove(constructor);
+        }
+        let named = Type::Named(name.to_string());
+        for (constructor, fields) in definition.constructors() {
+            let declared = if fields.is_empty() {
+                self.declare_variable(constructor, named.clone())
+            } else {
+                self.declare_function(constructor, fields.to_vec(), named.clone())
+            };
+            if let Err(err) = declared {
+                (self.variables, self.functions) = saved;
+                self.warnings.truncate(warnings);
+                return Err(err);
+            }
+        }
+        for constructor in previous.iter().flatten() {
+            if !self.variables.contains_key(constructor) {
+                self.values.remove(constructor);
+            }
+            if !self.variables.contains_key(constructor)
+                && !self.functions.contains_key(constructor)
+            {
+                self.tags.remove(constructor);
             }
+        }
+        self.types.insert(name.to_string(), definition);
+        Ok(())
+    }
 
-            Ok(return_type.clone())
-        } else {
-            Err(Error::UndeclaredFunction)
+    /// Whether `name` is declared as a variable, function, type or alias.
+    pub fn is_declared(&self, name: &str) -> bool {
+        self.variable(name).is_some()
+            || self.function(name).is_some()
+            || self.type_definition(name).is_some()
+            || self.alias(name).is_some()
+    }
+
+    /// The tags of `name`, here or in the base.
+    pub fn tags_of(&self, name: &str) -> BTreeSet<String> {
+        let mut tags = self
+            .base
+            .as_ref()
+            .map_or_else(BTreeSet::new, |base| base.tags_of(name));
+        tags.extend(self.tags.get(name).into_iter().flatten().cloned());
+        tags
+    }
+
+    /// A copy keeping only the variables and functions tagged `tag`, plus
+    /// the builtins and every type and alias so signatures still make sense.
+    pub fn tagged(&self, tag: &str) -> Environment {
+        let mut tagged = self.clone();
+        let keep = |name: &str| self.tags_of(name).contains(tag) || self.is_constructor(name);
+        tagged.variables.retain(|name, _| keep(name));
+        tagged.functions.retain(|name, (output_type, input_types)| {
+            keep(name) || self.builtins.contains(name, input_types, output_type)
+        });
+        tagged
+    }
+
+    /// Whether anything beyond the builtins has been declared, here or in
+    /// the base.
+    pub fn has_declarations(&self) -> bool {
+        let redeclared = self
+            .functions
+            .iter()
+            .any(|(name, (output_type, input_types))| {
+                !self.builtins.contains(name, input_types, output_type)
+            });
+        redeclared
+            || !self.variables.is_empty()
+            || !self.types.is_empty()
+            || !self.aliases.is_empty()
+            || self
+                .base
+                .as_ref()
+                .is_some_and(|base| base.has_declarations())
+    }
+
+    /// Whether `name` is a constructor of a user-declared type.
+    pub fn is_constructor(&self, name: &str) -> bool {
+        self.type_names().into_iter().any(|type_name| {
+            self.type_definition(type_name).is_some_and(|definition| {
+                definition
+                    .constructors()
+                    .into_iter()
+                    .any(|(constructor, _)| constructor == name)
+            })
+        })
+    }
+
+    /// Every type name that can appear in a declaration, builtins first,
+    /// then user types and aliases.
+    pub fn type_names(&self) -> Vec<&str> {
+        let mut user: Vec<&str> = self
+            .types
+            .keys()
+            .chain(self.aliases.keys())
+            .map(String::as_str)
+            .collect();
+        if let Some(base) = &self.base {
+            user.extend(base.type_names().into_iter().filter(|name| {
+                !self.types.contains_key(*name) && !self.aliases.contains_key(*name)
+            }));
         }
+        user.retain(|name| !BUILTIN_TYPES.iter().any(|(builtin, _)| builtin == name));
+        user.sort();
+        BUILTIN_TYPES
+            .iter()
+            .map(|(name, _)| *name)
+            .chain(user)
+            .collect()
+    }
+
+    /// Coercion policy for ascriptions: a type always fits itself, and an
+    /// `Int` may be widened to a `Float`.
+    pub fn can_coerce(&self, from: &Type, to: &Type) -> bool {
+        from == to || (*from == Type::Int && *to == Type::Float)
     }
 
-    pub fn declare_function(&mut self, name: &str, input_type: Type, output_type: Type) {
+    pub fn declare_function(
+        &mut self,
+        name: &str,
+        input_types: Vec<Type>,
+        output_type: Type,
+    ) -> Result<(), Error> {
+        self.ensure_mutable()?;
+        check_type_variables(name, &curried(&input_types, &output_type))?;
+        if let Some((previous_output, previous_inputs)) = self.function(name) {
+            let previous = curried(previous_inputs, previous_output);
+            let shadows = self
+                .scopes
+                .last()
+                .is_some_and(|scope| scope.functions.get(name) == self.functions.get(name));
+            self.check_redeclaration(
+                name,
+                previous,
+                &curried(&input_types, &output_type),
+                shadows,
+            )?;
+        }
         self.functions
-            .insert(name.to_string(), (output_type, vec![input_type]));
+            .insert(name.to_string(), (output_type, input_types));
+        Ok(())
+    }
+
+    /// Opens a nested scope. Declarations made inside it may shadow outer
+    /// ones, and all of them are undone by the matching `pop_scope`.
+    pub fn push_scope(&mut self) {
+        self.scopes.push(Scope {
+            variables: self.variables.clone(),
+            functions: self.functions.clone(),
+            types: self.types.clone(),
+            aliases: self.aliases.clone(),
+            operators: self.operators.clone(),
+            tags: self.tags.clone(),
+            values: self.values.clone(),
+        });
+    }
+
+    /// Closes the innermost scope, restoring the declarations from before
+    /// it began.
+    pub fn pop_scope(&mut self) -> Result<(), Error> {
+        self.ensure_mutable()?;
+        let scope = self.scopes.pop().ok_or(Error::NoOpenScope)?;
+        self.variables = scope.variables;
+        self.functions = scope.functions;
+        self.types = scope.types;
+        self.aliases = scope.aliases;
+        self.operators = scope.operators;
+        self.tags = scope.tags;
+        self.values = scope.values;
+        Ok(())
+    }
+
+    /// How many scopes are open.
+    pub fn scope_depth(&self) -> usize {
+        self.scopes.len()
+    }
+
+    /// Locks the declarations for good: every later declaration fails with
+    /// `Error::EnvironmentFrozen`. Calls still typecheck.
+    pub fn freeze(&mut self) {
+        self.frozen = true;
+    }
+
+    pub fn is_frozen(&self) -> bool {
+        self.frozen
+    }
+
+    /// The builtins the environment started with, which `reset` goes back
+    /// to.
+    pub fn builtins(&self) -> &BuiltinSet {
+        &self.builtins
+    }
+
+    /// Whether `name` with this signature is one of the builtins rather
+    /// than a user declaration.
+    pub fn is_builtin(&self, name: &str, input_types: &[Type], output_type: &Type) -> bool {
+        self.builtins.contains(name, input_types, output_type)
+    }
+
+    pub fn ensure_mutable(&self) -> Result<(), Error> {
+        if self.frozen {
+            Err(Error::EnvironmentFrozen)
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// Matches `args` against the leading parameters of `name`, instantiating
+/// type variables left to right.
+fn match_arguments(
+    name: &str,
+    input_types: &[Type],
+    return_type: &Type,
+    args: &[Type],
+) -> Result<HashMap<String, Type>, Error> {
+    let mut bindings = HashMap::new();
+    for (i, arg) in args.iter().enumerate() {
+        if !instantiate::match_type(&input_types[i], arg, &mut bindings) {
+            // Report the signature as instantiated so far, so a clash with
+            // an earlier argument reads plainly.
+            return Err(Error::ArgumentMismatch {
+                function: name.to_string(),
+                input_types: input_types
+                    .iter()
+                    .map(|t| instantiate::substitute(t, &bindings))
+                    .collect(),
+                output_type: instantiate::substitute(return_type, &bindings),
+                position: i,
+                provided: arg.clone(),
+            });
+        }
+    }
+    Ok(bindings)
+}
+
+/// Rejects a declared type whose result mentions a type variable no
+/// parameter binds.
+fn check_type_variables(name: &str, declared: &Type) -> Result<(), Error> {
+    match unify::unbound_variable(declared) {
+        Some(variable) => Err(Error::UnboundTypeVariable {
+            name: name.to_string(),
+            declared: declared.clone(),
+            variable,
+        }),
+        None => Ok(()),
     }
 }
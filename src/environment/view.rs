@@ -0,0 +1,164 @@
+use crate::environment::Environment;
+use crate::operators::Operator;
+use crate::prelude::BuiltinSet;
+use crate::types::type_definition::TypeDefinition;
+use crate::types::type_enum::Type;
+use crate::types::type_error::Error;
+use crate::value::Value;
+use std::collections::BTreeSet;
+
+/// A read-only handle on an [`Environment`]. It is `Copy`, so it can be
+/// handed to any number of analysis passes, none of which can mutate the
+/// environment behind it.
+#[derive(Clone, Copy)]
+pub struct EnvironmentView<'a> {
+    env: &'a Environment,
+}
+
+impl<'a> EnvironmentView<'a> {
+    pub fn new(env: &'a Environment) -> Self {
+        EnvironmentView { env }
+    }
+
+    pub fn variable(&self, name: &str) -> Option<Type> {
+        self.env.variable(name)
+    }
+
+    /// A function's `(input types, output type)`.
+    pub fn function(&self, name: &str) -> Option<(&'a [Type], Type)> {
+        self.env
+            .function(name)
+            .map(|(output_type, input_types)| (input_types.as_slice(), output_type.clone()))
+    }
+
+    /// The value a variable was declared with, if any.
+    pub fn value(&self, name: &str) -> Option<&'a Value> {
+        self.env.value(name)
+    }
+
+    /// Every visible variable, including those inherited from a base.
+    pub fn variables(&self) -> Vec<(&'a str, Type)> {
+        let mut variables = self.env.base().map_or_else(Vec::new, |base| {
+            EnvironmentView::new(base)
+                .variables()
+                .into_iter()
+                .filter(|(name, _)| !self.env.variables.contains_key(*name))
+                .collect()
+        });
+        variables.extend(
+            self.env
+                .variables
+                .iter()
+                .map(|(name, var_type)| (name.as_str(), var_type.clone())),
+        );
+        variables
+    }
+
+    /// The variables worth writing out: every visible variable except
+    /// constructors, which come with their type, and the result variable,
+    /// which commands rebind as they go. Sorted by name.
+    pub fn user_variables(&self) -> Vec<(&'a str, Type)> {
+        let mut variables = self.variables();
+        variables
+            .retain(|(name, _)| !self.is_constructor(name) && *name != self.env.result_variable);
+        variables.sort_by_key(|(name, _)| *name);
+        variables
+    }
+
+    /// Every visible function, including those inherited from a base.
+    pub fn functions(&self) -> Vec<(&'a str, &'a [Type], Type)> {
+        let mut functions = self.env.base().map_or_else(Vec::new, |base| {
+            EnvironmentView::new(base)
+                .functions()
+                .into_iter()
+                .filter(|(name, _, _)| !self.env.functions.contains_key(*name))
+                .collect()
+        });
+        functions.extend(
+            self.env
+                .functions
+                .iter()
+                .map(|(name, (output_type, input_types))| {
+                    (name.as_str(), input_types.as_slice(), output_type.clone())
+                }),
+        );
+        functions
+    }
+
+    pub fn call_function(&self, name: &str, args: &[Type]) -> Result<Type, Error> {
+        self.env.call_function(name, args)
+    }
+
+    pub fn type_names(&self) -> Vec<&'a str> {
+        self.env.type_names()
+    }
+
+    /// Every user-declared type with its definition, sorted by name.
+    pub fn types(&self) -> Vec<(&'a str, &'a TypeDefinition)> {
+        let env = self.env;
+        env.type_names()
+            .into_iter()
+            .filter_map(|name| {
+                env.type_definition(name)
+                    .map(|definition| (name, definition))
+            })
+            .collect()
+    }
+
+    /// Every alias with the type it expands to, sorted by name.
+    pub fn aliases(&self) -> Vec<(&'a str, &'a Type)> {
+        let env = self.env;
+        env.type_names()
+            .into_iter()
+            .filter_map(|name| env.alias(name).map(|target| (name, target)))
+            .collect()
+    }
+
+    /// The operators declared on top of the builtins, sorted by symbol. A
+    /// builtin redeclared with another signature counts as declared.
+    pub fn user_operators(&self) -> Vec<&'a Operator> {
+        let builtins = &self.env.builtins().operators;
+        let mut operators = self
+            .env
+            .operators
+            .iter()
+            .filter(|operator| !builtins.contains(operator))
+            .collect::<Vec<&Operator>>();
+        operators.sort_by(|a, b| a.symbol.cmp(&b.symbol));
+        operators
+    }
+
+    /// Every declaration that has tags, with its tags, sorted by name.
+    pub fn tags(&self) -> Vec<(&'a str, BTreeSet<String>)> {
+        let mut names = self
+            .variables()
+            .into_iter()
+            .map(|(name, _)| name)
+            .chain(self.functions().into_iter().map(|(name, _, _)| name))
+            .chain(self.type_names())
+            .collect::<Vec<&str>>();
+        names.sort();
+        names.dedup();
+        names
+            .into_iter()
+            .map(|name| (name, self.env.tags_of(name)))
+            .filter(|(_, tags)| !tags.is_empty())
+            .collect()
+    }
+
+    pub fn is_constructor(&self, name: &str) -> bool {
+        self.env.is_constructor(name)
+    }
+
+    pub fn is_frozen(&self) -> bool {
+        self.env.is_frozen()
+    }
+
+    pub fn builtins(&self) -> &'a BuiltinSet {
+        self.env.builtins()
+    }
+
+    pub fn is_builtin(&self, name: &str, input_types: &[Type], output_type: &Type) -> bool {
+        self.env.is_builtin(name, input_types, output_type)
+    }
+}
@@ -0,0 +1,49 @@
+use crate::diagnostic::json_string;
+use crate::types::type_enum::Type;
+use std::fmt;
+
+/// Something worth pointing out about a command that still succeeded.
+/// Warnings queue up on the environment until taken with
+/// `Environment::take_warnings`.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum Warning {
+    /// A variable or function was declared again with a different type,
+    /// replacing or shadowing the old declaration.
+    Redeclared {
+        name: String,
+        previous: Type,
+        current: Type,
+    },
+}
+
+impl fmt::Display for Warning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Warning::Redeclared {
+                name,
+                previous,
+                current,
+            } => write!(
+                f,
+                "`{}` was declared as `{}` and is now `{}`",
+                name, previous, current
+            ),
+        }
+    }
+}
+
+impl Warning {
+    /// The warning as JSON, with the line of the script that raised it
+    /// when there is one, like a diagnostic.
+    pub fn to_json(&self, line: Option<usize>) -> String {
+        let line = match line {
+            Some(line) => line.to_string(),
+            None => "null".to_string(),
+        };
+        format!(
+            "{{\"line\":{},\"warning\":{}}}",
+            line,
+            json_string(&self.to_string())
+        )
+    }
+}
@@ -0,0 +1,343 @@
+use crate::diagnostic::json_string;
+use crate::operators::Operator;
+use crate::prelude;
+use crate::pretty;
+use crate::types::instantiate::is_polymorphic;
+use crate::types::type_definition::TypeDefinition;
+use crate::types::type_enum::Type;
+use crate::{format_signature, AuditEntry, EnvironmentView};
+use std::collections::BTreeMap;
+
+/// Renders the environment as a Markdown design document: user types, user
+/// variables, user functions, then the prelude, each signature in a code
+/// block. Declarations longer than `width` are wrapped across lines.
+pub fn markdown(env: EnvironmentView, width: Option<usize>) -> String {
+    let types = env.types();
+    let has_aliases = !env.aliases().is_empty();
+    let variables = env.user_variables();
+    let mut functions = env.functions();
+    functions.retain(|(name, _, _)| !env.is_constructor(name));
+    functions.sort_by_key(|(name, _, _)| *name);
+    let (builtins, functions): (Vec<_>, Vec<_>) =
+        functions
+            .into_iter()
+            .partition(|(name, input_types, output_type)| {
+                env.is_builtin(name, input_types, output_type)
+            });
+
+    let mut out = String::from("# Type environment\n");
+
+    if !types.is_empty() || has_aliases {
+        out.push_str("\n## Types\n\n```haskell\n");
+        for (name, definition) in &types {
+            match definition {
+                TypeDefinition::Newtype { wrapped, .. } => {
+                    out.push_str(&format!("newtype {} = {} {}\n", name, name, wrapped))
+                }
+                _ => out.push_str(&format!(
+                    "{}\n",
+                    pretty::type_declaration("data", name, definition, width)
+                )),
+            }
+        }
+        for (name, target) in env.aliases() {
+            out.push_str(&format!("type {} = {}\n", name, target));
+        }
+        out.push_str("```\n");
+    }
+
+    if !variables.is_empty() {
+        out.push_str("\n## Variables\n\n```haskell\n");
+        for (name, var_type) in &variables {
+            out.push_str(&format!("{} :: {}\n", name, var_type));
+        }
+        out.push_str("```\n");
+    }
+
+    if !functions.is_empty() {
+        out.push_str("\n## Functions\n\n```haskell\n");
+        for (name, input_types, output_type) in &functions {
+            out.push_str(&format!(
+                "{}\n",
+                pretty::signature(
+                    name,
+                    &pretty::signature_parts(input_types, output_type, None),
+                    width
+                )
+            ));
+        }
+        out.push_str("```\n");
+    }
+
+    if !builtins.is_empty() {
+        out.push_str(&format!(
+            "\n## Prelude `{}` {}\n",
+            env.builtins().name(),
+            prelude::PRELUDE_VERSION
+        ));
+        for (name, input_types, output_type) in &builtins {
+            out.push_str(&format!(
+                "\n### `{}`\n\n```haskell\n{} :: {}\n```\n",
+                name,
+                name,
+                format_signature(input_types, output_type)
+            ));
+            let core = prelude::is_builtin(name, input_types, output_type);
+            if let Some(builtin) = prelude::builtin(name).filter(|_| core) {
+                out.push_str(&format!("\n{}\n", capitalize(builtin.note)));
+            }
+        }
+    }
+
+    out
+}
+
+fn capitalize(text: &str) -> String {
+    let mut chars = text.chars();
+    match chars.next() {
+        Some(first) => format!("{}{}.", first.to_uppercase(), chars.as_str()),
+        None => String::new(),
+    }
+}
+
+/// Renders the calls that typechecked this session as a Graphviz digraph:
+/// an edge from each variable passed to a function, and from a function to
+/// the variable `bind` stored its result in. Repeated edges are counted.
+pub fn callgraph_dot(env: EnvironmentView, audit: &[AuditEntry]) -> String {
+    let mut edges: Vec<((&str, &str), usize)> = Vec::new();
+    let mut add = |from, to| match edges.iter_mut().find(|(edge, _)| *edge == (from, to)) {
+        Some((_, count)) => *count += 1,
+        None => edges.push(((from, to), 1)),
+    };
+    for entry in audit {
+        if let AuditEntry::Call {
+            function,
+            args,
+            result,
+        } = entry
+        {
+            for arg in args {
+                add(arg.as_str(), function.as_str());
+            }
+            if let Some(result) = result {
+                add(function.as_str(), result.as_str());
+            }
+        }
+    }
+
+    let mut nodes: Vec<&str> = edges.iter().flat_map(|((a, b), _)| [*a, *b]).collect();
+    nodes.sort();
+    nodes.dedup();
+
+    let mut out = String::from("digraph calls {\n    rankdir=LR;\n");
+    for node in nodes {
+        let shape = if env.function(node).is_some() {
+            "box"
+        } else {
+            "ellipse"
+        };
+        out.push_str(&format!("    \"{}\" [shape={}];\n", node, shape));
+    }
+    for ((from, to), count) in edges {
+        if count > 1 {
+            out.push_str(&format!(
+                "    \"{}\" -> \"{}\" [label=\"{}\"];\n",
+                from, to, count
+            ));
+        } else {
+            out.push_str(&format!("    \"{}\" -> \"{}\";\n", from, to));
+        }
+    }
+    out.push('}');
+    out
+}
+
+/// Renders statistics about the user declarations as one JSON object, for
+/// charting API complexity over time: how many values have each type, how
+/// many functions take each number of arguments, how many are generic, and
+/// how big each namespace is. Builtins and constructors are left out.
+pub fn stats_json(env: EnvironmentView, operators: &[Operator]) -> String {
+    let variables = env.user_variables();
+    let mut functions = env.functions();
+    functions.retain(|(name, input_types, output_type)| {
+        !env.is_constructor(name) && !env.is_builtin(name, input_types, output_type)
+    });
+
+    let mut by_type: BTreeMap<String, usize> = BTreeMap::new();
+    for (_, var_type) in &variables {
+        *by_type.entry(var_type.to_string()).or_default() += 1;
+    }
+    for (_, _, output_type) in &functions {
+        *by_type.entry(output_type.to_string()).or_default() += 1;
+    }
+    let mut arities: BTreeMap<usize, usize> = BTreeMap::new();
+    for (_, input_types, _) in &functions {
+        *arities.entry(input_types.len()).or_default() += 1;
+    }
+    let generic = functions
+        .iter()
+        .filter(|(_, input_types, output_type)| {
+            input_types.iter().any(is_polymorphic) || is_polymorphic(output_type)
+        })
+        .count();
+    let ratio = match functions.len() {
+        0 => 0.0,
+        total => generic as f64 / total as f64,
+    };
+    let user_operators = operators
+        .iter()
+        .filter(|op| {
+            !env.builtins()
+                .operators
+                .iter()
+                .any(|builtin| builtin == *op)
+        })
+        .count();
+
+    let by_type = by_type
+        .iter()
+        .map(|(t, count)| format!("{}:{}", json_string(t), count))
+        .collect::<Vec<String>>();
+    let arities = arities
+        .iter()
+        .map(|(arity, count)| format!("\"{}\":{}", arity, count))
+        .collect::<Vec<String>>();
+    format!(
+        "{{\"by_type\":{{{}}},\"arity\":{{{}}},\"generic\":{},\"monomorphic\":{},\"generic_ratio\":{:.3},\"namespaces\":{{\"variables\":{},\"functions\":{},\"types\":{},\"aliases\":{},\"operators\":{}}}}}",
+        by_type.join(","),
+        arities.join(","),
+        generic,
+        functions.len() - generic,
+        ratio,
+        variables.len(),
+        functions.len(),
+        env.types().len(),
+        env.aliases().len(),
+        user_operators
+    )
+}
+
+/// Renders the environment as a script of commands that rebuilds it when
+/// replayed. Types come first, each after every type it refers to; types
+/// that refer to each other in a cycle share one `begin decls` block. User
+/// variables and functions follow, sorted by name, then their tags.
+pub fn script(env: EnvironmentView) -> String {
+    let mut out = String::new();
+    for group in type_order(&env.types()) {
+        match group.as_slice() {
+            [(name, definition)] => {
+                let command = match definition {
+                    TypeDefinition::Newtype { .. } => "newtype",
+                    _ => "declare_type",
+                };
+                out.push_str(&format!("{} {} = {}\n", command, name, definition));
+            }
+            cycle => {
+                out.push_str("begin decls\n");
+                for (name, definition) in cycle {
+                    out.push_str(&format!(
+                        "{} {} = {}\n",
+                        definition.keyword(),
+                        name,
+                        definition
+                    ));
+                }
+                out.push_str("end\n");
+            }
+        }
+    }
+
+    for (name, target) in env.aliases() {
+        out.push_str(&format!("alias {} = {}\n", name, target));
+    }
+
+    let variables = env.user_variables();
+    for (name, var_type) in variables {
+        match env.value(name) {
+            Some(value) => {
+                out.push_str(&format!("declare_var {} {} = {}\n", name, var_type, value))
+            }
+            None => out.push_str(&format!("declare_var {} {}\n", name, var_type)),
+        }
+    }
+
+    let mut functions = env.functions();
+    functions.retain(|(name, input_types, output_type)| {
+        !env.is_constructor(name) && !env.is_builtin(name, input_types, output_type)
+    });
+    functions.sort_by_key(|(name, _, _)| *name);
+    for (name, input_types, output_type) in functions {
+        let types = input_types
+            .iter()
+            .chain(std::iter::once(&output_type))
+            .map(|t| t.to_string())
+            .collect::<Vec<String>>();
+        out.push_str(&format!("declare_func {} {}\n", name, types.join(" ")));
+    }
+
+    for (name, tags) in env.tags() {
+        let tags = tags.into_iter().collect::<Vec<String>>();
+        out.push_str(&format!("tag {} {}\n", name, tags.join(",")));
+    }
+    out
+}
+
+/// Sorts types so each comes after the types it refers to (Kahn's
+/// algorithm, alphabetical among ready types). Whatever is left over lies on
+/// a cycle and is returned as one final group.
+fn type_order<'a>(
+    types: &[(&'a str, &'a TypeDefinition)],
+) -> Vec<Vec<(&'a str, &'a TypeDefinition)>> {
+    let dependencies = |definition: &TypeDefinition, own: &str| {
+        let mut names = Vec::new();
+        let fields: Vec<&Type> = match definition {
+            TypeDefinition::Sum(constructors) => {
+                constructors.iter().flat_map(|(_, fields)| fields).collect()
+            }
+            TypeDefinition::Record(fields) => fields.iter().map(|(_, t)| t).collect(),
+            TypeDefinition::Newtype { wrapped, .. } => vec![wrapped],
+        };
+        for field in fields {
+            named_types(field, &mut names);
+        }
+        names.retain(|name| name != own && types.iter().any(|(other, _)| other == name));
+        names
+    };
+
+    let mut remaining: Vec<(&str, &TypeDefinition)> = types.to_vec();
+    remaining.sort_by_key(|(name, _)| *name);
+    let mut groups = Vec::new();
+    while !remaining.is_empty() {
+        let ready = remaining.iter().position(|(name, definition)| {
+            dependencies(definition, name)
+                .iter()
+                .all(|dependency| !remaining.iter().any(|(other, _)| other == dependency))
+        });
+        match ready {
+            Some(index) => groups.push(vec![remaining.remove(index)]),
+            None => {
+                groups.push(remaining);
+                break;
+            }
+        }
+    }
+    groups
+}
+
+/// Collects the nominal types `t` mentions.
+pub(crate) fn named_types(t: &Type, names: &mut Vec<String>) {
+    match t {
+        Type::Named(name) => names.push(name.clone()),
+        Type::List(element) => named_types(element, names),
+        Type::Either(left, right) | Type::Function(left, right) => {
+            named_types(left, names);
+            named_types(right, names);
+        }
+        Type::Tuple(elements) => {
+            for element in elements {
+                named_types(element, names);
+            }
+        }
+        _ => {}
+    }
+}
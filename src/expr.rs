@@ -0,0 +1,29 @@
+use crate::inference::unify;
+use crate::operators::{Associativity, Operator};
+use crate::parser;
+use crate::types::type_enum::Type;
+use crate::types::type_error::Error;
+use crate::Environment;
+
+const SYMBOL_CHARS: &str = "!#$%&*+./<=>?@\\^|-~:";
+
+/// Whether `symbol` can name an infix operator, e.g. `+` or `<+>`.
+pub fn is_operator_symbol(symbol: &str) -> bool {
+    !symbol.is_empty() && symbol.chars().all(|c| SYMBOL_CHARS.contains(c))
+}
+
+/// Two adjacent operators of equal precedence can only be grouped when they
+/// associate the same way, and that way is not `none`: `a == b < c` and
+/// mixing `infixl 6` with `infixr 6` are both ambiguous.
+pub(crate) fn conflicts(left: &Operator, right: &Operator) -> bool {
+    left.precedence == right.precedence
+        && (left.associativity != right.associativity || right.associativity == Associativity::None)
+}
+
+/// Typechecks an infix expression such as `x + y * 2` or `(a <+> b) == c`.
+/// It is parsed and inferred like `check`, so operands may be anything
+/// `check` accepts and polymorphic operands are unified with the operators'
+/// types.
+pub fn check_expression(input: &str, env: &Environment) -> Result<Type, Error> {
+    unify::infer(&parser::parse_expr(input, env)?, env)
+}
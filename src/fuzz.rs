@@ -0,0 +1,158 @@
+use crate::environment::AuditEntry;
+use crate::types::instantiate;
+use crate::types::type_enum::Type;
+use crate::types::type_error::Error;
+use crate::Environment;
+
+/// A change made to the arguments of a valid call.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum Perturbation {
+    /// The argument at `position` (0-based) replaced by a `replacement`.
+    Replaced { position: usize, replacement: Type },
+    /// The arguments at two positions swapped.
+    Swapped(usize, usize),
+    /// The last argument dropped.
+    Dropped,
+    /// An extra `Int` argument appended.
+    Extended,
+}
+
+/// The outcome of perturbing the valid calls of one function.
+#[derive(Debug)]
+pub struct FuzzReport {
+    /// The declared parameter types.
+    pub parameters: Vec<Type>,
+    /// The argument lists the perturbations started from.
+    pub calls: Vec<Vec<Type>>,
+    /// Whether `calls` came from the declared signature because no valid
+    /// call was recorded.
+    pub from_signature: bool,
+    pub tried: usize,
+    /// Each perturbation the signature still accepted, with the arguments
+    /// it produced.
+    pub accepted: Vec<(Perturbation, Vec<Type>)>,
+}
+
+/// Perturbs the argument types of the recorded valid calls to `name` and
+/// keeps the perturbations its signature still accepts. A precise
+/// signature rejects nearly all of them; one that accepts many is probably
+/// too permissive, such as a parameter left as a type variable.
+pub fn fuzz(name: &str, env: &Environment) -> Result<FuzzReport, Error> {
+    let (_, input_types) = env
+        .function(name)
+        .ok_or_else(|| Error::UndeclaredFunction(name.to_string()))?;
+
+    let mut calls: Vec<Vec<Type>> = Vec::new();
+    for entry in &env.audit {
+        if let AuditEntry::Call { function, args, .. } = entry {
+            // Literal arguments are not recorded, so only calls whose every
+            // argument was a variable can be reconstructed.
+            let types: Option<Vec<Type>> = args.iter().map(|arg| env.variable(arg)).collect();
+            match types {
+                Some(types)
+                    if function == name
+                        && types.len() == input_types.len()
+                        && !calls.contains(&types) =>
+                {
+                    calls.push(types)
+                }
+                _ => {}
+            }
+        }
+    }
+    let from_signature = calls.is_empty();
+    if from_signature {
+        calls.push(input_types.iter().map(monomorphic).collect());
+    }
+
+    let mut pool: Vec<Type> = vec![
+        Type::Int,
+        Type::Float,
+        Type::Bool,
+        Type::String,
+        Type::Char,
+        Type::Unit,
+        Type::List(Box::new(Type::Int)),
+    ];
+    pool.extend(
+        env.view()
+            .types()
+            .into_iter()
+            .map(|(type_name, _)| Type::Named(type_name.to_string())),
+    );
+
+    let mut tried = 0;
+    let mut accepted = Vec::new();
+    for call in &calls {
+        for (perturbation, args) in perturbations(call, &pool) {
+            tried += 1;
+            if env.call_function(name, &args).is_ok()
+                && !accepted.iter().any(|(_, seen)| *seen == args)
+            {
+                accepted.push((perturbation, args));
+            }
+        }
+    }
+    Ok(FuzzReport {
+        parameters: input_types.clone(),
+        calls,
+        from_signature,
+        tried,
+        accepted,
+    })
+}
+
+fn perturbations(call: &[Type], pool: &[Type]) -> Vec<(Perturbation, Vec<Type>)> {
+    let mut out = Vec::new();
+    for (position, original) in call.iter().enumerate() {
+        for replacement in pool.iter().filter(|t| *t != original) {
+            let mut args = call.to_vec();
+            args[position] = replacement.clone();
+            out.push((
+                Perturbation::Replaced {
+                    position,
+                    replacement: replacement.clone(),
+                },
+                args,
+            ));
+        }
+    }
+    for i in 0..call.len() {
+        for j in i + 1..call.len() {
+            if call[i] != call[j] {
+                let mut args = call.to_vec();
+                args.swap(i, j);
+                out.push((Perturbation::Swapped(i, j), args));
+            }
+        }
+    }
+    if !call.is_empty() {
+        out.push((Perturbation::Dropped, call[..call.len() - 1].to_vec()));
+    }
+    let mut extended = call.to_vec();
+    extended.push(Type::Int);
+    out.push((Perturbation::Extended, extended));
+    out
+}
+
+/// A parameter type with its type variables replaced by `Int`, to stand in
+/// for a call when none was recorded.
+fn monomorphic(t: &Type) -> Type {
+    let mut variables = std::collections::HashMap::new();
+    let mut pending = vec![t];
+    while let Some(t) = pending.pop() {
+        match t {
+            Type::Var(name) => {
+                variables.insert(name.clone(), Type::Int);
+            }
+            Type::List(element) => pending.push(element),
+            Type::Either(left, right) | Type::Function(left, right) => {
+                pending.push(left);
+                pending.push(right);
+            }
+            Type::Tuple(elements) => pending.extend(elements),
+            _ => {}
+        }
+    }
+    instantiate::substitute(t, &variables)
+}
@@ -0,0 +1,35 @@
+use crate::types::type_enum::Type;
+
+/// A small expression language for type inference. Functions take one
+/// argument at a time, so `add x y` is `Apply(Apply(add, x), y)`.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum Expr {
+    /// A variable or function, looked up in the enclosing bindings and then
+    /// in the environment.
+    Name(String),
+    /// A value only known by its type, such as a literal or `Int` written
+    /// in place of an argument.
+    Typed(Type),
+    Apply(Box<Expr>, Box<Expr>),
+    /// `\x -> body`, with an optional annotation on the parameter.
+    Lambda(String, Option<Type>, Box<Expr>),
+    /// `let x = value in body`; `x` is generalized, so it may be used at
+    /// different types in `body`.
+    Let(String, Box<Expr>, Box<Expr>),
+    /// `if condition then a else b`: the condition is a `Bool` and both
+    /// branches share one type.
+    If(Box<Expr>, Box<Expr>, Box<Expr>),
+}
+
+impl Expr {
+    pub fn name(name: &str) -> Self {
+        Expr::Name(name.to_string())
+    }
+
+    /// Applies `function` to each of `arguments` in turn.
+    pub fn apply(function: Expr, arguments: Vec<Expr>) -> Self {
+        arguments.into_iter().fold(function, |function, argument| {
+            Expr::Apply(Box::new(function), Box::new(argument))
+        })
+    }
+}
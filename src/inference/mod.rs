@@ -0,0 +1,123 @@
+pub mod ast;
+pub mod unify;
+
+use crate::environment::AuditEntry;
+use crate::inference::ast::Expr;
+use crate::types::type_enum::Type;
+use crate::types::type_error::Error;
+use crate::Environment;
+
+/// A possible signature for a function, with the share of its recorded uses
+/// that agree with it.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Candidate {
+    pub input_types: Vec<Type>,
+    pub output_type: Type,
+    pub uses: usize,
+    pub confidence: f64,
+}
+
+/// Ranks the parameter lists `function` was called with while undeclared,
+/// most frequent first. Ties keep the order the calls were first seen in.
+pub fn rank_candidates(function: &str, output_type: Type, audit: &[AuditEntry]) -> Vec<Candidate> {
+    let mut counts: Vec<(Vec<Type>, usize)> = Vec::new();
+    for entry in audit {
+        if let AuditEntry::UndeclaredCall {
+            function: called,
+            args,
+        } = entry
+        {
+            if called != function {
+                continue;
+            }
+            match counts.iter_mut().find(|(seen, _)| seen == args) {
+                Some((_, count)) => *count += 1,
+                None => counts.push((args.clone(), 1)),
+            }
+        }
+    }
+
+    let total: usize = counts.iter().map(|(_, count)| count).sum();
+    counts.sort_by_key(|(_, count)| std::cmp::Reverse(*count));
+    counts
+        .into_iter()
+        .map(|(input_types, uses)| Candidate {
+            input_types,
+            output_type: output_type.clone(),
+            uses,
+            confidence: uses as f64 / total as f64,
+        })
+        .collect()
+}
+
+/// The most general parameter list every recorded call to `function` fits:
+/// each position takes the one type all of its observed argument types
+/// coerce to under the environment's coercion policy.
+pub fn most_general(function: &str, env: &Environment) -> Result<Vec<Type>, Error> {
+    let calls: Vec<&Vec<Type>> = env
+        .audit
+        .iter()
+        .filter_map(|entry| match entry {
+            AuditEntry::UndeclaredCall {
+                function: called,
+                args,
+            } if called == function => Some(args),
+            _ => None,
+        })
+        .collect();
+
+    let first = calls
+        .first()
+        .ok_or_else(|| Error::NoInferenceCandidates(function.to_string()))?;
+    if let Some(other) = calls.iter().find(|args| args.len() != first.len()) {
+        return Err(Error::InconsistentCalls {
+            function: function.to_string(),
+            detail: format!(
+                "called with both {} and {} argument(s)",
+                first.len(),
+                other.len()
+            ),
+        });
+    }
+
+    (0..first.len())
+        .map(|position| {
+            let observed: Vec<Type> = calls.iter().map(|args| args[position].clone()).collect();
+            observed
+                .iter()
+                .find(|target| observed.iter().all(|seen| env.can_coerce(seen, target)))
+                .cloned()
+                .ok_or_else(|| Error::InconsistentCalls {
+                    function: function.to_string(),
+                    detail: format!(
+                        "argument {} was {}",
+                        position + 1,
+                        observed
+                            .iter()
+                            .map(|t| format!("{}", t))
+                            .collect::<Vec<String>>()
+                            .join(", ")
+                    ),
+                })
+        })
+        .collect()
+}
+
+/// Shrinks a failing expression to the smallest sub-expression that fails
+/// on its own, with its error. Only applications and `if` are taken apart:
+/// the body of a lambda or `let` refers to names bound around it and
+/// cannot be checked alone. Returns `None` if `expr` typechecks.
+pub fn minimal_failure(expr: &Expr, env: &Environment) -> Option<(Expr, Error)> {
+    let error = unify::infer(expr, env).err()?;
+    let parts: Vec<&Expr> = match expr {
+        Expr::Apply(function, argument) => vec![function, argument],
+        Expr::If(condition, then_branch, else_branch) => {
+            vec![condition, then_branch, else_branch]
+        }
+        _ => Vec::new(),
+    };
+    parts
+        .into_iter()
+        .find_map(|part| minimal_failure(part, env))
+        .or(Some((expr.clone(), error)))
+}
@@ -0,0 +1,454 @@
+use crate::inference::ast::Expr;
+use crate::types::type_enum::Type;
+use crate::types::type_error::Error;
+use crate::Environment;
+use std::collections::HashMap;
+
+/// A type generalized over `variables`, each replaced by a fresh variable
+/// wherever the binding is used.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Scheme {
+    pub variables: Vec<String>,
+    pub body: Type,
+}
+
+impl Scheme {
+    fn monomorphic(body: Type) -> Self {
+        Scheme {
+            variables: Vec::new(),
+            body,
+        }
+    }
+
+    /// Declarations in the environment are closed, so all of their
+    /// variables are generalized.
+    fn closed(body: Type) -> Self {
+        let mut variables = Vec::new();
+        free_variables(&body, &mut variables);
+        Scheme { variables, body }
+    }
+}
+
+/// Infers the most general type of `expr` in `env`, Hindley–Milner style.
+/// Variables in the result are renamed `a`, `b`, ... in order of appearance.
+pub fn infer(expr: &Expr, env: &Environment) -> Result<Type, Error> {
+    let mut inferencer = Inferencer::default();
+    let inferred = inferencer.infer(expr, &mut Vec::new(), env)?;
+    Ok(normalize(&inferencer.resolve(&inferred)))
+}
+
+/// The curried type of a declared function, `Int -> Int -> Int` for a
+/// function of two `Int`s.
+pub fn curried(input_types: &[Type], output_type: &Type) -> Type {
+    input_types
+        .iter()
+        .rev()
+        .fold(output_type.clone(), |result, argument| {
+            Type::Function(Box::new(argument.clone()), Box::new(result))
+        })
+}
+
+/// Splits a function type into its parameters and final result, the
+/// inverse of [`curried`].
+pub fn uncurried(t: &Type) -> (Type, Vec<Type>) {
+    let mut input_types = Vec::new();
+    let mut result = t;
+    while let Type::Function(argument, rest) = result {
+        input_types.push((**argument).clone());
+        result = rest;
+    }
+    (result.clone(), input_types)
+}
+
+#[derive(Default)]
+struct Inferencer {
+    next: usize,
+    substitution: HashMap<String, Type>,
+    /// Fresh variables standing for the variables of annotations, shared
+    /// across the whole expression.
+    annotations: HashMap<String, Type>,
+}
+
+impl Inferencer {
+    fn fresh(&mut self) -> Type {
+        self.next += 1;
+        Type::Var(format!("t{}", self.next))
+    }
+
+    fn infer(
+        &mut self,
+        expr: &Expr,
+        scope: &mut Vec<(String, Scheme)>,
+        env: &Environment,
+    ) -> Result<Type, Error> {
+        match expr {
+            Expr::Name(name) => {
+                let scheme = match scope.iter().rev().find(|(bound, _)| bound == name) {
+                    Some((_, scheme)) => scheme.clone(),
+                    None => lookup(name, env)?,
+                };
+                Ok(self.instantiate(&scheme))
+            }
+            Expr::Typed(t) => Ok(self.annotation(t)),
+            Expr::Apply(function, argument) => {
+                let function = self.infer(function, scope, env)?;
+                let argument = self.infer(argument, scope, env)?;
+                match self.shallow(&function) {
+                    Type::Function(parameter, result) => {
+                        self.unify(&parameter, &argument)?;
+                        Ok(*result)
+                    }
+                    _ => {
+                        let result = self.fresh();
+                        let expected = Type::Function(Box::new(argument), Box::new(result.clone()));
+                        self.unify(&expected, &function)?;
+                        Ok(result)
+                    }
+                }
+            }
+            Expr::Lambda(parameter, annotation, body) => {
+                let parameter_type = match annotation {
+                    Some(t) => self.annotation(t),
+                    None => self.fresh(),
+                };
+                scope.push((
+                    parameter.clone(),
+                    Scheme::monomorphic(parameter_type.clone()),
+                ));
+                let body = self.infer(body, scope, env);
+                scope.pop();
+                Ok(Type::Function(Box::new(parameter_type), Box::new(body?)))
+            }
+            Expr::Let(name, value, body) => {
+                let value = self.infer(value, scope, env)?;
+                let scheme = self.generalize(&value, scope);
+                scope.push((name.clone(), scheme));
+                let body = self.infer(body, scope, env);
+                scope.pop();
+                body
+            }
+            Expr::If(condition, then_branch, else_branch) => {
+                let condition = self.infer(condition, scope, env)?;
+                self.unify(&Type::Bool, &condition)?;
+                let then_type = self.infer(then_branch, scope, env)?;
+                let else_type = self.infer(else_branch, scope, env)?;
+                match self.unify(&then_type, &else_type) {
+                    Err(Error::TypeMismatch { .. }) => {
+                        let (then_branch, else_branch) =
+                            normalize_pair(&self.resolve(&then_type), &self.resolve(&else_type));
+                        Err(Error::BranchMismatch {
+                            then_branch,
+                            else_branch,
+                        })
+                    }
+                    other => other.map(|_| then_type),
+                }
+            }
+        }
+    }
+
+    fn instantiate(&mut self, scheme: &Scheme) -> Type {
+        let fresh: HashMap<String, Type> = scheme
+            .variables
+            .iter()
+            .map(|variable| (variable.clone(), self.fresh()))
+            .collect();
+        crate::types::instantiate::substitute(&scheme.body, &fresh)
+    }
+
+    fn annotation(&mut self, t: &Type) -> Type {
+        let mut variables = Vec::new();
+        free_variables(t, &mut variables);
+        for variable in variables {
+            if !self.annotations.contains_key(&variable) {
+                let fresh = self.fresh();
+                self.annotations.insert(variable, fresh);
+            }
+        }
+        crate::types::instantiate::substitute(t, &self.annotations)
+    }
+
+    /// Generalizes over the variables of `t` not bound in the enclosing
+    /// scope, which would otherwise escape it.
+    fn generalize(&self, t: &Type, scope: &[(String, Scheme)]) -> Scheme {
+        let body = self.resolve(t);
+        let mut in_scope = Vec::new();
+        for (_, scheme) in scope {
+            let mut variables = Vec::new();
+            free_variables(&self.resolve(&scheme.body), &mut variables);
+            in_scope.extend(
+                variables
+                    .into_iter()
+                    .filter(|variable| !scheme.variables.contains(variable)),
+            );
+        }
+        let mut variables = Vec::new();
+        free_variables(&body, &mut variables);
+        variables.retain(|variable| !in_scope.contains(variable));
+        Scheme { variables, body }
+    }
+
+    /// Follows the substitution at the top of `t` only.
+    fn shallow(&self, t: &Type) -> Type {
+        let mut t = t.clone();
+        while let Type::Var(name) = &t {
+            match self.substitution.get(name) {
+                Some(bound) => t = bound.clone(),
+                None => break,
+            }
+        }
+        t
+    }
+
+    /// Applies the substitution throughout `t`.
+    fn resolve(&self, t: &Type) -> Type {
+        match self.shallow(t) {
+            Type::List(element) => Type::List(Box::new(self.resolve(&element))),
+            Type::Either(left, right) => Type::Either(
+                Box::new(self.resolve(&left)),
+                Box::new(self.resolve(&right)),
+            ),
+            Type::Function(argument, result) => Type::Function(
+                Box::new(self.resolve(&argument)),
+                Box::new(self.resolve(&result)),
+            ),
+            Type::Tuple(elements) => Type::Tuple(
+                elements
+                    .iter()
+                    .map(|element| self.resolve(element))
+                    .collect(),
+            ),
+            other => other,
+        }
+    }
+
+    /// Unifies the two types, reporting a mismatch between them as a whole
+    /// rather than between the parts that clashed.
+    fn unify(&mut self, expected: &Type, actual: &Type) -> Result<(), Error> {
+        match self.unify_parts(expected, actual) {
+            Err(Error::TypeMismatch { .. }) => {
+                let (expected, actual) =
+                    normalize_pair(&self.resolve(expected), &self.resolve(actual));
+                Err(Error::TypeMismatch { expected, actual })
+            }
+            other => other,
+        }
+    }
+
+    fn unify_parts(&mut self, expected: &Type, actual: &Type) -> Result<(), Error> {
+        match (self.shallow(expected), self.shallow(actual)) {
+            (Type::Var(a), Type::Var(b)) if a == b => Ok(()),
+            (Type::Var(variable), other) | (other, Type::Var(variable)) => {
+                let other = self.resolve(&other);
+                if occurs(&variable, &other) {
+                    let (variable, within) = normalize_pair(&Type::Var(variable), &other);
+                    return Err(Error::InfiniteType {
+                        variable: variable.to_string(),
+                        within,
+                    });
+                }
+                self.substitution.insert(variable, other);
+                Ok(())
+            }
+            (Type::List(a), Type::List(b)) => self.unify_parts(&a, &b),
+            (Type::Either(a1, a2), Type::Either(b1, b2))
+            | (Type::Function(a1, a2), Type::Function(b1, b2)) => {
+                self.unify_parts(&a1, &b1)?;
+                self.unify_parts(&a2, &b2)
+            }
+            (Type::Tuple(a), Type::Tuple(b)) if a.len() == b.len() => {
+                for (a, b) in a.iter().zip(&b) {
+                    self.unify_parts(a, b)?;
+                }
+                Ok(())
+            }
+            (a, b) if a == b => Ok(()),
+            (a, b) => Err(Error::TypeMismatch {
+                expected: a,
+                actual: b,
+            }),
+        }
+    }
+}
+
+fn lookup(name: &str, env: &Environment) -> Result<Scheme, Error> {
+    if let Some(var_type) = env.variable(name) {
+        return Ok(Scheme::closed(var_type));
+    }
+    if let Some((output_type, input_types)) = env.function(name) {
+        return Ok(Scheme::closed(curried(input_types, output_type)));
+    }
+    match env.operator(name) {
+        Some(operator) => Ok(Scheme::closed(curried(
+            &[operator.left.clone(), operator.right.clone()],
+            &operator.output,
+        ))),
+        None => Err(Error::UndeclaredVariable(name.to_string())),
+    }
+}
+
+/// The first variable of `t`'s result that none of its parameters
+/// mentions, so that nothing a caller passes could decide it.
+pub fn unbound_variable(t: &Type) -> Option<String> {
+    let (output_type, input_types) = uncurried(t);
+    let mut bound = Vec::new();
+    for input_type in &input_types {
+        free_variables(input_type, &mut bound);
+    }
+    let mut variables = Vec::new();
+    free_variables(&output_type, &mut variables);
+    variables
+        .into_iter()
+        .find(|variable| !bound.contains(variable))
+}
+
+fn occurs(variable: &str, t: &Type) -> bool {
+    let mut variables = Vec::new();
+    free_variables(t, &mut variables);
+    variables.iter().any(|v| v == variable)
+}
+
+/// Collects the variables of `t` in order of first appearance.
+fn free_variables(t: &Type, variables: &mut Vec<String>) {
+    match t {
+        Type::Var(name) if !variables.contains(name) => variables.push(name.clone()),
+        Type::List(element) => free_variables(element, variables),
+        Type::Either(left, right) | Type::Function(left, right) => {
+            free_variables(left, variables);
+            free_variables(right, variables);
+        }
+        Type::Tuple(elements) => {
+            for element in elements {
+                free_variables(element, variables);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Renames the variables of `t` to `a`, `b`, ... in order of appearance.
+pub fn normalize(t: &Type) -> Type {
+    normalize_pair(t, &Type::Unit).0
+}
+
+/// Renames the variables of both types to `a`, `b`, ... consistently, so a
+/// mismatch reads the same however many fresh variables were made.
+fn normalize_pair(first: &Type, second: &Type) -> (Type, Type) {
+    let mut variables = Vec::new();
+    free_variables(first, &mut variables);
+    free_variables(second, &mut variables);
+    let names: HashMap<String, Type> = variables
+        .into_iter()
+        .enumerate()
+        .map(|(i, variable)| (variable, Type::Var(variable_name(i))))
+        .collect();
+    (
+        crate::types::instantiate::substitute(first, &names),
+        crate::types::instantiate::substitute(second, &names),
+    )
+}
+
+/// The canonical form of `t`, for comparing signatures as text: variables
+/// renamed to `a`, `b`, ... in order of appearance and, with
+/// `fold_aliases`, every part equal to an alias's expansion written as the
+/// alias. Aliases are already expanded when a type is parsed, so without it
+/// they stay expanded.
+pub fn canonical(t: &Type, env: &Environment, fold_aliases: bool) -> Type {
+    let t = normalize(t);
+    if !fold_aliases {
+        return t;
+    }
+    let aliases: Vec<(&str, Type)> = env
+        .type_names()
+        .into_iter()
+        .filter_map(|name| env.alias(name).map(|target| (name, normalize(target))))
+        .collect();
+    fold(&t, &aliases)
+}
+
+/// `t` with every outermost part equal to an alias's expansion written as
+/// the alias, so `{ x: Int, y: Float }` prints as `Point` again.
+pub fn fold_aliases(t: &Type, env: &Environment) -> Type {
+    let aliases: Vec<(&str, Type)> = env
+        .type_names()
+        .into_iter()
+        .filter_map(|name| env.alias(name).map(|target| (name, target.clone())))
+        .collect();
+    fold(t, &aliases)
+}
+
+/// Replaces the outermost parts of `t` equal to an alias's target by the
+/// alias, the first alphabetically when several share a target.
+fn fold(t: &Type, aliases: &[(&str, Type)]) -> Type {
+    if let Some((name, _)) = aliases.iter().find(|(_, target)| target == t) {
+        return Type::Named(name.to_string());
+    }
+    match t {
+        Type::List(element) => Type::List(Box::new(fold(element, aliases))),
+        Type::Either(left, right) => Type::Either(
+            Box::new(fold(left, aliases)),
+            Box::new(fold(right, aliases)),
+        ),
+        Type::Function(argument, result) => Type::Function(
+            Box::new(fold(argument, aliases)),
+            Box::new(fold(result, aliases)),
+        ),
+        Type::Tuple(elements) => Type::Tuple(
+            elements
+                .iter()
+                .map(|element| fold(element, aliases))
+                .collect(),
+        ),
+        other => other.clone(),
+    }
+}
+
+fn variable_name(i: usize) -> String {
+    let letter = (b'a' + (i % 26) as u8) as char;
+    match i / 26 {
+        0 => letter.to_string(),
+        n => format!("{}{}", letter, n),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse_expr;
+
+    fn infer_str(source: &str) -> Result<Type, Error> {
+        let env = Environment::new();
+        infer(&parse_expr(source, &env).unwrap(), &env)
+    }
+
+    #[test]
+    fn self_application_fails_the_occurs_check() {
+        assert!(matches!(
+            infer_str("\\x -> x x"),
+            Err(Error::InfiniteType { .. })
+        ));
+    }
+
+    #[test]
+    fn let_bindings_are_generalized() {
+        assert_eq!(
+            infer_str("let id = \\x -> x in id 1 == id 2 && id true"),
+            Ok(Type::Bool)
+        );
+        assert_eq!(
+            infer_str("let k = \\x -> \\y -> x in k"),
+            Ok(curried(
+                &[Type::Var("a".to_string()), Type::Var("b".to_string())],
+                &Type::Var("a".to_string())
+            ))
+        );
+    }
+
+    #[test]
+    fn lambda_parameters_are_not_generalized() {
+        assert!(matches!(
+            infer_str("\\f -> f 1 && f true"),
+            Err(Error::TypeMismatch { .. })
+        ));
+    }
+}
@@ -0,0 +1,1336 @@
+use crate::diagnostic::json_string;
+use crate::operators::{Associativity, Operator};
+use crate::parser;
+use crate::prelude::BuiltinSet;
+use crate::types::type_definition::TypeDefinition;
+use crate::types::type_enum::Type;
+use crate::types::type_error::Error;
+use crate::value::Value;
+use crate::Environment;
+use std::collections::BTreeSet;
+use std::fmt;
+
+/// A parsed JSON document. Objects keep their keys in the order written.
+#[derive(Debug, PartialEq, Clone)]
+pub enum Json {
+    Null,
+    Bool(bool),
+    /// A number as written, so integers and floats read back exactly.
+    Number(String),
+    String(String),
+    Array(Vec<Json>),
+    Object(Vec<(String, Json)>),
+}
+
+impl Json {
+    /// Parses a complete JSON document.
+    pub fn parse(text: &str) -> Result<Json, Error> {
+        let mut reader = Reader { text, at: 0 };
+        let json = reader.value()?;
+        reader.skip_whitespace();
+        if reader.at < text.len() {
+            return Err(reader.error("unexpected text after the document"));
+        }
+        Ok(json)
+    }
+
+    pub(crate) fn object(entries: Vec<(&str, Json)>) -> Json {
+        Json::Object(
+            entries
+                .into_iter()
+                .map(|(key, value)| (key.to_string(), value))
+                .collect(),
+        )
+    }
+
+    /// A one-entry object naming an enum variant, as in `{"List":"Int"}`.
+    fn variant(name: &str, payload: Json) -> Json {
+        Json::object(vec![(name, payload)])
+    }
+
+    pub(crate) fn field(&self, key: &str) -> Result<&Json, Error> {
+        match self {
+            Json::Object(entries) => entries
+                .iter()
+                .find(|(name, _)| name == key)
+                .map(|(_, value)| value)
+                .ok_or_else(|| invalid(&format!("a `{}` field", key), self)),
+            _ => Err(invalid("an object", self)),
+        }
+    }
+
+    /// The field `key`, or `None` when the object has no such field.
+    pub(crate) fn optional_field(&self, key: &str) -> Result<Option<&Json>, Error> {
+        match self {
+            Json::Object(entries) => Ok(entries
+                .iter()
+                .find(|(name, _)| name == key)
+                .map(|(_, value)| value)),
+            _ => Err(invalid("an object", self)),
+        }
+    }
+
+    pub(crate) fn as_str(&self) -> Result<&str, Error> {
+        match self {
+            Json::String(text) => Ok(text),
+            _ => Err(invalid("a string", self)),
+        }
+    }
+
+    fn as_array(&self) -> Result<&[Json], Error> {
+        match self {
+            Json::Array(elements) => Ok(elements),
+            _ => Err(invalid("an array", self)),
+        }
+    }
+
+    fn as_object(&self) -> Result<&[(String, Json)], Error> {
+        match self {
+            Json::Object(entries) => Ok(entries),
+            _ => Err(invalid("an object", self)),
+        }
+    }
+
+    /// The variant name and payload of a one-entry object, or the name
+    /// alone for a variant without a payload, written as a bare string.
+    fn as_variant(&self) -> Result<(&str, Option<&Json>), Error> {
+        match self {
+            Json::String(name) => Ok((name, None)),
+            Json::Object(entries) if entries.len() == 1 => {
+                Ok((entries[0].0.as_str(), Some(&entries[0].1)))
+            }
+            _ => Err(invalid("a variant name or one-entry object", self)),
+        }
+    }
+
+    /// The two elements of a pair array.
+    fn as_pair(&self) -> Result<(&Json, &Json), Error> {
+        match self.as_array()? {
+            [first, second] => Ok((first, second)),
+            _ => Err(invalid("a two-element array", self)),
+        }
+    }
+}
+
+impl fmt::Display for Json {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Json::Null => write!(f, "null"),
+            Json::Bool(b) => write!(f, "{}", b),
+            Json::Number(n) => write!(f, "{}", n),
+            Json::String(text) => write!(f, "{}", json_string(text)),
+            Json::Array(elements) => {
+                let elements = elements
+                    .iter()
+                    .map(|element| element.to_string())
+                    .collect::<Vec<String>>();
+                write!(f, "[{}]", elements.join(","))
+            }
+            Json::Object(entries) => {
+                let entries = entries
+                    .iter()
+                    .map(|(key, value)| format!("{}:{}", json_string(key), value))
+                    .collect::<Vec<String>>();
+                write!(f, "{{{}}}", entries.join(","))
+            }
+        }
+    }
+}
+
+fn invalid(expected: &str, found: &Json) -> Error {
+    let mut found = found.to_string();
+    if found.chars().count() > 40 {
+        found = found.chars().take(40).collect::<String>() + "...";
+    }
+    Error::Syntax(format!(
+        "invalid JSON: expected {}, got `{}`",
+        expected, found
+    ))
+}
+
+/// A cursor over JSON text.
+struct Reader<'a> {
+    text: &'a str,
+    at: usize,
+}
+
+impl Reader<'_> {
+    fn error(&self, message: &str) -> Error {
+        let line = self.text[..self.at].matches('\n').count() + 1;
+        Error::Syntax(format!("invalid JSON at line {}: {}", line, message))
+    }
+
+    fn rest(&self) -> &str {
+        &self.text[self.at..]
+    }
+
+    fn skip_whitespace(&mut self) {
+        let rest = self.rest();
+        self.at += rest.len() - rest.trim_start().len();
+    }
+
+    fn eat(&mut self, token: &str) -> bool {
+        self.skip_whitespace();
+        if self.rest().starts_with(token) {
+            self.at += token.len();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, token: &str) -> Result<(), Error> {
+        if self.eat(token) {
+            Ok(())
+        } else {
+            Err(self.error(&format!("expected `{}`", token)))
+        }
+    }
+
+    fn value(&mut self) -> Result<Json, Error> {
+        self.skip_whitespace();
+        match self.rest().chars().next() {
+            Some('{') => {
+                self.at += 1;
+                let mut entries = Vec::new();
+                if !self.eat("}") {
+                    loop {
+                        self.skip_whitespace();
+                        let key = self.string()?;
+                        self.expect(":")?;
+                        entries.push((key, self.value()?));
+                        if self.eat("}") {
+                            break;
+                        }
+                        self.expect(",")?;
+                    }
+                }
+                Ok(Json::Object(entries))
+            }
+            Some('[') => {
+                self.at += 1;
+                let mut elements = Vec::new();
+                if !self.eat("]") {
+                    loop {
+                        elements.push(self.value()?);
+                        if self.eat("]") {
+                            break;
+                        }
+                        self.expect(",")?;
+                    }
+                }
+                Ok(Json::Array(elements))
+            }
+            Some('"') => self.string().map(Json::String),
+            Some('-' | '0'..='9') => {
+                let length = self
+                    .rest()
+                    .find(|c: char| !matches!(c, '-' | '+' | '.' | 'e' | 'E' | '0'..='9'))
+                    .unwrap_or(self.rest().len());
+                let number = self.rest()[..length].to_string();
+                if number.parse::<f64>().is_err() {
+                    return Err(self.error(&format!("`{}` is not a number", number)));
+                }
+                self.at += length;
+                Ok(Json::Number(number))
+            }
+            _ if self.eat("true") => Ok(Json::Bool(true)),
+            _ if self.eat("false") => Ok(Json::Bool(false)),
+            _ if self.eat("null") => Ok(Json::Null),
+            _ => Err(self.error("expected a value")),
+        }
+    }
+
+    fn string(&mut self) -> Result<String, Error> {
+        if !self.rest().starts_with('"') {
+            return Err(self.error("expected a string"));
+        }
+        self.at += 1;
+        let mut out = String::new();
+        let mut chars = self.rest().char_indices();
+        while let Some((i, c)) = chars.next() {
+            match c {
+                '"' => {
+                    self.at += i + 1;
+                    return Ok(out);
+                }
+                '\\' => {
+                    let escaped = match chars.next().map(|(_, escaped)| escaped) {
+                        Some('n') => '\n',
+                        Some('t') => '\t',
+                        Some('r') => '\r',
+                        Some('b') => '\u{8}',
+                        Some('f') => '\u{c}',
+                        Some('u') => {
+                            let hex = (0..4)
+                                .filter_map(|_| chars.next().map(|(_, digit)| digit))
+                                .collect::<String>();
+                            let code = u32::from_str_radix(&hex, 16).ok();
+                            match code.and_then(char::from_u32) {
+                                Some(c) => c,
+                                None => return Err(self.error("bad `\\u` escape")),
+                            }
+                        }
+                        Some(other @ ('"' | '\\' | '/')) => other,
+                        _ => return Err(self.error("bad escape")),
+                    };
+                    out.push(escaped);
+                }
+                c => out.push(c),
+            }
+        }
+        Err(self.error("unterminated string"))
+    }
+}
+
+impl Type {
+    /// The type as JSON, each variant named as in the enum: scalars as
+    /// strings such as `"Int"`, the others as one-entry objects such as
+    /// `{"List":"Int"}` or `{"Function":["Int","Bool"]}`.
+    pub fn to_json(&self) -> String {
+        self.to_json_value().to_string()
+    }
+
+    /// Reads a type written by [`Type::to_json`].
+    pub fn from_json(text: &str) -> Result<Type, Error> {
+        Type::from_json_value(&Json::parse(text)?)
+    }
+
+    fn to_json_value(&self) -> Json {
+        let pair = |first: &Type, second: &Type| {
+            Json::Array(vec![first.to_json_value(), second.to_json_value()])
+        };
+        match self {
+            Type::Int => Json::String("Int".to_string()),
+            Type::Float => Json::String("Float".to_string()),
+            Type::Bool => Json::String("Bool".to_string()),
+            Type::String => Json::String("String".to_string()),
+            Type::Char => Json::String("Char".to_string()),
+            Type::Unit => Json::String("Unit".to_string()),
+            Type::List(element) => Json::variant("List", element.to_json_value()),
+            Type::Either(left, right) => Json::variant("Either", pair(left, right)),
+            Type::Named(name) => Json::variant("Named", Json::String(name.clone())),
+            Type::Tuple(elements) => Json::variant(
+                "Tuple",
+                Json::Array(elements.iter().map(Type::to_json_value).collect()),
+            ),
+            Type::Var(name) => Json::variant("Var", Json::String(name.clone())),
+            Type::Function(input, output) => Json::variant("Function", pair(input, output)),
+        }
+    }
+
+    fn from_json_value(json: &Json) -> Result<Type, Error> {
+        let pair = |payload: &Json| -> Result<(Box<Type>, Box<Type>), Error> {
+            let (first, second) = payload.as_pair()?;
+            Ok((
+                Box::new(Type::from_json_value(first)?),
+                Box::new(Type::from_json_value(second)?),
+            ))
+        };
+        Ok(match json.as_variant()? {
+            ("Int", None) => Type::Int,
+            ("Float", None) => Type::Float,
+            ("Bool", None) => Type::Bool,
+            ("String", None) => Type::String,
+            ("Char", None) => Type::Char,
+            ("Unit", None) => Type::Unit,
+            ("List", Some(element)) => Type::List(Box::new(Type::from_json_value(element)?)),
+            ("Either", Some(payload)) => {
+                let (left, right) = pair(payload)?;
+                Type::Either(left, right)
+            }
+            ("Named", Some(name)) => Type::Named(name.as_str()?.to_string()),
+            ("Tuple", Some(elements)) => Type::Tuple(
+                elements
+                    .as_array()?
+                    .iter()
+                    .map(Type::from_json_value)
+                    .collect::<Result<Vec<Type>, Error>>()?,
+            ),
+            ("Var", Some(name)) => Type::Var(name.as_str()?.to_string()),
+            ("Function", Some(payload)) => {
+                let (input, output) = pair(payload)?;
+                Type::Function(input, output)
+            }
+            _ => return Err(invalid("a type", json)),
+        })
+    }
+}
+
+fn types_to_json(types: &[Type]) -> Json {
+    Json::Array(types.iter().map(Type::to_json_value).collect())
+}
+
+fn types_from_json(json: &Json) -> Result<Vec<Type>, Error> {
+    json.as_array()?.iter().map(Type::from_json_value).collect()
+}
+
+fn definition_to_json(definition: &TypeDefinition) -> Json {
+    match definition {
+        TypeDefinition::Sum(constructors) => Json::variant(
+            "Sum",
+            Json::Array(
+                constructors
+                    .iter()
+                    .map(|(name, fields)| {
+                        Json::Array(vec![Json::String(name.clone()), types_to_json(fields)])
+                    })
+                    .collect(),
+            ),
+        ),
+        TypeDefinition::Record(fields) => Json::variant(
+            "Record",
+            Json::Array(
+                fields
+                    .iter()
+                    .map(|(name, field_type)| {
+                        Json::Array(vec![Json::String(name.clone()), field_type.to_json_value()])
+                    })
+                    .collect(),
+            ),
+        ),
+        TypeDefinition::Newtype {
+            constructor,
+            wrapped,
+        } => Json::variant(
+            "Newtype",
+            Json::object(vec![
+                ("constructor", Json::String(constructor.clone())),
+                ("wrapped", wrapped.to_json_value()),
+            ]),
+        ),
+    }
+}
+
+fn definition_from_json(json: &Json) -> Result<TypeDefinition, Error> {
+    Ok(match json.as_variant()? {
+        ("Sum", Some(constructors)) => TypeDefinition::Sum(
+            constructors
+                .as_array()?
+                .iter()
+                .map(|constructor| {
+                    let (name, fields) = constructor.as_pair()?;
+                    Ok((name.as_str()?.to_string(), types_from_json(fields)?))
+                })
+                .collect::<Result<_, Error>>()?,
+        ),
+        ("Record", Some(fields)) => TypeDefinition::Record(
+            fields
+                .as_array()?
+                .iter()
+                .map(|field| {
+                    let (name, field_type) = field.as_pair()?;
+                    Ok((
+                        name.as_str()?.to_string(),
+                        Type::from_json_value(field_type)?,
+                    ))
+                })
+                .collect::<Result<_, Error>>()?,
+        ),
+        ("Newtype", Some(payload)) => TypeDefinition::Newtype {
+            constructor: payload.field("constructor")?.as_str()?.to_string(),
+            wrapped: Type::from_json_value(payload.field("wrapped")?)?,
+        },
+        _ => return Err(invalid("a type definition", json)),
+    })
+}
+
+fn operator_to_json(operator: &Operator) -> Json {
+    let associativity = match operator.associativity {
+        Associativity::Left => "Left",
+        Associativity::Right => "Right",
+        Associativity::None => "None",
+    };
+    Json::object(vec![
+        ("symbol", Json::String(operator.symbol.clone())),
+        ("precedence", Json::Number(operator.precedence.to_string())),
+        ("associativity", Json::String(associativity.to_string())),
+        ("left", operator.left.to_json_value()),
+        ("right", operator.right.to_json_value()),
+        ("output", operator.output.to_json_value()),
+        ("note", Json::String(operator.note.clone())),
+    ])
+}
+
+fn operator_from_json(json: &Json) -> Result<Operator, Error> {
+    let precedence = json.field("precedence")?;
+    let associativity = json.field("associativity")?;
+    Ok(Operator {
+        symbol: json.field("symbol")?.as_str()?.to_string(),
+        precedence: match precedence {
+            Json::Number(n) => n
+                .parse()
+                .map_err(|_| invalid("a precedence from 0 to 9", precedence))?,
+            _ => return Err(invalid("a number", precedence)),
+        },
+        associativity: match associativity.as_str()? {
+            "Left" => Associativity::Left,
+            "Right" => Associativity::Right,
+            "None" => Associativity::None,
+            _ => return Err(invalid("`Left`, `Right` or `None`", associativity)),
+        },
+        left: Type::from_json_value(json.field("left")?)?,
+        right: Type::from_json_value(json.field("right")?)?,
+        output: Type::from_json_value(json.field("output")?)?,
+        note: match json.optional_field("note")? {
+            Some(note) => note.as_str()?.to_string(),
+            None => String::new(),
+        },
+    })
+}
+
+fn value_to_json(value: &Value) -> Json {
+    match value {
+        Value::Int(n) => Json::variant("Int", Json::Number(n.to_string())),
+        // JSON has no infinities or NaN, so those are written as strings.
+        Value::Float(x) if x.is_finite() => {
+            Json::variant("Float", Json::Number(format!("{:?}", x)))
+        }
+        Value::Float(x) => Json::variant("Float", Json::String(x.to_string())),
+        Value::Bool(b) => Json::variant("Bool", Json::Bool(*b)),
+        Value::String(text) => Json::variant("String", Json::String(text.clone())),
+        Value::Char(c) => Json::variant("Char", Json::String(c.to_string())),
+        Value::Unit => Json::String("Unit".to_string()),
+        Value::List(elements) => Json::variant(
+            "List",
+            Json::Array(elements.iter().map(value_to_json).collect()),
+        ),
+        Value::Record { type_name, fields } => Json::variant(
+            "Record",
+            Json::object(vec![
+                ("type_name", Json::String(type_name.clone())),
+                (
+                    "fields",
+                    Json::Array(
+                        fields
+                            .iter()
+                            .map(|(name, value)| {
+                                Json::Array(vec![Json::String(name.clone()), value_to_json(value)])
+                            })
+                            .collect(),
+                    ),
+                ),
+            ]),
+        ),
+        Value::Closure(expr) => Json::variant("Closure", Json::String(parser::show_expr(expr))),
+    }
+}
+
+fn value_from_json(json: &Json, env: &Environment) -> Result<Value, Error> {
+    Ok(match json.as_variant()? {
+        ("Int", Some(Json::Number(n))) => {
+            Value::Int(n.parse().map_err(|_| invalid("an integer", json))?)
+        }
+        ("Float", Some(Json::Number(x) | Json::String(x))) => {
+            Value::Float(x.parse().map_err(|_| invalid("a float", json))?)
+        }
+        ("Bool", Some(Json::Bool(b))) => Value::Bool(*b),
+        ("String", Some(Json::String(text))) => Value::String(text.clone()),
+        ("Char", Some(Json::String(text))) => {
+            let mut chars = text.chars();
+            match (chars.next(), chars.next()) {
+                (Some(c), None) => Value::Char(c),
+                _ => return Err(invalid("a single character", json)),
+            }
+        }
+        ("Unit", None) => Value::Unit,
+        ("List", Some(elements)) => Value::List(
+            elements
+                .as_array()?
+                .iter()
+                .map(|element| value_from_json(element, env))
+                .collect::<Result<_, Error>>()?,
+        ),
+        ("Record", Some(payload)) => Value::Record {
+            type_name: payload.field("type_name")?.as_str()?.to_string(),
+            fields: payload
+                .field("fields")?
+                .as_array()?
+                .iter()
+                .map(|field| {
+                    let (name, value) = field.as_pair()?;
+                    Ok((name.as_str()?.to_string(), value_from_json(value, env)?))
+                })
+                .collect::<Result<_, Error>>()?,
+        },
+        ("Closure", Some(Json::String(source))) => Value::Closure(parser::parse_expr(source, env)?),
+        _ => return Err(invalid("a value", json)),
+    })
+}
+
+impl Environment {
+    /// Everything declared in the environment as one JSON object: its
+    /// builtins, then the user's variables, functions, types, aliases,
+    /// operators, tags and values. Constructors are left to their types and
+    /// builtins to `builtins`. Names are sorted, so equal environments give
+    /// equal text.
+    pub fn to_json(&self) -> String {
+        let view = self.view();
+        let builtins = self.builtins();
+        let variables = view.user_variables();
+        let mut functions = view.functions();
+        functions.retain(|(name, input_types, output_type)| {
+            !view.is_constructor(name) && !self.is_builtin(name, input_types, output_type)
+        });
+        functions.sort_by_key(|(name, _, _)| *name);
+        let signature = |input_types: &[Type], output_type: &Type| {
+            Json::object(vec![
+                ("inputs", types_to_json(input_types)),
+                ("output", output_type.to_json_value()),
+            ])
+        };
+        let mut values = variables
+            .iter()
+            .filter_map(|(name, _)| view.value(name).map(|value| (*name, value_to_json(value))))
+            .collect::<Vec<_>>();
+        values.sort_by_key(|(name, _)| *name);
+
+        Json::object(vec![
+            (
+                "builtins",
+                Json::object(vec![
+                    (
+                        "functions",
+                        Json::Array(
+                            builtins
+                                .functions
+                                .iter()
+                                .map(|(name, input_types, output_type)| {
+                                    Json::Array(vec![
+                                        Json::String(name.clone()),
+                                        signature(input_types, output_type),
+                                    ])
+                                })
+                                .collect(),
+                        ),
+                    ),
+                    (
+                        "operators",
+                        Json::Array(builtins.operators.iter().map(operator_to_json).collect()),
+                    ),
+                ]),
+            ),
+            (
+                "variables",
+                Json::object(
+                    variables
+                        .iter()
+                        .map(|(name, var_type)| (*name, var_type.to_json_value()))
+                        .collect(),
+                ),
+            ),
+            (
+                "functions",
+                Json::object(
+                    functions
+                        .iter()
+                        .map(|(name, input_types, output_type)| {
+                            (*name, signature(input_types, output_type))
+                        })
+                        .collect(),
+                ),
+            ),
+            (
+                "types",
+                Json::object(
+                    view.types()
+                        .into_iter()
+                        .map(|(name, definition)| (name, definition_to_json(definition)))
+                        .collect(),
+                ),
+            ),
+            (
+                "aliases",
+                Json::object(
+                    view.aliases()
+                        .into_iter()
+                        .map(|(name, target)| (name, target.to_json_value()))
+                        .collect(),
+                ),
+            ),
+            (
+                "operators",
+                Json::Array(
+                    self.operators
+                        .iter()
+                        .filter(|op| !builtins.operators.contains(op))
+                        .map(operator_to_json)
+                        .collect(),
+                ),
+            ),
+            (
+                "tags",
+                Json::object(
+                    view.tags()
+                        .into_iter()
+                        .map(|(name, tags)| {
+                            (
+                                name,
+                                Json::Array(tags.into_iter().map(Json::String).collect()),
+                            )
+                        })
+                        .collect(),
+                ),
+            ),
+            ("values", Json::object(values)),
+        ])
+        .to_string()
+    }
+
+    /// Rebuilds an environment written by [`Environment::to_json`]. Every
+    /// declaration goes through the usual checks, so hand-edited JSON is
+    /// held to the same rules as commands.
+    pub fn from_json(text: &str) -> Result<Environment, Error> {
+        let json = Json::parse(text)?;
+        let signature = |json: &Json| -> Result<(Vec<Type>, Type), Error> {
+            Ok((
+                types_from_json(json.field("inputs")?)?,
+                Type::from_json_value(json.field("output")?)?,
+            ))
+        };
+        let builtins = json.field("builtins")?;
+        let builtins = BuiltinSet {
+            functions: builtins
+                .field("functions")?
+                .as_array()?
+                .iter()
+                .map(|function| {
+                    let (name, types) = function.as_pair()?;
+                    let (input_types, output_type) = signature(types)?;
+                    Ok((name.as_str()?.to_string(), input_types, output_type))
+                })
+                .collect::<Result<_, Error>>()?,
+            operators: builtins
+                .field("operators")?
+                .as_array()?
+                .iter()
+                .map(operator_from_json)
+                .collect::<Result<_, Error>>()?,
+        };
+
+        let mut env = Environment::with_builtins(builtins);
+        for (name, definition) in json.field("types")?.as_object()? {
+            env.declare_type(name, definition_from_json(definition)?)?;
+        }
+        for (name, target) in json.field("aliases")?.as_object()? {
+            env.declare_alias(name, Type::from_json_value(target)?)?;
+        }
+        for (name, var_type) in json.field("variables")?.as_object()? {
+            env.declare_variable(name, Type::from_json_value(var_type)?)?;
+        }
+        for (name, types) in json.field("functions")?.as_object()? {
+            let (input_types, output_type) = signature(types)?;
+            env.declare_function(name, input_types, output_type)?;
+        }
+        for operator in json.field("operators")?.as_array()? {
+            env.declare_operator(operator_from_json(operator)?)?;
+        }
+        for (name, tags) in json.field("tags")?.as_object()? {
+            let tags = tags
+                .as_array()?
+                .iter()
+                .map(|tag| tag.as_str().map(str::to_string))
+                .collect::<Result<BTreeSet<String>, Error>>()?;
+            env.tags.insert(name.clone(), tags);
+        }
+        for (name, value) in json.field("values")?.as_object()? {
+            let var_type = env
+                .variable(name)
+                .ok_or_else(|| Error::UndeclaredVariable(name.clone()))?;
+            let value = value_from_json(value, &env)?;
+            env.define_variable(name, var_type, value)?;
+        }
+        Ok(env)
+    }
+}
+
+impl Error {
+    /// The error as JSON, named as in the enum like [`Type::to_json`]:
+    /// `"NoOpenScope"`, `{"Syntax":"..."}` or `{"ArityMismatch":{"function":
+    /// "f","expected":2,"got":1}}`, with types written as `Type::to_json`
+    /// writes them. For the rendered message, notes and help, see
+    /// [`Diagnostic::to_json`](crate::diagnostic::Diagnostic::to_json).
+    pub fn to_json(&self) -> String {
+        self.to_json_value().to_string()
+    }
+
+    /// Reads an error written by [`Error::to_json`].
+    pub fn from_json(text: &str) -> Result<Error, Error> {
+        Error::from_json_value(&Json::parse(text)?)
+    }
+
+    fn to_json_value(&self) -> Json {
+        let text = |text: &str| Json::String(text.to_string());
+        let number = |n: usize| Json::Number(n.to_string());
+        let texts = |texts: &[String]| Json::Array(texts.iter().map(|t| text(t)).collect());
+        let fields =
+            |name: &str, fields: Vec<(&str, Json)>| Json::variant(name, Json::object(fields));
+        match self {
+            Error::UndeclaredFunction(name) => Json::variant("UndeclaredFunction", text(name)),
+            Error::UndeclaredVariable(name) => Json::variant("UndeclaredVariable", text(name)),
+            Error::ArityMismatch {
+                function,
+                expected,
+                got,
+            } => fields(
+                "ArityMismatch",
+                vec![
+                    ("function", text(function)),
+                    ("expected", number(*expected)),
+                    ("got", number(*got)),
+                ],
+            ),
+            Error::UnknownCommand { name, suggestions } => fields(
+                "UnknownCommand",
+                vec![("name", text(name)), ("suggestions", texts(suggestions))],
+            ),
+            Error::Syntax(message) => Json::variant("Syntax", text(message)),
+            Error::ArgumentMismatch {
+                function,
+                input_types,
+                output_type,
+                position,
+                provided,
+            } => fields(
+                "ArgumentMismatch",
+                vec![
+                    ("function", text(function)),
+                    ("input_types", types_to_json(input_types)),
+                    ("output_type", output_type.to_json_value()),
+                    ("position", number(*position)),
+                    ("provided", provided.to_json_value()),
+                ],
+            ),
+            Error::AscriptionMismatch {
+                expression,
+                actual,
+                ascribed,
+            } => fields(
+                "AscriptionMismatch",
+                vec![
+                    ("expression", text(expression)),
+                    ("actual", actual.to_json_value()),
+                    ("ascribed", ascribed.to_json_value()),
+                ],
+            ),
+            Error::UnknownType { name, suggestions } => fields(
+                "UnknownType",
+                vec![("name", text(name)), ("suggestions", texts(suggestions))],
+            ),
+            Error::InvalidName(name) => Json::variant("InvalidName", text(name)),
+            Error::Usage {
+                command,
+                expected,
+                problem,
+            } => fields(
+                "Usage",
+                vec![
+                    ("command", text(command)),
+                    ("expected", text(expected)),
+                    ("problem", text(problem)),
+                ],
+            ),
+            Error::UnknownOperator(symbol) => Json::variant("UnknownOperator", text(symbol)),
+            Error::AmbiguousOperators {
+                left,
+                right,
+                precedence,
+            } => fields(
+                "AmbiguousOperators",
+                vec![
+                    ("left", text(left)),
+                    ("right", text(right)),
+                    ("precedence", number(*precedence as usize)),
+                ],
+            ),
+            Error::UnknownField {
+                type_name,
+                field,
+                suggestions,
+            } => fields(
+                "UnknownField",
+                vec![
+                    ("type_name", text(type_name)),
+                    ("field", text(field)),
+                    ("suggestions", texts(suggestions)),
+                ],
+            ),
+            Error::NotARecord(t) => Json::variant("NotARecord", t.to_json_value()),
+            Error::CompositionMismatch {
+                outer,
+                inner,
+                produced,
+                expected,
+            } => fields(
+                "CompositionMismatch",
+                vec![
+                    ("outer", text(outer)),
+                    ("inner", text(inner)),
+                    ("produced", produced.to_json_value()),
+                    ("expected", expected.to_json_value()),
+                ],
+            ),
+            Error::TypeMismatch { expected, actual } => fields(
+                "TypeMismatch",
+                vec![
+                    ("expected", expected.to_json_value()),
+                    ("actual", actual.to_json_value()),
+                ],
+            ),
+            Error::InfiniteType { variable, within } => fields(
+                "InfiniteType",
+                vec![
+                    ("variable", text(variable)),
+                    ("within", within.to_json_value()),
+                ],
+            ),
+            Error::BranchMismatch {
+                then_branch,
+                else_branch,
+            } => fields(
+                "BranchMismatch",
+                vec![
+                    ("then_branch", then_branch.to_json_value()),
+                    ("else_branch", else_branch.to_json_value()),
+                ],
+            ),
+            Error::CorruptSnapshot {
+                path,
+                expected,
+                actual,
+            } => fields(
+                "CorruptSnapshot",
+                vec![
+                    ("path", text(path)),
+                    ("expected", text(expected)),
+                    ("actual", text(actual)),
+                ],
+            ),
+            Error::MissingChecksum(path) => Json::variant("MissingChecksum", text(path)),
+            Error::UnknownModule { name, suggestions } => fields(
+                "UnknownModule",
+                vec![("name", text(name)), ("suggestions", texts(suggestions))],
+            ),
+            Error::ImportCycle(modules) => Json::variant("ImportCycle", texts(modules)),
+            Error::UnknownSymbol { name, suggestions } => fields(
+                "UnknownSymbol",
+                vec![("name", text(name)), ("suggestions", texts(suggestions))],
+            ),
+            Error::InvalidManifest {
+                path,
+                line,
+                message,
+            } => fields(
+                "InvalidManifest",
+                vec![
+                    ("path", text(path)),
+                    ("line", number(*line)),
+                    ("message", text(message)),
+                ],
+            ),
+            Error::PreludeMismatch { saved, current } => fields(
+                "PreludeMismatch",
+                vec![("saved", text(saved)), ("current", text(current))],
+            ),
+            Error::NoInferenceCandidates(function) => {
+                Json::variant("NoInferenceCandidates", text(function))
+            }
+            Error::InconsistentCalls { function, detail } => fields(
+                "InconsistentCalls",
+                vec![("function", text(function)), ("detail", text(detail))],
+            ),
+            Error::NoPendingCandidates => text("NoPendingCandidates"),
+            Error::NoOpenScope => text("NoOpenScope"),
+            Error::Redeclaration { name, previous } => fields(
+                "Redeclaration",
+                vec![("name", text(name)), ("previous", previous.to_json_value())],
+            ),
+            Error::NameTaken(name) => Json::variant("NameTaken", text(name)),
+            Error::ConfirmationRequired { question } => {
+                fields("ConfirmationRequired", vec![("question", text(question))])
+            }
+            Error::EnvironmentFrozen => text("EnvironmentFrozen"),
+            Error::LimitExceeded { limit, max, actual } => fields(
+                "LimitExceeded",
+                vec![
+                    ("limit", text(limit)),
+                    ("max", number(*max)),
+                    ("actual", number(*actual)),
+                ],
+            ),
+            Error::Io { path, message } => {
+                fields("Io", vec![("path", text(path)), ("message", text(message))])
+            }
+            Error::UnknownTemplate(name) => Json::variant("UnknownTemplate", text(name)),
+            Error::AssertionFailed {
+                expression,
+                expected,
+                actual,
+            } => fields(
+                "AssertionFailed",
+                vec![
+                    ("expression", text(expression)),
+                    ("expected", expected.to_json_value()),
+                    ("actual", actual.to_json_value()),
+                ],
+            ),
+            Error::ExpectedError {
+                command,
+                expected,
+                actual,
+            } => fields(
+                "ExpectedError",
+                vec![
+                    ("command", text(command)),
+                    ("expected", text(expected)),
+                    ("actual", actual.as_deref().map_or(Json::Null, text)),
+                ],
+            ),
+            Error::VerificationFailed {
+                pack,
+                total,
+                failures,
+            } => fields(
+                "VerificationFailed",
+                vec![
+                    ("pack", text(pack)),
+                    ("total", number(*total)),
+                    ("failures", texts(failures)),
+                ],
+            ),
+            Error::ImportFailed { path, errors } => fields(
+                "ImportFailed",
+                vec![
+                    ("path", text(path)),
+                    (
+                        "errors",
+                        Json::Array(
+                            errors
+                                .iter()
+                                .map(|(line, err)| {
+                                    Json::Array(vec![number(*line), err.to_json_value()])
+                                })
+                                .collect(),
+                        ),
+                    ),
+                ],
+            ),
+            Error::ValueMismatch { value, expected } => fields(
+                "ValueMismatch",
+                vec![
+                    ("value", text(value)),
+                    ("expected", expected.to_json_value()),
+                ],
+            ),
+            Error::UnboundTypeVariable {
+                name,
+                declared,
+                variable,
+            } => fields(
+                "UnboundTypeVariable",
+                vec![
+                    ("name", text(name)),
+                    ("declared", declared.to_json_value()),
+                    ("variable", text(variable)),
+                ],
+            ),
+            Error::InSubexpression {
+                subexpression,
+                error,
+            } => fields(
+                "InSubexpression",
+                vec![
+                    ("subexpression", text(subexpression)),
+                    ("error", error.to_json_value()),
+                ],
+            ),
+        }
+    }
+
+    fn from_json_value(json: &Json) -> Result<Error, Error> {
+        let text = |payload: &Json, key: &str| -> Result<String, Error> {
+            Ok(payload.field(key)?.as_str()?.to_string())
+        };
+        let number = |payload: &Json, key: &str| -> Result<usize, Error> {
+            let n = payload.field(key)?;
+            match n {
+                Json::Number(digits) => digits.parse().map_err(|_| invalid("a count", n)),
+                _ => Err(invalid("a number", n)),
+            }
+        };
+        let texts = |payload: &Json, key: &str| -> Result<Vec<String>, Error> {
+            payload
+                .field(key)?
+                .as_array()?
+                .iter()
+                .map(|t| t.as_str().map(str::to_string))
+                .collect()
+        };
+        let of_type = |payload: &Json, key: &str| Type::from_json_value(payload.field(key)?);
+        Ok(match json.as_variant()? {
+            ("UndeclaredFunction", Some(name)) => {
+                Error::UndeclaredFunction(name.as_str()?.to_string())
+            }
+            ("UndeclaredVariable", Some(name)) => {
+                Error::UndeclaredVariable(name.as_str()?.to_string())
+            }
+            ("ArityMismatch", Some(payload)) => Error::ArityMismatch {
+                function: text(payload, "function")?,
+                expected: number(payload, "expected")?,
+                got: number(payload, "got")?,
+            },
+            ("UnknownCommand", Some(payload)) => Error::UnknownCommand {
+                name: text(payload, "name")?,
+                suggestions: texts(payload, "suggestions")?,
+            },
+            ("Syntax", Some(message)) => Error::Syntax(message.as_str()?.to_string()),
+            ("ArgumentMismatch", Some(payload)) => Error::ArgumentMismatch {
+                function: text(payload, "function")?,
+                input_types: types_from_json(payload.field("input_types")?)?,
+                output_type: of_type(payload, "output_type")?,
+                position: number(payload, "position")?,
+                provided: of_type(payload, "provided")?,
+            },
+            ("AscriptionMismatch", Some(payload)) => Error::AscriptionMismatch {
+                expression: text(payload, "expression")?,
+                actual: of_type(payload, "actual")?,
+                ascribed: of_type(payload, "ascribed")?,
+            },
+            ("UnknownType", Some(payload)) => Error::UnknownType {
+                name: text(payload, "name")?,
+                suggestions: texts(payload, "suggestions")?,
+            },
+            ("InvalidName", Some(name)) => Error::InvalidName(name.as_str()?.to_string()),
+            ("Usage", Some(payload)) => Error::Usage {
+                command: text(payload, "command")?,
+                expected: text(payload, "expected")?,
+                problem: text(payload, "problem")?,
+            },
+            ("UnknownOperator", Some(symbol)) => {
+                Error::UnknownOperator(symbol.as_str()?.to_string())
+            }
+            ("AmbiguousOperators", Some(payload)) => {
+                let precedence = payload.field("precedence")?;
+                Error::AmbiguousOperators {
+                    left: text(payload, "left")?,
+                    right: text(payload, "right")?,
+                    precedence: number(payload, "precedence")?
+                        .try_into()
+                        .map_err(|_| invalid("a precedence from 0 to 9", precedence))?,
+                }
+            }
+            ("UnknownField", Some(payload)) => Error::UnknownField {
+                type_name: text(payload, "type_name")?,
+                field: text(payload, "field")?,
+                suggestions: texts(payload, "suggestions")?,
+            },
+            ("NotARecord", Some(t)) => Error::NotARecord(Type::from_json_value(t)?),
+            ("CompositionMismatch", Some(payload)) => Error::CompositionMismatch {
+                outer: text(payload, "outer")?,
+                inner: text(payload, "inner")?,
+                produced: of_type(payload, "produced")?,
+                expected: of_type(payload, "expected")?,
+            },
+            ("TypeMismatch", Some(payload)) => Error::TypeMismatch {
+                expected: of_type(payload, "expected")?,
+                actual: of_type(payload, "actual")?,
+            },
+            ("InfiniteType", Some(payload)) => Error::InfiniteType {
+                variable: text(payload, "variable")?,
+                within: of_type(payload, "within")?,
+            },
+            ("BranchMismatch", Some(payload)) => Error::BranchMismatch {
+                then_branch: of_type(payload, "then_branch")?,
+                else_branch: of_type(payload, "else_branch")?,
+            },
+            ("CorruptSnapshot", Some(payload)) => Error::CorruptSnapshot {
+                path: text(payload, "path")?,
+                expected: text(payload, "expected")?,
+                actual: text(payload, "actual")?,
+            },
+            ("MissingChecksum", Some(path)) => Error::MissingChecksum(path.as_str()?.to_string()),
+            ("UnknownModule", Some(payload)) => Error::UnknownModule {
+                name: text(payload, "name")?,
+                suggestions: texts(payload, "suggestions")?,
+            },
+            ("ImportCycle", Some(modules)) => Error::ImportCycle(
+                modules
+                    .as_array()?
+                    .iter()
+                    .map(|module| module.as_str().map(str::to_string))
+                    .collect::<Result<_, Error>>()?,
+            ),
+            ("UnknownSymbol", Some(payload)) => Error::UnknownSymbol {
+                name: text(payload, "name")?,
+                suggestions: texts(payload, "suggestions")?,
+            },
+            ("InvalidManifest", Some(payload)) => Error::InvalidManifest {
+                path: text(payload, "path")?,
+                line: number(payload, "line")?,
+                message: text(payload, "message")?,
+            },
+            ("PreludeMismatch", Some(payload)) => Error::PreludeMismatch {
+                saved: text(payload, "saved")?,
+                current: text(payload, "current")?,
+            },
+            ("NoInferenceCandidates", Some(function)) => {
+                Error::NoInferenceCandidates(function.as_str()?.to_string())
+            }
+            ("InconsistentCalls", Some(payload)) => Error::InconsistentCalls {
+                function: text(payload, "function")?,
+                detail: text(payload, "detail")?,
+            },
+            ("NoPendingCandidates", None) => Error::NoPendingCandidates,
+            ("NoOpenScope", None) => Error::NoOpenScope,
+            ("Redeclaration", Some(payload)) => Error::Redeclaration {
+                name: text(payload, "name")?,
+                previous: of_type(payload, "previous")?,
+            },
+            ("NameTaken", Some(name)) => Error::NameTaken(name.as_str()?.to_string()),
+            ("ConfirmationRequired", Some(payload)) => Error::ConfirmationRequired {
+                question: text(payload, "question")?,
+            },
+            ("EnvironmentFrozen", None) => Error::EnvironmentFrozen,
+            ("LimitExceeded", Some(payload)) => {
+                let limit = payload.field("limit")?;
+                Error::LimitExceeded {
+                    // The limits are fixed, so the name maps back to one.
+                    limit: match limit.as_str()? {
+                        "max_tokens" => "max_tokens",
+                        "max_depth" => "max_depth",
+                        "requests_per_second" => "requests_per_second",
+                        _ => return Err(invalid("a limit name", limit)),
+                    },
+                    max: number(payload, "max")?,
+                    actual: number(payload, "actual")?,
+                }
+            }
+            ("Io", Some(payload)) => Error::Io {
+                path: text(payload, "path")?,
+                message: text(payload, "message")?,
+            },
+            ("UnknownTemplate", Some(name)) => Error::UnknownTemplate(name.as_str()?.to_string()),
+            ("AssertionFailed", Some(payload)) => Error::AssertionFailed {
+                expression: text(payload, "expression")?,
+                expected: of_type(payload, "expected")?,
+                actual: of_type(payload, "actual")?,
+            },
+            ("ExpectedError", Some(payload)) => Error::ExpectedError {
+                command: text(payload, "command")?,
+                expected: text(payload, "expected")?,
+                actual: match payload.field("actual")? {
+                    Json::Null => None,
+                    actual => Some(actual.as_str()?.to_string()),
+                },
+            },
+            ("VerificationFailed", Some(payload)) => Error::VerificationFailed {
+                pack: text(payload, "pack")?,
+                total: number(payload, "total")?,
+                failures: texts(payload, "failures")?,
+            },
+            ("ImportFailed", Some(payload)) => Error::ImportFailed {
+                path: text(payload, "path")?,
+                errors: payload
+                    .field("errors")?
+                    .as_array()?
+                    .iter()
+                    .map(|failure| {
+                        let (line, err) = failure.as_pair()?;
+                        let line = match line {
+                            Json::Number(digits) => {
+                                digits.parse().map_err(|_| invalid("a line number", line))?
+                            }
+                            _ => return Err(invalid("a line number", line)),
+                        };
+                        Ok((line, Error::from_json_value(err)?))
+                    })
+                    .collect::<Result<_, Error>>()?,
+            },
+            ("ValueMismatch", Some(payload)) => Error::ValueMismatch {
+                value: text(payload, "value")?,
+                expected: of_type(payload, "expected")?,
+            },
+            ("UnboundTypeVariable", Some(payload)) => Error::UnboundTypeVariable {
+                name: text(payload, "name")?,
+                declared: of_type(payload, "declared")?,
+                variable: text(payload, "variable")?,
+            },
+            ("InSubexpression", Some(payload)) => Error::InSubexpression {
+                subexpression: text(payload, "subexpression")?,
+                error: Box::new(Error::from_json_value(payload.field("error")?)?),
+            },
+            _ => return Err(invalid("an error", json)),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn types_round_trip() {
+        let t = Type::Function(
+            Box::new(Type::List(Box::new(Type::Var("a".to_string())))),
+            Box::new(Type::Tuple(vec![
+                Type::Int,
+                Type::Named("Shape".to_string()),
+            ])),
+        );
+        assert_eq!(
+            t.to_json(),
+            r#"{"Function":[{"List":{"Var":"a"}},{"Tuple":["Int",{"Named":"Shape"}]}]}"#
+        );
+        assert_eq!(Type::from_json(&t.to_json()), Ok(t));
+    }
+
+    #[test]
+    fn errors_round_trip() {
+        let errors = [
+            Error::NoOpenScope,
+            Error::Syntax("unexpected `)`".to_string()),
+            Error::ArgumentMismatch {
+                function: "f".to_string(),
+                input_types: vec![Type::Int, Type::Bool],
+                output_type: Type::Unit,
+                position: 1,
+                provided: Type::String,
+            },
+            Error::LimitExceeded {
+                limit: "max_depth",
+                max: 64,
+                actual: 65,
+            },
+            Error::ExpectedError {
+                command: "call f 1".to_string(),
+                expected: "ArityMismatch".to_string(),
+                actual: None,
+            },
+            Error::ImportFailed {
+                path: "sigs.hsig".to_string(),
+                errors: vec![(3, Error::InvalidName("1x".to_string()))],
+            },
+            Error::InSubexpression {
+                subexpression: "g x".to_string(),
+                error: Box::new(Error::UndeclaredFunction("g".to_string())),
+            },
+        ];
+        for err in errors {
+            assert_eq!(Error::from_json(&err.to_json()), Ok(err));
+        }
+        assert_eq!(Error::NoOpenScope.to_json(), r#""NoOpenScope""#);
+        assert!(Error::from_json(r#"{"LimitExceeded":{"limit":"x","max":1,"actual":2}}"#).is_err());
+    }
+
+    #[test]
+    fn environments_round_trip() {
+        let mut env = Environment::new();
+        env.declare_variable("x", Type::Int).unwrap();
+        env.define_variable("n", Type::Int, Value::Int(5)).unwrap();
+        env.declare_function("f", vec![Type::Int], Type::Bool)
+            .unwrap();
+        let loaded = Environment::from_json(&env.to_json()).unwrap();
+        assert_eq!(loaded.to_json(), env.to_json());
+        assert_eq!(loaded.value("n"), Some(&Value::Int(5)));
+    }
+}
@@ -1,141 +1,2957 @@
+pub mod commands;
+pub mod diagnostic;
+pub mod diff;
 pub mod environment;
+pub mod export;
+pub mod expr;
+pub mod fuzz;
+pub mod inference;
+pub mod json;
+pub mod limits;
+pub mod locale;
+pub mod operators;
+pub mod parser;
+pub mod paths;
+pub mod prelude;
+pub mod pretty;
+pub mod project;
+pub mod script;
+pub mod session;
+pub mod suggest;
+pub mod templates;
 pub mod types;
+pub mod value;
 
+use crate::inference::ast::Expr;
+use crate::inference::unify::{self, uncurried};
+use crate::operators::{Associativity, Operator};
+use crate::types::instantiate;
+use crate::types::type_definition::TypeDefinition;
 use crate::types::type_enum::Type;
 use crate::types::type_error::Error;
-pub use environment::Environment;
+use crate::types::type_parser;
+use crate::value::Value;
+pub use commands::{ArgSpec, Command, Registry, TokenKind};
+pub use diagnostic::Diagnostic;
+pub use environment::{AuditEntry, Environment, EnvironmentBuilder, EnvironmentView, Warning};
 
+use std::cmp::Reverse;
+use std::collections::BTreeMap;
 use std::fmt;
-use std::io::{self, Write};
+use std::fs;
+use std::path::Path;
 use std::str::FromStr;
+use std::time::{Duration, Instant};
 
 impl FromStr for Type {
     type Err = ();
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        match s {
-            "Int" => Ok(Type::Int),
-            "Float" => Ok(Type::Float),
-            "Bool" => Ok(Type::Bool),
+        match type_parser::parse_types(s, &|_| None) {
+            Ok(mut types) if types.len() == 1 => Ok(types.pop().unwrap()),
             _ => Err(()),
         }
     }
 }
 
+impl fmt::Display for Type {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Type::List(element) => write!(f, "[{}]", element),
+            Type::Either(left, right) => {
+                write!(f, "Either {} {}", Argument(left), Argument(right))
+            }
+            Type::Named(name) | Type::Var(name) => write!(f, "{}", name),
+            Type::Tuple(elements) => {
+                let elements = elements
+                    .iter()
+                    .map(Type::to_string)
+                    .collect::<Vec<String>>();
+                write!(f, "({})", elements.join(", "))
+            }
+            Type::Function(argument, result) => match **argument {
+                Type::Function(..) => write!(f, "({}) -> {}", argument, result),
+                _ => write!(f, "{} -> {}", argument, result),
+            },
+            other => write!(f, "{:?}", other),
+        }
+    }
+}
+
+impl fmt::Display for TypeDefinition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TypeDefinition::Sum(constructors) => {
+                for (i, (constructor, fields)) in constructors.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, " | ")?;
+                    }
+                    write!(f, "{}", constructor)?;
+                    for field in fields {
+                        write!(f, " {}", Argument(field))?;
+                    }
+                }
+                Ok(())
+            }
+            TypeDefinition::Newtype { wrapped, .. } => write!(f, "{}", wrapped),
+            TypeDefinition::Record(fields) => {
+                let fields = fields
+                    .iter()
+                    .map(|(name, field_type)| format!("{}: {}", name, field_type))
+                    .collect::<Vec<String>>();
+                write!(f, "{{ {} }}", fields.join(", "))
+            }
+        }
+    }
+}
+
+/// A constructor argument, parenthesized when it takes arguments itself.
+pub(crate) struct Argument<'a>(&'a Type);
+
+impl fmt::Display for Argument<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.0 {
+            Type::Either(..) | Type::Function(..) => write!(f, "({})", self.0),
+            other => write!(f, "{}", other),
+        }
+    }
+}
+
 impl fmt::Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
-            Error::TypeError => write!(f, "Type Error"),
-            Error::UndeclaredFunction => write!(f, "Undeclared Function"),
-            Error::UndeclaredVariable => write!(f, "Undeclared Variable"),
+            Error::UndeclaredFunction(name) => write!(f, "Undeclared function `{}`", name),
+            Error::UndeclaredVariable(name) => write!(f, "Undeclared variable `{}`", name),
+            Error::ArityMismatch {
+                function,
+                expected,
+                got,
+            } => write!(
+                f,
+                "Type Error: `{}` takes {} argument(s) but {} were supplied",
+                function, expected, got
+            ),
+            Error::UnknownCommand { name, .. } => write!(f, "Unknown command `{}`", name),
+            Error::Syntax(message) => write!(f, "Syntax error: {}", message),
+            Error::InvalidName(name) => write!(f, "Invalid name `{}`", name),
+            Error::ArgumentMismatch {
+                function,
+                input_types,
+                output_type,
+                position,
+                provided,
+            } => {
+                writeln!(
+                    f,
+                    "Type Error: argument {} of {} to `{}` has the wrong type",
+                    position + 1,
+                    input_types.len(),
+                    function
+                )?;
+
+                // Underline the offending parameter within the full signature.
+                let prefix = format!("    {} :: ", function);
+                let mut offset = prefix.len();
+                for input_type in &input_types[..*position] {
+                    offset += format!("{} -> ", input_type).len();
+                }
+                let expected = format!("{}", input_types[*position]);
+
+                writeln!(
+                    f,
+                    "{}{}",
+                    prefix,
+                    format_signature(input_types, output_type)
+                )?;
+                writeln!(
+                    f,
+                    "{}{} expected",
+                    " ".repeat(offset),
+                    "^".repeat(expected.len())
+                )?;
+                write!(f, "{}{} provided", " ".repeat(offset), provided)
+            }
+            Error::AscriptionMismatch {
+                expression,
+                actual,
+                ascribed,
+            } => write!(
+                f,
+                "Type Error: `{}` has type {} and cannot be ascribed {}",
+                expression, actual, ascribed
+            ),
+            Error::UnknownType { name, .. } => write!(f, "Unknown type `{}`", name),
+            Error::Usage {
+                command,
+                expected,
+                problem,
+            } => write!(f, "{} expects {}, {}", command, expected, problem),
+            Error::LimitExceeded { limit, max, actual } => {
+                write!(f, "Limit `{}` exceeded: {} > {}", limit, actual, max)
+            }
+            Error::UnknownOperator(symbol) => write!(f, "Unknown operator `{}`", symbol),
+            Error::AmbiguousOperators {
+                left,
+                right,
+                precedence,
+            } => write!(
+                f,
+                "Cannot mix `{}` and `{}` without parentheses, both have precedence {}",
+                left, right, precedence
+            ),
+            Error::UnknownField {
+                type_name, field, ..
+            } => write!(f, "Type Error: `{}` has no field `{}`", type_name, field),
+            Error::NotARecord(target_type) => {
+                write!(f, "Type Error: `{}` is not a record type", target_type)
+            }
+            Error::CompositionMismatch {
+                outer,
+                inner,
+                produced,
+                expected,
+            } => write!(
+                f,
+                "Type Error: `{}` returns `{}` but `{}` takes `{}`",
+                inner, produced, outer, expected
+            ),
+            Error::TypeMismatch { expected, actual } => {
+                write!(f, "Type Error: expected `{}`, found `{}`", expected, actual)
+            }
+            Error::BranchMismatch {
+                then_branch,
+                else_branch,
+            } => write!(
+                f,
+                "Type Error: the branches of `if` differ, `then` gives `{}` but `else` gives `{}`",
+                then_branch, else_branch
+            ),
+            Error::InfiniteType { variable, within } => write!(
+                f,
+                "Type Error: `{}` would have to be `{}`, which contains itself",
+                variable, within
+            ),
+            Error::CorruptSnapshot { path, .. } => {
+                write!(
+                    f,
+                    "Snapshot {} is corrupt, its checksum does not match",
+                    path
+                )
+            }
+            Error::MissingChecksum(path) => write!(f, "Snapshot {} has no checksum", path),
+            Error::UnknownModule { name, .. } => write!(f, "Unknown module `{}`", name),
+            Error::ConfirmationRequired { question } => write!(f, "{}", question),
+            Error::UnknownSymbol { name, .. } => {
+                write!(f, "`{}` is not declared anywhere", name)
+            }
+            Error::ImportCycle(modules) => {
+                write!(f, "Import cycle: {}", modules.join(" -> "))
+            }
+            Error::InvalidManifest {
+                path,
+                line: 0,
+                message,
+            } => write!(f, "Invalid manifest {}: {}", path, message),
+            Error::InvalidManifest {
+                path,
+                line,
+                message,
+            } => write!(f, "Invalid manifest {}:{}: {}", path, line, message),
+            Error::PreludeMismatch { saved, current } => write!(
+                f,
+                "Snapshot was saved with prelude {} but this session runs {}",
+                saved, current
+            ),
+            Error::NoInferenceCandidates(name) => {
+                write!(f, "No recorded calls to infer `{}` from", name)
+            }
+            Error::InconsistentCalls { function, detail } => write!(
+                f,
+                "No single signature fits the calls to `{}`: {}",
+                function, detail
+            ),
+            Error::NoPendingCandidates => write!(f, "No such candidate signature"),
+            Error::NoOpenScope => write!(f, "No scope is open"),
+            Error::NameTaken(name) => write!(f, "`{}` is already declared", name),
+            Error::Redeclaration { name, previous } => {
+                write!(f, "`{}` is already declared as `{}`", name, previous)
+            }
+            Error::EnvironmentFrozen => write!(f, "Environment is frozen"),
+            Error::Io { path, message } => write!(f, "Cannot read `{}`: {}", path, message),
+            Error::UnknownTemplate(name) => write!(f, "Unknown template `{}`", name),
+            Error::ValueMismatch { value, expected } => {
+                write!(
+                    f,
+                    "Type Error: the value `{}` is not a `{}`",
+                    value, expected
+                )
+            }
+            Error::UnboundTypeVariable {
+                name,
+                declared,
+                variable,
+            } => write!(
+                f,
+                "Type Error: `{} :: {}` mentions the type variable `{}`, which no parameter binds",
+                name, declared, variable
+            ),
+            Error::InSubexpression { error, .. } => write!(f, "{}", error),
+            Error::AssertionFailed {
+                expression,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "Assertion failed: `{}` has type `{}`, not `{}`",
+                expression, actual, expected
+            ),
+            Error::ExpectedError {
+                command,
+                expected,
+                actual: None,
+            } => write!(
+                f,
+                "Assertion failed: `{}` succeeded, expected it to fail with {}",
+                command, expected
+            ),
+            Error::ExpectedError {
+                command,
+                expected,
+                actual: Some(actual),
+            } => write!(
+                f,
+                "Assertion failed: `{}` failed with {}, expected {}",
+                command, actual, expected
+            ),
+            Error::VerificationFailed {
+                pack,
+                total,
+                failures,
+            } => {
+                write!(
+                    f,
+                    "{} of {} test(s) failed in pack `{}`",
+                    failures.len(),
+                    total,
+                    pack
+                )?;
+                for failure in failures {
+                    write!(f, "\n    {}", failure)?;
+                }
+                Ok(())
+            }
+            Error::ImportFailed { path, errors } => {
+                write!(f, "Nothing imported from `{}`", path)?;
+                for (line, err) in errors {
+                    write!(f, "\n    line {}: {}", line, err)?;
+                }
+                Ok(())
+            }
         }
     }
 }
 
-fn call_function(input: &[&str], env: &mut Environment) -> Result<String, Error> {
-    if input.len() < 1 {
-        return Err(Error::TypeError);
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            // An import fails as a whole; its first line error is the cause.
+            Error::ImportFailed { errors, .. } => errors
+                .first()
+                .map(|(_, err)| err as &(dyn std::error::Error + 'static)),
+            _ => None,
+        }
     }
+}
 
-    let func_name = input[0];
-    let args = &input[1..];
+/// Renders a signature, parenthesizing function-typed parameters and
+/// results so they are not read as more parameters.
+pub(crate) fn format_signature(input_types: &[Type], output_type: &Type) -> String {
+    input_types
+        .iter()
+        .chain(std::iter::once(output_type))
+        .map(|t| match t {
+            Type::Function(..) => format!("({})", t),
+            _ => format!("{}", t),
+        })
+        .collect::<Vec<String>>()
+        .join(" -> ")
+}
 
-    let mut converted_args = Vec::new();
+/// `t` as the display settings of `env` print it, with aliases folded back
+/// in when `fold on` is set.
+fn displayed(t: &Type, env: &Environment) -> Type {
+    if env.fold_aliases {
+        unify::fold_aliases(t, env)
+    } else {
+        t.clone()
+    }
+}
+
+/// Renders `ty` with constructor nesting past `depth` elided, so `[[[Int]]]`
+/// at depth 2 prints as `[[…]]`. `None` prints the whole type. Arrows are
+/// not constructors, so the parameters and result of a function are elided
+/// at the depth of the function itself.
+pub(crate) fn format_elided(ty: &Type, depth: Option<usize>) -> String {
+    let inner = depth.map(|d| d.saturating_sub(1));
+    match (ty, depth) {
+        (Type::List(_) | Type::Either(..) | Type::Tuple(_), Some(0)) => "…".to_string(),
+        (Type::Tuple(elements), _) => {
+            let elements = elements
+                .iter()
+                .map(|element| format_elided(element, inner))
+                .collect::<Vec<String>>();
+            format!("({})", elements.join(", "))
+        }
+        (Type::List(element), _) => format!("[{}]", format_elided(element, inner)),
+        (Type::Either(left, right), _) => {
+            let argument = |t: &Type| match t {
+                Type::Function(..) => format!("({})", format_elided(t, inner)),
+                Type::Either(..) if inner != Some(0) => format!("({})", format_elided(t, inner)),
+                _ => format_elided(t, inner),
+            };
+            format!("Either {} {}", argument(left), argument(right))
+        }
+        (Type::Function(argument, result), _) => {
+            let argument = match **argument {
+                Type::Function(..) => format!("({})", format_elided(argument, depth)),
+                _ => format_elided(argument, depth),
+            };
+            format!("{} -> {}", argument, format_elided(result, depth))
+        }
+        (other, _) => other.to_string(),
+    }
+}
+
+/// Parses a sequence of types, see [`type_parser::parse_types`], adding
+/// suggestions to unknown type names.
+pub(crate) fn parse_types(source: &str, env: &Environment) -> Result<Vec<Type>, Error> {
+    parse_types_with(source, env, &[])
+}
+
+/// Like `parse_types`, also accepting the `pending` names about to be
+/// declared with the type each stands for, so a type may refer to itself or
+/// its neighbours. Aliases expand to their definition.
+fn parse_types_with(
+    source: &str,
+    env: &Environment,
+    pending: &[(&str, Type)],
+) -> Result<Vec<Type>, Error> {
+    let resolve = |name: &str| {
+        pending
+            .iter()
+            .find(|(pending, _)| *pending == name)
+            .map(|(_, t)| t.clone())
+            .or_else(|| env.alias(name).cloned())
+            .or_else(|| {
+                env.type_definition(name)
+                    .map(|_| Type::Named(name.to_string()))
+            })
+    };
+    type_parser::parse_types(source, &resolve).map_err(|err| match err {
+        Error::UnknownType { name, .. } => Error::UnknownType {
+            suggestions: suggest::suggestions(
+                &name,
+                env.type_names().into_iter().chain(["Either"]),
+            ),
+            name,
+        },
+        other => other,
+    })
+}
+
+pub(crate) fn parse_type(source: &str, env: &Environment) -> Result<Type, Error> {
+    let mut types = parse_types(source, env)?;
+    if types.len() == 1 {
+        Ok(types.pop().unwrap())
+    } else {
+        Err(Error::Syntax(format!(
+            "expected one type, found {} in `{}`",
+            types.len(),
+            source
+        )))
+    }
+}
+
+/// An argument is a literal, a declared variable or a concrete type written
+/// in place, as in `call f 3 x Int`.
+pub(crate) fn resolve_argument(arg: &str, env: &Environment) -> Result<Type, Error> {
+    if let Some(literal) = parser::literal_type(arg) {
+        return Ok(literal);
+    }
+    if let Some(var_type) = env.variable(arg) {
+        return Ok(var_type);
+    }
+    match parse_type(arg, env) {
+        Ok(arg_type) if !instantiate::is_polymorphic(&arg_type) => Ok(arg_type),
+        _ => Err(Error::UndeclaredVariable(arg.to_string())),
+    }
+}
+
+// Arguments are a bare name or type, an ascription `(name : Type)` or a
+// nested call `(f ARG...)`, so the tokens are re-split around parentheses
+// and colons first.
+fn resolve_arguments(args: &[&str], env: &Environment) -> Result<Vec<Type>, Error> {
+    let mut tokens: Vec<&str> = Vec::new();
     for arg in args {
-        if let Ok(var_type) = arg.parse::<Type>() {
-            converted_args.push(var_type);
-        } else if let Some(var_type) = env.variables.get(&arg.to_string()) {
-            converted_args.push(var_type.clone());
-        } else {
-            return Err(Error::UndeclaredVariable);
+        let mut start = 0;
+        let mut i = 0;
+        while let Some(c) = arg[i..].chars().next() {
+            // String and character literals stay whole, so `"(a)"` and
+            // `':'` keep their parentheses and colons.
+            let literal = match c {
+                '"' => parser::string_length(&arg[i..]),
+                '\'' => parser::char_length(&arg[i..]),
+                _ => None,
+            };
+            if let Some(length) = literal {
+                i += length;
+                continue;
+            }
+            if matches!(c, '(' | ')' | ':') {
+                if start < i {
+                    tokens.push(&arg[start..i]);
+                }
+                tokens.push(&arg[i..i + 1]);
+                start = i + 1;
+            }
+            i += c.len_utf8();
+        }
+        if start < arg.len() {
+            tokens.push(&arg[start..]);
         }
     }
+    resolve_terms(&tokens, env)
+}
 
-    match env.call_function(func_name, &converted_args) {
-        Ok(return_type) => Ok(format!(
-            "Called function {} with return type {:?}",
-            func_name, return_type
-        )),
-        Err(err) => Err(err),
+fn resolve_terms(tokens: &[&str], env: &Environment) -> Result<Vec<Type>, Error> {
+    let mut converted_args = Vec::new();
+    let mut rest = tokens;
+    while !rest.is_empty() {
+        match rest {
+            ["(", name, ":", type_name, ")", tail @ ..] => {
+                let actual = resolve_argument(name, env)?;
+                let ascribed = parse_type(type_name, env)?;
+                if !env.can_coerce(&actual, &ascribed) {
+                    return Err(Error::AscriptionMismatch {
+                        expression: name.to_string(),
+                        actual,
+                        ascribed,
+                    });
+                }
+                converted_args.push(ascribed);
+                rest = tail;
+            }
+            ["(", tail @ ..] => {
+                let close = matching_paren(tail)
+                    .ok_or_else(|| Error::Syntax("missing `)` after a nested call".to_string()))?;
+                converted_args.push(resolve_group(&tail[..close], env)?);
+                rest = &tail[close + 1..];
+            }
+            [token @ (")" | ":"), ..] => {
+                return Err(Error::Syntax(format!(
+                    "unexpected `{}`, ascriptions look like `(NAME : Type)` and nested calls like `(f ARG...)`",
+                    token
+                )))
+            }
+            [arg, tail @ ..] => {
+                converted_args.push(resolve_argument(arg, env)?);
+                rest = tail;
+            }
+            [] => unreachable!(),
+        }
     }
+
+    Ok(converted_args)
 }
 
-fn declare_variable(input: &[&str], env: &mut Environment) -> Result<String, Error> {
-    if input.len() != 2 {
-        return Err(Error::TypeError);
+/// Index of the `)` closing a group whose `(` came just before `tokens`.
+fn matching_paren(tokens: &[&str]) -> Option<usize> {
+    let mut depth = 0;
+    for (i, token) in tokens.iter().enumerate() {
+        match *token {
+            "(" => depth += 1,
+            ")" if depth == 0 => return Some(i),
+            ")" => depth -= 1,
+            _ => {}
+        }
+    }
+    None
+}
+
+/// A parenthesized argument: a nested call, optionally spelled
+/// `(call f ARG...)`, whose result type becomes the argument, or else a
+/// type written in parentheses such as `(Either Int Bool)`.
+fn resolve_group(group: &[&str], env: &Environment) -> Result<Type, Error> {
+    let group = match group {
+        ["call", rest @ ..] => rest,
+        _ => group,
+    };
+    match group {
+        [] => Err(Error::Syntax("empty parentheses in a call".to_string())),
+        [function, args @ ..] if env.callable(function).is_some() => {
+            let args = resolve_terms(args, env)?;
+            if env.function(function).is_some() {
+                env.call_function(function, &args)
+            } else {
+                env.apply_function(function, &args)
+            }
+        }
+        [function, _, ..] if is_identifier_start(function) => {
+            Err(Error::UndeclaredFunction(function.to_string()))
+        }
+        _ => parse_type(&format!("({})", group.join(" ")), env),
+    }
+}
+
+fn is_identifier_start(token: &str) -> bool {
+    token.starts_with(|c: char| c.is_lowercase() || c == '_')
+}
+
+pub(crate) fn check_call(input: &[&str], env: &Environment) -> Result<Type, Error> {
+    if input.is_empty() {
+        return Err(Error::Syntax("missing function name".to_string()));
+    }
+
+    let func_name = input[0];
+    let converted_args = resolve_arguments(&input[1..], env)?;
+    env.call_function(func_name, &converted_args)
+}
+
+/// Like `check_call`, but records the call in the audit log: calls to
+/// undeclared functions so their signature can be inferred later, and
+/// successful calls for the call graph. `bound_to` names the variable a
+/// `bind` stores the result in.
+fn check_call_recorded(
+    input: &[&str],
+    bound_to: Option<&str>,
+    env: &mut Environment,
+) -> Result<Type, Error> {
+    let result = check_call(input, env);
+    match result {
+        Ok(_) => {
+            // Type names passed as arguments are not part of the data flow.
+            let args = input[1..]
+                .iter()
+                .map(|token| token.trim_matches(|c| c == '(' || c == ')'))
+                .filter(|token| env.variable(token).is_some())
+                .map(str::to_string)
+                .collect();
+            env.audit.push(AuditEntry::Call {
+                function: input[0].to_string(),
+                args,
+                result: bound_to.map(str::to_string),
+            });
+        }
+        Err(Error::UndeclaredFunction(_)) => {
+            if let Ok(args) = resolve_arguments(&input[1..], env) {
+                env.audit.push(AuditEntry::UndeclaredCall {
+                    function: input[0].to_string(),
+                    args,
+                });
+            }
+        }
+        Err(_) => {}
+    }
+    result
+}
+
+fn call_function(input: &[&str], env: &mut Environment) -> Result<String, Error> {
+    let return_type = match check_call_recorded(input, None, env) {
+        // In deferred mode the recorded call stands in for the declaration.
+        Err(Error::UndeclaredFunction(name)) if env.deferred => {
+            return Ok(format!("Deferred call to {}, see `obligations`", name))
+        }
+        result => result?,
+    };
+    // A frozen environment still typechecks calls, it just stops tracking `it`.
+    if !env.is_frozen() {
+        let result_variable = env.result_variable.clone();
+        env.declare_variable(&result_variable, return_type.clone())?;
+    }
+    Ok(format!(
+        "Called function {} with return type {}",
+        input[0],
+        format_elided(&displayed(&return_type, env), env.display_depth)
+    ))
+}
+
+fn apply_function(input: &[&str], env: &mut Environment) -> Result<String, Error> {
+    let args = resolve_arguments(&input[1..], env)?;
+    let result = env.apply_function(input[0], &args)?;
+    if !env.is_frozen() {
+        let result_variable = env.result_variable.clone();
+        env.declare_variable(&result_variable, result.clone())?;
+    }
+    let arity = match env.function(input[0]) {
+        Some((_, input_types)) => input_types.len(),
+        None => uncurried(&env.variable(input[0]).unwrap_or(Type::Unit))
+            .1
+            .len(),
+    };
+    let verb = if args.len() < arity {
+        "Partially applied"
+    } else {
+        "Applied"
+    };
+    Ok(format!(
+        "{} {} with result type {}",
+        verb,
+        input[0],
+        format_elided(&displayed(&result, env), env.display_depth)
+    ))
+}
+
+/// `compose f g [as h]`: the function applying `f` to the result of `g`,
+/// taking `g`'s parameters and then any further parameters of `f`.
+fn compose_functions(input: &[&str], env: &mut Environment) -> Result<String, Error> {
+    let (outer, inner) = (input[0], input[1]);
+    let bound_to = match input[2..] {
+        [] => None,
+        ["as", name] => Some(name),
+        _ => return Err(usage_error(env, "compose", "only `as NAME` may follow")),
+    };
+    let (_, outer_inputs) = env
+        .callable(outer)
+        .ok_or_else(|| Error::UndeclaredFunction(outer.to_string()))?;
+    let (inner_output, inner_inputs) = env
+        .callable(inner)
+        .ok_or_else(|| Error::UndeclaredFunction(inner.to_string()))?;
+    if outer_inputs.is_empty() {
+        return Err(Error::ArityMismatch {
+            function: outer.to_string(),
+            expected: 0,
+            got: 1,
+        });
     }
 
+    // `\x1 ... xn -> f (g x1 ... xn)`, with parameter names no declaration
+    // can shadow.
+    let parameters: Vec<String> = (0..inner_inputs.len()).map(|i| format!("'{}", i)).collect();
+    let body = Expr::apply(
+        Expr::name(outer),
+        vec![Expr::apply(
+            Expr::name(inner),
+            parameters.iter().map(|p| Expr::name(p)).collect(),
+        )],
+    );
+    let composed = parameters.into_iter().rev().fold(body, |body, parameter| {
+        Expr::Lambda(parameter, None, Box::new(body))
+    });
+    let composed = match unify::infer(&composed, env) {
+        Err(Error::TypeMismatch { .. }) => {
+            return Err(Error::CompositionMismatch {
+                outer: outer.to_string(),
+                inner: inner.to_string(),
+                produced: inner_output,
+                expected: outer_inputs[0].clone(),
+            })
+        }
+        result => result?,
+    };
+
+    let (output_type, input_types) = uncurried(&composed);
+    let signature = format_signature(&input_types, &output_type);
+    match bound_to {
+        Some(name) => {
+            env.declare_function(name, input_types, output_type)?;
+            Ok(format!("{} :: {}", name, signature))
+        }
+        None => {
+            if !env.is_frozen() {
+                let result_variable = env.result_variable.clone();
+                env.declare_variable(&result_variable, composed)?;
+            }
+            Ok(format!("{} . {} :: {}", outer, inner, signature))
+        }
+    }
+}
+
+/// `check EXPR`: infers the type of an expression, nested applications
+/// and polymorphic functions included, without declaring anything.
+fn check_expression(input: &[&str], env: &mut Environment) -> Result<String, Error> {
+    let source = input.join(" ");
+    let expr = parser::parse_expr(&source, env)?;
+    let inferred = match unify::infer(&expr, env) {
+        Ok(inferred) => inferred,
+        Err(error) => {
+            return Err(match inference::minimal_failure(&expr, env) {
+                Some((part, error)) if part != expr => Error::InSubexpression {
+                    subexpression: parser::show_expr(&part),
+                    error: Box::new(error),
+                },
+                _ => error,
+            })
+        }
+    };
+    Ok(format!(
+        "{} :: {}",
+        source,
+        format_elided(&displayed(&inferred, env), env.display_depth)
+    ))
+}
+
+/// `assert_type EXPRESSION :: TYPE`: fails unless the expression's inferred
+/// type is `TYPE`, up to the names of its type variables.
+fn assert_type(input: &[&str], env: &mut Environment) -> Result<String, Error> {
+    let Some(split) = input.iter().rposition(|word| *word == "::") else {
+        return Err(usage_error(env, "assert_type", "missing `:: TYPE`"));
+    };
+    let source = input[..split].join(" ");
+    let expected = unify::normalize(&parser::annotation(&input[split + 1..].join(" "), env)?);
+    let actual = unify::infer(&parser::parse_expr(&source, env)?, env)?;
+    if actual != expected {
+        return Err(Error::AssertionFailed {
+            expression: source,
+            expected,
+            actual,
+        });
+    }
+    Ok(format!("ok: {} :: {}", source, actual))
+}
+
+/// `assert_error CODE COMMAND...`: fails unless the command fails with an
+/// error of that code. The command runs in a copy of the environment, so
+/// its declarations are dropped either way; files it saves or exports are
+/// still written.
+fn assert_error(input: &[&str], env: &mut Environment) -> Result<String, Error> {
+    let expected = input[0];
+    let command = input[1..].join(" ");
+    let actual = match process_input(&command, &mut env.clone()) {
+        Ok(_) => None,
+        Err(err) if err.code() == expected => {
+            return Ok(format!("ok: `{}` failed with {}", command, expected))
+        }
+        Err(err) => Some(err.code().to_string()),
+    };
+    Err(Error::ExpectedError {
+        command,
+        expected: expected.to_string(),
+        actual,
+    })
+}
+
+/// `run FILE [--tests]`: runs a script against the environment, or with
+/// `--tests` runs it in a copy and reports only its test blocks.
+fn run_script_file(input: &[&str], env: &mut Environment) -> Result<String, Error> {
+    let path = input[0];
+    let source = fs::read_to_string(path).map_err(|err| Error::Io {
+        path: path.to_string(),
+        message: err.to_string(),
+    })?;
+    match &input[1..] {
+        [] => {
+            let mut lines = Vec::new();
+            for (line, result) in script::process_script(&source, env) {
+                match result {
+                    Ok(output) if output.is_empty() => {}
+                    Ok(output) => lines.push(format!("line {}: {}", line, output)),
+                    Err(err) => lines.push(format!("line {}: Error: {}", line, err)),
+                }
+            }
+            Ok(lines.join("\n"))
+        }
+        ["--tests"] => {
+            let report = script::process_tests(&source, env);
+            let mut lines = Vec::new();
+            for (line, err) in &report.errors {
+                lines.push(format!("line {}: Error: {}", line, err));
+            }
+            for test in &report.tests {
+                lines.push(format!("line {}: {}", test.line, test.summary()));
+                for (line, err) in &test.failures {
+                    lines.push(format!("  line {}: {}", line, err));
+                }
+            }
+            let tests = report.tests.len();
+            let failed = report.tests.iter().filter(|test| !test.passed()).count();
+            let mut summary = format!(
+                "{} test(s), {} passed, {} failed",
+                tests,
+                tests - failed,
+                failed
+            );
+            if !report.errors.is_empty() {
+                summary.push_str(&format!(
+                    ", FAILED with {} error(s) outside tests",
+                    report.errors.len()
+                ));
+            }
+            lines.push(summary);
+            Ok(lines.join("\n"))
+        }
+        _ => Err(usage_error(
+            env,
+            "run",
+            "only `--tests` may follow the file",
+        )),
+    }
+}
+
+fn bind_result(input: &[&str], env: &mut Environment) -> Result<String, Error> {
+    let var_name = input[0];
+    let return_type = check_call_recorded(&input[3..], Some(var_name), env)?;
+    env.declare_variable(var_name, return_type.clone())?;
+    Ok(format!("{} :: {}", var_name, return_type))
+}
+
+fn declare_variable(input: &[&str], env: &mut Environment) -> Result<String, Error> {
     let var_name = input[0];
-    let var_type = match input[1] {
-        "Int" => Type::Int,
-        "Float" => Type::Float,
-        "Bool" => Type::Bool,
-        _ => return Err(Error::TypeError),
+    // `declare_var x Int = 5` gives the variable a value as well.
+    let (type_tokens, value_tokens) = match input[1..].iter().position(|token| *token == "=") {
+        Some(i) => (&input[1..i + 1], Some(&input[i + 2..])),
+        None => (&input[1..], None),
+    };
+    let var_type = parse_type(&type_tokens.join(" "), env)?;
+    let Some(value_tokens) = value_tokens else {
+        env.declare_variable(var_name, var_type.clone())?;
+        return Ok(format!("{} :: {}", var_name, var_type));
     };
-    env.declare_variable(var_name, var_type);
-    Ok(format!("{} :: {:?}", var_name, var_type))
+    let literal = value_tokens.join(" ");
+    let value = Value::from_literal(&literal).ok_or_else(|| {
+        usage_error(
+            env,
+            "declare_var",
+            format!("`{}` is not a literal value", literal),
+        )
+    })?;
+    env.define_variable(var_name, var_type.clone(), value)?;
+    // Shown as stored, an `Int` for a `Float` widened.
+    let stored = env
+        .value(var_name)
+        .map(Value::to_string)
+        .unwrap_or_default();
+    Ok(format!("{} :: {} = {}", var_name, var_type, stored))
 }
 
 fn declare_function(input: &[&str], env: &mut Environment) -> Result<String, Error> {
-    if input.len() != 3 {
-        return Err(Error::TypeError);
+    let func_name = input[0];
+    // The last type is the return type, every type before it is a parameter.
+    let mut types = parse_types(&input[1..].join(" "), env)?;
+    let output_type = types.pop().unwrap();
+    if types.is_empty() {
+        return infer_function(func_name, output_type, env);
     }
 
-    let func_name = input[0];
-    let input_type = match input[1] {
-        "Int" => Type::Int,
-        "Float" => Type::Float,
-        "Bool" => Type::Bool,
-        _ => return Err(Error::TypeError),
+    let signature = format_signature(&types, &output_type);
+    env.declare_function(func_name, types, output_type)?;
+    Ok(format!("{} :: {}", func_name, signature))
+}
+
+// `declare_func f RETURN` leaves the parameters to be inferred from the calls
+// to `f` recorded while it was undeclared.
+fn infer_function(name: &str, output_type: Type, env: &mut Environment) -> Result<String, Error> {
+    let candidates = inference::rank_candidates(name, output_type.clone(), &env.audit);
+    match candidates.as_slice() {
+        [] => Err(Error::NoInferenceCandidates(name.to_string())),
+        [only] => {
+            let signature = format_signature(&only.input_types, &output_type);
+            env.declare_function(name, only.input_types.clone(), output_type)?;
+            Ok(format!("{} :: {} (inferred)", name, signature))
+        }
+        many => {
+            let lines = many
+                .iter()
+                .enumerate()
+                .map(|(i, candidate)| {
+                    format!(
+                        "  {}. {} :: {}  ({:.0}%, {} call(s))",
+                        i + 1,
+                        name,
+                        format_signature(&candidate.input_types, &output_type),
+                        candidate.confidence * 100.0,
+                        candidate.uses
+                    )
+                })
+                .collect::<Vec<String>>();
+            env.pending_candidates = Some((name.to_string(), candidates));
+            Ok(format!(
+                "Possible signatures for {}, pick one with `accept N`:\n{}",
+                name,
+                lines.join("\n")
+            ))
+        }
+    }
+}
+
+fn infer_from_calls(input: &[&str], env: &mut Environment) -> Result<String, Error> {
+    let name = input[0];
+    let input_types = inference::most_general(name, env)?;
+    let Some(output_type) = input.get(1).map(|ret| parse_type(ret, env)).transpose()? else {
+        let params = input_types
+            .iter()
+            .map(|t| format!("{} -> ", t))
+            .collect::<String>();
+        return Ok(format!(
+            "{} :: {}?\nName the return type with `infer_from_calls {} RETURN`",
+            name, params, name
+        ));
+    };
+
+    let signature = format_signature(&input_types, &output_type);
+    let uses = env
+        .audit
+        .iter()
+        .filter(|entry| matches!(entry, AuditEntry::UndeclaredCall { function, .. } if function == name))
+        .count();
+    env.pending_candidates = Some((
+        name.to_string(),
+        vec![inference::Candidate {
+            input_types,
+            output_type,
+            uses,
+            confidence: 1.0,
+        }],
+    ));
+    Ok(format!(
+        "{} :: {}\nDeclare it with `accept 1`",
+        name, signature
+    ))
+}
+
+fn set_deferred(input: &[&str], env: &mut Environment) -> Result<String, Error> {
+    match input {
+        ["on"] => env.deferred = true,
+        ["off"] => env.deferred = false,
+        _ => return Err(usage_error(env, "deferred", "expected on or off")),
+    }
+    Ok(format!("Deferred mode {}", input[0]))
+}
+
+/// Lists every function that was called while undeclared and still is,
+/// with the signature its calls constrain it to.
+fn list_obligations(_input: &[&str], env: &Environment) -> Result<String, Error> {
+    let mut names: Vec<&str> = env
+        .audit
+        .iter()
+        .filter_map(|entry| match entry {
+            AuditEntry::UndeclaredCall { function, .. } => Some(function.as_str()),
+            _ => None,
+        })
+        .filter(|name| env.function(name).is_none())
+        .collect();
+    names.sort_unstable();
+    names.dedup();
+
+    Ok(names
+        .into_iter()
+        .map(|name| match inference::most_general(name, env) {
+            Ok(input_types) => format!(
+                "{} :: {}?",
+                name,
+                input_types
+                    .iter()
+                    .map(|t| format!("{} -> ", t))
+                    .collect::<String>()
+            ),
+            Err(err) => format!("{} :: ?  ({})", name, err),
+        })
+        .collect::<Vec<String>>()
+        .join("\n"))
+}
+
+fn accept_candidate(input: &[&str], env: &mut Environment) -> Result<String, Error> {
+    let (name, candidates) = env
+        .pending_candidates
+        .as_ref()
+        .ok_or(Error::NoPendingCandidates)?;
+    let index = input[0]
+        .parse::<usize>()
+        .ok()
+        .filter(|n| (1..=candidates.len()).contains(n))
+        .ok_or(Error::NoPendingCandidates)?;
+
+    let name = name.clone();
+    let candidate = candidates[index - 1].clone();
+    env.declare_function(
+        &name,
+        candidate.input_types.clone(),
+        candidate.output_type.clone(),
+    )?;
+    env.audit.push(AuditEntry::AcceptedInference {
+        function: name.clone(),
+        input_types: candidate.input_types.clone(),
+        output_type: candidate.output_type.clone(),
+    });
+    env.pending_candidates = None;
+    Ok(format!(
+        "{} :: {}",
+        name,
+        format_signature(&candidate.input_types, &candidate.output_type)
+    ))
+}
+
+// `declare_vars x,y Int` / `declare_funcs f,g Int Int` run the single-name
+// declaration once per comma-separated name and report each on its own line.
+// They run on a copy, so when one name fails none of them is declared.
+fn declare_many(
+    input: &[&str],
+    env: &mut Environment,
+    declare: fn(&[&str], &mut Environment) -> Result<String, Error>,
+) -> Result<String, Error> {
+    let mut declared = env.clone();
+    let mut reports = Vec::new();
+    for name in input[0].split(',').filter(|name| !name.is_empty()) {
+        let mut tokens = vec![name];
+        tokens.extend_from_slice(&input[1..]);
+        reports.push(declare(&tokens, &mut declared)?);
+    }
+    *env = declared;
+    Ok(reports.join("\n"))
+}
+
+/// Parses the terse `name :: T1 -> T2` form. A single type declares a
+/// variable, more than one declares a function returning the last type.
+pub(crate) fn parse_signature(line: &str, env: &Environment) -> Result<(String, Vec<Type>), Error> {
+    parse_signature_with(line, env, &[])
+}
+
+/// Like `parse_signature`, also accepting the `pending` type names of a
+/// block that declares them.
+pub(crate) fn parse_signature_with(
+    line: &str,
+    env: &Environment,
+    pending: &[(&str, Type)],
+) -> Result<(String, Vec<Type>), Error> {
+    let (name, signature) = line
+        .split_once("::")
+        .ok_or_else(|| Error::Syntax(format!("expected `name :: Type`, got `{}`", line)))?;
+    let name = name.trim();
+    if name.is_empty() || name.contains(char::is_whitespace) {
+        return Err(Error::Syntax(format!("invalid name `{}`", name)));
+    }
+
+    let types = split_arrows(signature)
+        .into_iter()
+        .map(|part| {
+            let mut types = parse_types_with(part, env, pending)?;
+            match types.len() {
+                1 => Ok(types.pop().unwrap()),
+                _ => Err(Error::Syntax(format!(
+                    "expected one type, found `{}`",
+                    part.trim()
+                ))),
+            }
+        })
+        .collect::<Result<Vec<Type>, Error>>()?;
+    Ok((name.to_string(), types))
+}
+
+/// Splits a signature at its top-level arrows, leaving those inside
+/// brackets to the type parser.
+fn split_arrows(signature: &str) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut depth = 0i32;
+    let mut start = 0;
+    for (i, c) in signature.char_indices() {
+        match c {
+            '(' | '[' => depth += 1,
+            ')' | ']' => depth -= 1,
+            '-' if depth == 0 && signature[i..].starts_with("->") => {
+                parts.push(&signature[start..i]);
+                start = i + 2;
+            }
+            _ => {}
+        }
+    }
+    parts.push(&signature[start..]);
+    parts
+}
+
+/// Parses the body of `declare_type NAME = BODY`: either constructors
+/// separated by `|`, each a capitalized name followed by its field types, or
+/// a record `{ name: Type, ... }`.
+pub(crate) fn parse_type_definition(
+    body: &str,
+    env: &Environment,
+    pending: &[(&str, Type)],
+) -> Result<TypeDefinition, Error> {
+    let body = body.trim();
+    if let Some(fields) = body.strip_prefix('{') {
+        let fields = fields
+            .strip_suffix('}')
+            .ok_or_else(|| Error::Syntax("missing `}` after the record fields".to_string()))?;
+        return parse_record_fields(fields, env, pending).map(TypeDefinition::Record);
+    }
+
+    body.split('|')
+        .map(|alternative| {
+            let alternative = alternative.trim();
+            let (constructor, fields) = alternative
+                .split_once(char::is_whitespace)
+                .unwrap_or((alternative, ""));
+            if !constructor.starts_with(char::is_uppercase)
+                || !constructor.chars().all(|c| c.is_alphanumeric() || c == '_')
+            {
+                return Err(Error::Syntax(format!(
+                    "expected a capitalized constructor name, got `{}`",
+                    constructor
+                )));
+            }
+            Ok((
+                constructor.to_string(),
+                parse_types_with(fields, env, pending)?,
+            ))
+        })
+        .collect::<Result<Vec<_>, Error>>()
+        .map(TypeDefinition::Sum)
+}
+
+fn parse_record_fields(
+    fields: &str,
+    env: &Environment,
+    pending: &[(&str, Type)],
+) -> Result<Vec<(String, Type)>, Error> {
+    let mut parsed: Vec<(String, Type)> = Vec::new();
+    for field in fields.split(',').map(str::trim).filter(|f| !f.is_empty()) {
+        let (name, field_type) = field
+            .split_once(':')
+            .ok_or_else(|| Error::Syntax(format!("expected `name: Type`, got `{}`", field)))?;
+        let name = name.trim();
+        if !name.starts_with(|c: char| c.is_lowercase() || c == '_')
+            || !name.chars().all(|c| c.is_alphanumeric() || c == '_')
+        {
+            return Err(Error::Syntax(format!(
+                "field names start with a lowercase letter, got `{}`",
+                name
+            )));
+        }
+        if parsed.iter().any(|(seen, _)| seen == name) {
+            return Err(Error::Syntax(format!("field `{}` is declared twice", name)));
+        }
+        let mut types = parse_types_with(field_type, env, pending)?;
+        if types.len() != 1 {
+            return Err(Error::Syntax(format!(
+                "expected one type for field `{}`, found `{}`",
+                name,
+                field_type.trim()
+            )));
+        }
+        parsed.push((name.to_string(), types.pop().unwrap()));
+    }
+    if parsed.is_empty() {
+        return Err(Error::Syntax(
+            "a record needs at least one field".to_string(),
+        ));
+    }
+    Ok(parsed)
+}
+
+/// Parses `NAME = BODY`, see [`parse_type_definition`]. The name itself is
+/// always accepted in the body, so types may be recursive.
+pub(crate) fn parse_type_line<'a>(
+    line: &'a str,
+    env: &Environment,
+    pending: &[(&str, Type)],
+) -> Result<(&'a str, TypeDefinition), Error> {
+    let (name, body) = line
+        .split_once('=')
+        .ok_or_else(|| Error::Syntax(format!("expected `Name = Body`, got `{}`", line)))?;
+    let name = name.trim();
+    if !name.starts_with(char::is_uppercase)
+        || !name.chars().all(|c| c.is_alphanumeric() || c == '_')
+    {
+        return Err(Error::Syntax(format!(
+            "type names are capitalized, got `{}`",
+            name
+        )));
+    }
+    let mut pending = pending.to_vec();
+    pending.push((name, Type::Named(name.to_string())));
+    Ok((name, parse_type_definition(body, env, &pending)?))
+}
+
+/// Parses `NAME = TYPE` for an alias.
+pub(crate) fn parse_alias_line<'a>(
+    line: &'a str,
+    env: &Environment,
+    pending: &[(&str, Type)],
+) -> Result<(&'a str, Type), Error> {
+    let (name, target) = line
+        .split_once('=')
+        .ok_or_else(|| Error::Syntax(format!("expected `Name = Type`, got `{}`", line)))?;
+    let name = name.trim();
+    if !name.starts_with(char::is_uppercase)
+        || !name.chars().all(|c| c.is_alphanumeric() || c == '_')
+    {
+        return Err(Error::Syntax(format!(
+            "type names are capitalized, got `{}`",
+            name
+        )));
+    }
+    let mut types = parse_types_with(target, env, pending)?;
+    if types.len() != 1 {
+        return Err(Error::Syntax(format!(
+            "expected one type, found `{}`",
+            target.trim()
+        )));
+    }
+    Ok((name, types.pop().unwrap()))
+}
+
+/// Whether a declarations-block line is an operator declaration such as
+/// `infixl 6 (<+>) :: Int -> Int -> Int`, which a signature of something
+/// named `infix` is not.
+pub(crate) fn is_operator_line(line: &str) -> bool {
+    let mut words = line.split_whitespace();
+    matches!(words.next(), Some("infixl" | "infixr" | "infix"))
+        && words.next().is_some_and(|word| !word.starts_with("::"))
+}
+
+/// Parses an `infixl|infixr|infix PRECEDENCE (SYMBOL) :: LEFT -> RIGHT ->
+/// RETURN` line into a user operator.
+pub(crate) fn parse_operator_line(
+    line: &str,
+    env: &Environment,
+    pending: &[(&str, Type)],
+) -> Result<Operator, Error> {
+    let malformed = || {
+        Error::Syntax(format!(
+            "expected `infixl|infixr|infix PRECEDENCE (SYMBOL) :: TYPE -> TYPE -> RETURN`, got `{}`",
+            line
+        ))
     };
-    let output_type = match input[2] {
-        "Int" => Type::Int,
-        "Float" => Type::Float,
-        "Bool" => Type::Bool,
-        _ => return Err(Error::TypeError),
+    let (fixity, rest) = line.split_once(' ').ok_or_else(malformed)?;
+    let associativity = match fixity {
+        "infixl" => Associativity::Left,
+        "infixr" => Associativity::Right,
+        "infix" => Associativity::None,
+        _ => return Err(malformed()),
     };
+    let (precedence, signature) = rest.trim_start().split_once(' ').ok_or_else(malformed)?;
+    let precedence = precedence
+        .parse::<u8>()
+        .ok()
+        .filter(|precedence| *precedence <= 9)
+        .ok_or_else(|| Error::Syntax("precedence must be 0 to 9".to_string()))?;
+    let (symbol, types) = parse_signature_with(signature, env, pending)?;
+    let symbol = symbol
+        .strip_prefix('(')
+        .and_then(|symbol| symbol.strip_suffix(')'))
+        .filter(|symbol| expr::is_operator_symbol(symbol))
+        .ok_or_else(malformed)?;
+    let [left, right, output] = <[Type; 3]>::try_from(types)
+        .map_err(|_| Error::Syntax(format!("operator `{}` takes exactly two operands", symbol)))?;
+    Ok(Operator {
+        symbol: symbol.to_string(),
+        precedence,
+        associativity,
+        left,
+        right,
+        output,
+        note: "user-declared".to_string(),
+    })
+}
 
-    env.declare_function(func_name, input_type, output_type);
+pub(crate) fn declare_alias(
+    name: &str,
+    target: Type,
+    env: &mut Environment,
+) -> Result<String, Error> {
+    let report = format!("alias {} = {}", name, target);
+    env.declare_alias(name, target)?;
+    Ok(report)
+}
+
+fn alias_command(input: &[&str], env: &mut Environment) -> Result<String, Error> {
+    let line = input.join(" ");
+    let (name, target) = parse_alias_line(&line, env, &[])?;
+    declare_alias(name, target, env)
+}
+
+pub(crate) fn declare_type_definition(
+    name: &str,
+    definition: TypeDefinition,
+    env: &mut Environment,
+) -> Result<String, Error> {
+    let report = format!("{} {} = {}", definition.keyword(), name, definition);
+    env.declare_type(name, definition)?;
+    Ok(report)
+}
+
+/// `field p x`: the type of field `x` of the record `p`.
+fn access_field(input: &[&str], env: &mut Environment) -> Result<String, Error> {
+    let target_type = resolve_argument(input[0], env)?;
+    let field = input[1];
+    let definition = match &target_type {
+        Type::Named(name) => env.type_definition(name),
+        _ => None,
+    };
+    let field_type = match definition {
+        Some(TypeDefinition::Record(fields)) => match definition.and_then(|d| d.field(field)) {
+            Some(field_type) => field_type.clone(),
+            None => {
+                return Err(Error::UnknownField {
+                    type_name: target_type.to_string(),
+                    field: field.to_string(),
+                    suggestions: suggest::suggestions(
+                        field,
+                        fields.iter().map(|(name, _)| name.as_str()),
+                    ),
+                })
+            }
+        },
+        _ => return Err(Error::NotARecord(target_type)),
+    };
+    if !env.is_frozen() {
+        let result_variable = env.result_variable.clone();
+        env.declare_variable(&result_variable, field_type.clone())?;
+    }
+    Ok(format!("{}.{} :: {}", input[0], field, field_type))
+}
+
+/// Wraps a `NAME = TYPE` line parsed like an alias into a newtype.
+pub(crate) fn newtype_definition(name: &str, wrapped: Type) -> TypeDefinition {
+    TypeDefinition::Newtype {
+        constructor: name.to_string(),
+        wrapped,
+    }
+}
+
+fn declare_newtype(input: &[&str], env: &mut Environment) -> Result<String, Error> {
+    let line = input.join(" ");
+    let (name, wrapped) = parse_alias_line(&line, env, &[])?;
+    declare_type_definition(name, newtype_definition(name, wrapped), env)
+}
+
+fn declare_type(input: &[&str], env: &mut Environment) -> Result<String, Error> {
+    let line = input.join(" ");
+    let (name, definition) = parse_type_line(&line, env, &[])?;
+    declare_type_definition(name, definition, env)
+}
+
+pub(crate) fn declare_signature(
+    name: &str,
+    mut types: Vec<Type>,
+    env: &mut Environment,
+) -> Result<String, Error> {
+    let output_type = types.pop().unwrap();
+    if types.is_empty() {
+        env.declare_variable(name, output_type.clone())?;
+        Ok(format!("{} :: {}", name, output_type))
+    } else {
+        let signature = format_signature(&types, &output_type);
+        env.declare_function(name, types, output_type)?;
+        Ok(format!("{} :: {}", name, signature))
+    }
+}
+
+fn import_signatures(input: &[&str], env: &mut Environment) -> Result<String, Error> {
+    let path = input[1];
+    let source = fs::read_to_string(path).map_err(|err| Error::Io {
+        path: path.to_string(),
+        message: err.to_string(),
+    })?;
+    let lines: Vec<(usize, &str)> = source
+        .lines()
+        .enumerate()
+        .map(|(i, l)| (i + 1, l))
+        .collect();
+    script::process_decl_block(&lines, env).map_err(|errors| Error::ImportFailed {
+        path: path.to_string(),
+        errors,
+    })
+}
+
+fn export_environment(input: &[&str], env: &mut Environment) -> Result<String, Error> {
+    // `--tag TAG` at the end narrows the export to the tagged declarations.
+    let tagged;
+    let (env, input): (&Environment, &[&str]) = match input {
+        [rest @ .., "--tag", tag] => {
+            tagged = env.tagged(tag);
+            (&tagged, rest)
+        }
+        _ => (env, input),
+    };
+    let (contents, path) =
+        match input {
+            ["md", path] => (export::markdown(env.view(), env.display_width), *path),
+            ["script", path] => (export::script(env.view()), *path),
+            ["callgraph", "dot"] => return Ok(export::callgraph_dot(env.view(), &env.audit)),
+            ["callgraph", "dot", path] => (export::callgraph_dot(env.view(), &env.audit), *path),
+            ["stats", "json"] => return Ok(export::stats_json(env.view(), &env.operators)),
+            ["stats", "json", path] => (export::stats_json(env.view(), &env.operators), *path),
+            _ => return Err(usage_error(
+                env,
+                "export",
+                "expected `md FILE`, `script FILE`, `callgraph dot [FILE]` or `stats json [FILE]`",
+            )),
+        };
+    fs::write(path, contents).map_err(|err| Error::Io {
+        path: path.to_string(),
+        message: err.to_string(),
+    })?;
+    Ok(format!("Exported to {}", path))
+}
+
+fn annotate_file(input: &[&str], env: &mut Environment) -> Result<String, Error> {
+    // Only free-form pseudocode is understood so far.
+    if input.len() > 1 && input[1..] != ["--lang", "pseudo"] {
+        let language = input.get(2).copied().unwrap_or("");
+        return Err(usage_error(
+            env,
+            "annotate",
+            format!("unsupported language `{}`", language),
+        ));
+    }
+
+    let path = input[0];
+    let source = fs::read_to_string(path).map_err(|err| Error::Io {
+        path: path.to_string(),
+        message: err.to_string(),
+    })?;
+    script::annotate(&source, env)
+}
+
+fn load_file(input: &[&str], env: &mut Environment) -> Result<String, Error> {
+    let mut selection = session::Selection::default();
+    let mut replace = false;
+    let mut flags = input[1..].iter();
+    while let Some(flag) = flags.next() {
+        match *flag {
+            "--only" => match flags.next() {
+                Some(pattern) => selection.only = Some(pattern.to_string()),
+                None => return Err(usage_error(env, "load", "`--only` needs a pattern")),
+            },
+            "--exclude-vars" => selection.exclude_variables = true,
+            "--force" => selection.force = true,
+            "--replace" => replace = true,
+            "--yes" => {}
+            other => {
+                return Err(usage_error(
+                    env,
+                    "load",
+                    format!("unknown flag `{}`", other),
+                ))
+            }
+        }
+    }
+    if replace {
+        if selection.only.is_some() || selection.exclude_variables {
+            return Err(usage_error(
+                env,
+                "load",
+                "`--replace` restores the whole snapshot and takes no selection",
+            ));
+        }
+        confirm(
+            env,
+            input,
+            format!("Replace the current declarations with {}?", input[0]),
+        )?;
+        return session::restore_file(input[0], selection.force, env);
+    }
+    confirm(
+        env,
+        input,
+        format!("Merge {} into the current declarations?", input[0]),
+    )?;
+    session::load_file(input[0], &selection, env)
+}
+
+/// `save FILE`: writes every declaration to a snapshot file.
+fn save_file(input: &[&str], env: &mut Environment) -> Result<String, Error> {
+    session::save_file(input[0], env)
+}
+
+/// `definition NAME` and `references NAME`, answered from the project in
+/// the current directory.
+fn find_definition(input: &[&str], _env: &mut Environment) -> Result<String, Error> {
+    let index = project::index(Path::new("."))?;
+    let location = index.definition(input[0])?;
     Ok(format!(
-        "{} :: {:#?} -> {:?}",
-        func_name, input_type, output_type
+        "{}:{}: {}",
+        location.file, location.line, location.text
     ))
 }
 
-fn show_declaration(input: &[&str], env: &Environment) -> Result<String, Error> {
-    if input.len() != 1 {
-        return Err(Error::TypeError);
+fn find_references(input: &[&str], _env: &mut Environment) -> Result<String, Error> {
+    let index = project::index(Path::new("."))?;
+    let references = index.references(input[0])?;
+    if references.is_empty() {
+        return Ok(format!("`{}` is never referenced", input[0]));
+    }
+    Ok(references
+        .iter()
+        .map(|location| format!("{}:{}: {}", location.file, location.line, location.text))
+        .collect::<Vec<String>>()
+        .join("\n"))
+}
+
+/// Asks before a destructive command goes ahead, when the environment has
+/// declarations to lose and its host can ask: fails with
+/// `Error::ConfirmationRequired` unless `--yes` is among `args`.
+fn confirm(env: &Environment, args: &[&str], question: String) -> Result<(), Error> {
+    if env.confirm_destructive && env.has_declarations() && !args.contains(&"--yes") {
+        return Err(Error::ConfirmationRequired { question });
     }
+    Ok(())
+}
 
+/// `delete NAME [--yes]`: removes a variable or function declaration.
+fn delete_declaration(input: &[&str], env: &mut Environment) -> Result<String, Error> {
     let name = input[0];
+    if env.is_constructor(name) {
+        return Err(Error::Syntax(format!(
+            "`{}` is a constructor and goes away only with its type",
+            name
+        )));
+    }
+    let mut removed = Vec::new();
     if let Some(var_type) = env.variables.get(name) {
-        Ok(format!("{} :: {:?}", name, var_type))
-    } else if let Some((output_type, input_types)) = env.functions.get(name) {
-        let input_types_str = input_types
+        removed.push(format!("{} :: {}", name, var_type));
+    }
+    if let Some((output_type, input_types)) = env.functions.get(name) {
+        removed.push(format!(
+            "{} :: {}",
+            name,
+            format_signature(input_types, output_type)
+        ));
+    }
+    if removed.is_empty() {
+        return Err(Error::UnknownSymbol {
+            name: name.to_string(),
+            suggestions: suggest::suggestions(
+                name,
+                env.variables
+                    .keys()
+                    .chain(env.functions.keys())
+                    .map(String::as_str),
+            ),
+        });
+    }
+    confirm(env, input, format!("Delete `{}`?", removed.join("` and `")))?;
+    if env.variables.contains_key(name) {
+        env.remove_variable(name)?;
+    }
+    if env.functions.contains_key(name) {
+        env.remove_function(name)?;
+    }
+    Ok(format!("Deleted {}", removed.join(", ")))
+}
+
+/// `fuzz_sig NAME`: reports the perturbed calls `NAME` still accepts.
+fn fuzz_signature(input: &[&str], env: &mut Environment) -> Result<String, Error> {
+    let name = input[0];
+    let report = fuzz::fuzz(name, env)?;
+    let source = if report.from_signature {
+        "no recorded calls, perturbed the signature".to_string()
+    } else {
+        format!("{} recorded call(s)", report.calls.len())
+    };
+    let mut lines = vec![format!(
+        "{}: {}, {} of {} perturbation(s) accepted",
+        name,
+        source,
+        report.accepted.len(),
+        report.tried
+    )];
+    for (perturbation, args) in &report.accepted {
+        let args = args
             .iter()
-            .map(|t| format!("{:?}", t))
-            .collect::<Vec<String>>()
-            .join(" -> ");
+            .map(|arg| Argument(arg).to_string())
+            .collect::<Vec<String>>();
+        let why = match perturbation {
+            fuzz::Perturbation::Replaced {
+                position,
+                replacement,
+            } => format!(
+                "parameter {} `{}` took `{}`",
+                position + 1,
+                report.parameters[*position],
+                replacement
+            ),
+            fuzz::Perturbation::Swapped(i, j) => {
+                format!("parameters {} and {} swapped", i + 1, j + 1)
+            }
+            fuzz::Perturbation::Dropped => "last argument dropped".to_string(),
+            fuzz::Perturbation::Extended => "extra argument".to_string(),
+        };
+        lines.push(format!(
+            "  accepted: {} {}  -- {}",
+            name,
+            args.join(" "),
+            why
+        ));
+    }
+    Ok(lines.join("\n"))
+}
+
+/// `reset [--yes]`: back to the builtins.
+fn reset_environment(input: &[&str], env: &mut Environment) -> Result<String, Error> {
+    confirm(
+        env,
+        input,
+        "Discard every declaration and keep only the builtins?".to_string(),
+    )?;
+    env.reset()?;
+    Ok("Environment reset to the builtins".to_string())
+}
+
+/// `clear-all [--yes]`: an environment with nothing in it at all.
+fn clear_environment(input: &[&str], env: &mut Environment) -> Result<String, Error> {
+    // Only the builtins would be lost, which is worth asking about too.
+    if env.confirm_destructive && !input.contains(&"--yes") {
+        return Err(Error::ConfirmationRequired {
+            question: "Discard every declaration, builtins included?".to_string(),
+        });
+    }
+    env.clear_all()?;
+    Ok("Environment cleared, builtins included".to_string())
+}
+
+/// `rename OLD NEW`: moves a variable or function declaration.
+fn rename_declaration(input: &[&str], env: &mut Environment) -> Result<String, Error> {
+    let (old, new) = (input[0], input[1]);
+    if env.is_constructor(old) {
+        return Err(Error::Syntax(format!(
+            "`{}` is a constructor and can only be renamed in its type",
+            old
+        )));
+    }
+    match env.rename(old, new) {
+        Ok(()) => Ok(format!("Renamed {} to {}", old, new)),
+        Err(Error::UnknownSymbol { name, .. }) => Err(Error::UnknownSymbol {
+            suggestions: suggest::suggestions(
+                &name,
+                env.variables
+                    .keys()
+                    .chain(env.functions.keys())
+                    .map(String::as_str),
+            ),
+            name,
+        }),
+        Err(err) => Err(err),
+    }
+}
+
+pub(crate) fn tag_declaration(input: &[&str], env: &mut Environment) -> Result<String, Error> {
+    let name = input[0];
+    if !env.is_declared(name) {
+        return Err(Error::UnknownSymbol {
+            name: name.to_string(),
+            suggestions: Vec::new(),
+        });
+    }
+    env.ensure_mutable()?;
+    let tags = env.tags.entry(name.to_string()).or_default();
+    tags.extend(input[1].split(',').map(str::to_string));
+    let tags = env.tags_of(name).into_iter().collect::<Vec<String>>();
+    Ok(format!("{} tagged {}", name, tags.join(", ")))
+}
+
+/// `list [--tag TAG]`: every user declaration, with its tags, or only those
+/// tagged `TAG`.
+fn list_declarations(input: &[&str], env: &mut Environment) -> Result<String, Error> {
+    let tag = match input {
+        [] => None,
+        ["--tag", tag] => Some(*tag),
+        _ => return Err(usage_error(env, "list", "only `--tag TAG` may follow")),
+    };
+    let width = env.display_width;
+    let builtins = declarations(&Environment::with_builtins(env.builtins().clone()), width);
+    let lines = declarations(env, width)
+        .into_iter()
+        .filter(|(key, declaration)| builtins.get(key) != Some(declaration))
+        .filter_map(|(key, declaration)| {
+            let name = key.trim_start_matches("type ");
+            let tags = env.tags_of(name);
+            if tag.is_some_and(|tag| !tags.contains(tag)) {
+                return None;
+            }
+            Some(if tags.is_empty() {
+                declaration
+            } else {
+                let tags = tags.into_iter().collect::<Vec<String>>();
+                format!("{}  [{}]", declaration, tags.join(", "))
+            })
+        })
+        .collect::<Vec<String>>();
+    Ok(lines.join("\n"))
+}
+
+fn session_command(input: &[&str], env: &mut Environment) -> Result<String, Error> {
+    match input {
+        ["save", name] => session::save_session(name, env),
+        ["load", name, flags @ ..]
+            if flags
+                .iter()
+                .all(|flag| matches!(*flag, "--force" | "--yes")) =>
+        {
+            confirm(
+                env,
+                flags,
+                format!("Replace the current declarations with session {}?", name),
+            )?;
+            session::load_session(name, flags.contains(&"--force"), env)
+        }
+        ["list"] => session::list_sessions(),
+        _ => Err(usage_error(env, "session", "expected save, load or list")),
+    }
+}
+
+fn show_declaration(input: &[&str], env: &Environment) -> Result<String, Error> {
+    let (name, depth) = match input {
+        ["--full", name] => (*name, None),
+        [name] => (*name, env.display_depth),
+        _ => {
+            return Err(usage_error(
+                env,
+                "show",
+                "only `--full` may come before the name",
+            ))
+        }
+    };
+    if let Some(operator) = env.operator(name.trim_start_matches('(').trim_end_matches(')')) {
         Ok(format!(
-            "{} :: {} -> {:?}",
-            name, input_types_str, output_type
+            "({}) :: {}\n    -- {}, precedence {}, {:?} associative",
+            operator.symbol,
+            operator.signature(),
+            operator.note,
+            operator.precedence,
+            operator.associativity
         ))
+    } else if let Some(target) = env.alias(name) {
+        Ok(format!("alias {} = {}", name, target))
+    } else if let Some(definition) = env.type_definition(name) {
+        Ok(pretty::type_declaration(
+            definition.keyword(),
+            name,
+            definition,
+            env.display_width,
+        ))
+    } else if let Some(var_type) = env.variable(name) {
+        let mut text = format!(
+            "{} :: {}",
+            name,
+            format_elided(&displayed(&var_type, env), depth)
+        );
+        if let Some(value) = env.value(name) {
+            text.push_str(&format!(" = {}", value));
+        }
+        Ok(text)
+    } else if let Some((output_type, input_types)) = env.function(name) {
+        let shown_inputs = input_types
+            .iter()
+            .map(|t| displayed(t, env))
+            .collect::<Vec<Type>>();
+        let mut text = pretty::signature(
+            name,
+            &pretty::signature_parts(&shown_inputs, &displayed(output_type, env), depth),
+            env.display_width,
+        );
+        // Only annotate builtins that have not been redeclared by the user.
+        if env.is_builtin(name, input_types, output_type) {
+            let note = prelude::builtin(name)
+                .filter(|_| prelude::is_builtin(name, input_types, output_type))
+                .map_or("builtin", |builtin| builtin.note);
+            text.push_str(&format!(
+                "\n    -- {} (from prelude {} {})",
+                note,
+                env.builtins().name(),
+                prelude::PRELUDE_VERSION
+            ));
+        }
+        Ok(text)
     } else {
-        Err(Error::UndeclaredVariable)
+        Err(Error::UndeclaredVariable(name.to_string()))
     }
 }
 
+fn list_types(_input: &[&str], env: &Environment) -> Result<String, Error> {
+    Ok(env
+        .type_names()
+        .into_iter()
+        .map(|name| match (env.type_definition(name), env.alias(name)) {
+            (Some(definition @ TypeDefinition::Newtype { .. }), _) => {
+                format!("{:<8} :: *    newtype  {}", name, definition)
+            }
+            (Some(definition), _) => format!("{:<8} :: *    user     {}", name, definition),
+            (_, Some(target)) => format!("{:<8} :: *    alias    {}", name, target),
+            _ => {
+                let description = prelude::BUILTIN_TYPES
+                    .iter()
+                    .find(|(builtin, _)| *builtin == name)
+                    .map_or("", |(_, description)| description);
+                format!("{:<8} :: *    builtin  {}", name, description)
+            }
+        })
+        .chain([
+            format!(
+                "{:<8} :: * -> * builtin  list of any type, written `[Int]`",
+                "[]"
+            ),
+            format!(
+                "{:<8} :: * -> * -> * builtin  one of two types, `Left` usually an error",
+                "Either"
+            ),
+            format!(
+                "{:<8} :: * -> ... builtin  tuple of two or more types, written `(Int, Bool)`",
+                "(,)"
+            ),
+        ])
+        .collect::<Vec<String>>()
+        .join("\n"))
+}
+
+fn begin_scope(_input: &[&str], env: &mut Environment) -> Result<String, Error> {
+    env.ensure_mutable()?;
+    env.push_scope();
+    Ok(format!("Opened scope {}", env.scope_depth()))
+}
+
+fn end_scope(_input: &[&str], env: &mut Environment) -> Result<String, Error> {
+    let depth = env.scope_depth();
+    env.pop_scope()?;
+    Ok(format!("Closed scope {}", depth))
+}
+
+fn freeze_environment(_input: &[&str], env: &mut Environment) -> Result<String, Error> {
+    env.freeze();
+    Ok("Environment frozen".to_string())
+}
+
+/// `declare_op SYMBOL PRECEDENCE left|right|none LEFT RIGHT RETURN`, or the
+/// Haskell-style `declare_op SYMBOL LEFT RIGHT RETURN infixl|infixr|infix
+/// PRECEDENCE`.
+fn declare_operator(input: &[&str], env: &mut Environment) -> Result<String, Error> {
+    let symbol = input[0];
+    let (precedence, associativity, types) = match input[4] {
+        "infixl" => (input[5], "left", &input[1..4]),
+        "infixr" => (input[5], "right", &input[1..4]),
+        "infix" => (input[5], "none", &input[1..4]),
+        fixity if fixity.starts_with("infix") => {
+            return Err(usage_error(
+                env,
+                "declare_op",
+                "fixity must be infixl, infixr or infix",
+            ))
+        }
+        _ => (input[1], input[2], &input[3..6]),
+    };
+    if !expr::is_operator_symbol(symbol) {
+        return Err(usage_error(
+            env,
+            "declare_op",
+            format!("`{}` is not an operator symbol", symbol),
+        ));
+    }
+    let precedence = precedence
+        .parse::<u8>()
+        .ok()
+        .filter(|precedence| *precedence <= 9)
+        .ok_or_else(|| usage_error(env, "declare_op", "precedence must be 0 to 9"))?;
+    let associativity = match associativity {
+        "left" => Associativity::Left,
+        "right" => Associativity::Right,
+        "none" => Associativity::None,
+        _ => {
+            return Err(usage_error(
+                env,
+                "declare_op",
+                "associativity must be left, right or none",
+            ))
+        }
+    };
+
+    let operator = Operator {
+        symbol: symbol.to_string(),
+        precedence,
+        associativity,
+        left: parse_type(types[0], env)?,
+        right: parse_type(types[1], env)?,
+        output: parse_type(types[2], env)?,
+        note: "user-declared".to_string(),
+    };
+    let report = format!("({}) :: {}", symbol, operator.signature());
+    env.declare_operator(operator)?;
+    Ok(report)
+}
+
+fn evaluate_expression(input: &[&str], env: &mut Environment) -> Result<String, Error> {
+    let result_type = expr::check_expression(&input.join(" "), env)?;
+    if !env.is_frozen() {
+        let result_variable = env.result_variable.clone();
+        env.declare_variable(&result_variable, result_type.clone())?;
+    }
+    Ok(format!("{} :: {}", input.join(" "), result_type))
+}
+
+fn list_operators(_input: &[&str], env: &Environment) -> Result<String, Error> {
+    let mut operators: Vec<_> = env.operators.iter().collect();
+    operators.sort_by_key(|op| (Reverse(op.precedence), op.symbol.clone()));
+    Ok(operators
+        .into_iter()
+        .map(|op| {
+            let suffix = match op.associativity {
+                Associativity::Left => "l",
+                Associativity::Right => "r",
+                Associativity::None => "",
+            };
+            format!(
+                "({:<3}) {:<8} :: {:<26} -- {}",
+                op.symbol,
+                format!("infix{} {}", suffix, op.precedence),
+                op.signature(),
+                op.note
+            )
+        })
+        .collect::<Vec<String>>()
+        .join("\n"))
+}
+
+fn set_timing(input: &[&str], env: &mut Environment) -> Result<String, Error> {
+    match input {
+        ["on"] => env.timing = true,
+        ["off"] => env.timing = false,
+        _ => return Err(usage_error(env, "timing", "expected on or off")),
+    }
+    Ok(format!("Timing {}", input[0]))
+}
+
+fn set_strict(input: &[&str], env: &mut Environment) -> Result<String, Error> {
+    match input {
+        ["on"] => env.strict_redeclaration = true,
+        ["off"] => env.strict_redeclaration = false,
+        _ => return Err(usage_error(env, "strict", "expected on or off")),
+    }
+    Ok(format!("Strict redeclaration {}", input[0]))
+}
+
+fn set_display_depth(input: &[&str], env: &mut Environment) -> Result<String, Error> {
+    env.display_depth = match input[0] {
+        "off" => None,
+        depth => Some(
+            depth
+                .parse::<usize>()
+                .map_err(|_| usage_error(env, "depth", "expected a nesting depth or off"))?,
+        ),
+    };
+    Ok(match env.display_depth {
+        Some(depth) => format!("Eliding types nested deeper than {}", depth),
+        None => "Printing types in full".to_string(),
+    })
+}
+
+/// `canon NAME [--fold]`: prints a declaration's type in canonical form,
+/// see [`unify::canonical`].
+fn canonical_signature(input: &[&str], env: &mut Environment) -> Result<String, Error> {
+    let (name, fold) = match input {
+        [name] => (*name, false),
+        [name, "--fold"] | ["--fold", name] => (*name, true),
+        _ => return Err(usage_error(env, "canon", "only `--fold` may be given")),
+    };
+    let declared = match (env.function(name), env.variable(name)) {
+        (Some((output_type, input_types)), _) => unify::curried(input_types, output_type),
+        (None, Some(var_type)) => var_type,
+        (None, None) => return Err(Error::UndeclaredVariable(name.to_string())),
+    };
+    Ok(format!(
+        "{} :: {}",
+        name,
+        unify::canonical(&declared, env, fold)
+    ))
+}
+
+fn set_display_width(input: &[&str], env: &mut Environment) -> Result<String, Error> {
+    env.display_width = match input[0] {
+        "off" => None,
+        width => Some(
+            width
+                .parse::<usize>()
+                .map_err(|_| usage_error(env, "width", "expected a column count or off"))?,
+        ),
+    };
+    Ok(match env.display_width {
+        Some(width) => format!("Wrapping declarations wider than {} columns", width),
+        None => "Printing declarations on one line".to_string(),
+    })
+}
+
+fn set_fold_aliases(input: &[&str], env: &mut Environment) -> Result<String, Error> {
+    match input {
+        ["on"] => env.fold_aliases = true,
+        ["off"] => env.fold_aliases = false,
+        _ => return Err(usage_error(env, "fold", "expected on or off")),
+    }
+    Ok(match env.fold_aliases {
+        true => "Printing types with aliases folded back in".to_string(),
+        false => "Printing types with aliases expanded".to_string(),
+    })
+}
+
+fn show_profile(_input: &[&str], env: &Environment) -> Result<String, Error> {
+    let mut entries: Vec<_> = env.profile.iter().collect();
+    entries.sort_by_key(|(_, (_, total))| Reverse(*total));
+    Ok(entries
+        .into_iter()
+        .map(|(command, (runs, total))| {
+            format!(
+                "{:<14} {:>6} runs {:>12.3?} total {:>12.3?} avg",
+                command,
+                runs,
+                total,
+                *total / *runs
+            )
+        })
+        .collect::<Vec<String>>()
+        .join("\n"))
+}
+
+fn show_help(input: &[&str], env: &mut Environment) -> Result<String, Error> {
+    match input {
+        [] => Ok(env
+            .commands
+            .commands()
+            .map(|command| format!("{:<42} {}", command.usage, command.help))
+            .collect::<Vec<String>>()
+            .join("\n")),
+        [name] => {
+            let command = find_command(name, env)?;
+            let mut text = format!("{}\n    {}", command.usage, command.help);
+            if !command.aliases.is_empty() {
+                text.push_str(&format!("\n    aliases: {}", command.aliases.join(", ")));
+            }
+            Ok(text)
+        }
+        _ => unreachable!(),
+    }
+}
+
+fn find_command<'a>(name: &str, env: &'a Environment) -> Result<&'a Command, Error> {
+    env.commands
+        .find(name)
+        .ok_or_else(|| Error::UnknownCommand {
+            name: name.to_string(),
+            suggestions: suggest::suggestions(name, env.commands.names()),
+        })
+}
+
+fn usage_error(env: &Environment, command: &str, problem: impl Into<String>) -> Error {
+    match env.commands.find(command) {
+        Some(command) => command.usage_error(problem.into()),
+        None => Error::Syntax(problem.into()),
+    }
+}
+
+/// The commands every environment starts with. Further commands can be
+/// added to `Environment::commands` at runtime.
+pub fn builtin_commands() -> Registry {
+    let mut registry = Registry::new();
+    let commands = [
+        Command {
+            name: "declare_var",
+            aliases: &[],
+            usage: "declare_var NAME TYPE [= VALUE]",
+            help: "Declare a variable, optionally holding a literal value that `show` prints; `call` and `check` only use its type",
+            args: ArgSpec::at_least(2, &[TokenKind::Name], TokenKind::Any),
+            handler: declare_variable,
+        },
+        Command {
+            name: "declare_func",
+            aliases: &[],
+            usage: "declare_func NAME [TYPE...] RETURN",
+            help: "Declare a function, inferring omitted parameters from earlier calls",
+            args: ArgSpec::at_least(2, &[TokenKind::Name], TokenKind::Type),
+            handler: declare_function,
+        },
+        Command {
+            name: "infer_from_calls",
+            aliases: &[],
+            usage: "infer_from_calls NAME [RETURN]",
+            help: "Propose the most general signature fitting the recorded calls to NAME",
+            args: ArgSpec::between(1, 2, &[TokenKind::Name, TokenKind::Type]),
+            handler: infer_from_calls,
+        },
+        Command {
+            name: "deferred",
+            aliases: &[],
+            usage: "deferred on|off",
+            help: "Record calls to undeclared functions as obligations instead of failing",
+            args: ArgSpec::exactly(&[TokenKind::Any]),
+            handler: set_deferred,
+        },
+        Command {
+            name: "obligations",
+            aliases: &[],
+            usage: "obligations",
+            help: "List called but still undeclared functions with their constraints",
+            args: ArgSpec::exactly(&[]),
+            handler: |input, env| list_obligations(input, env),
+        },
+        Command {
+            name: "accept",
+            aliases: &[],
+            usage: "accept N",
+            help: "Declare the N-th signature offered by an inferring declare_func",
+            args: ArgSpec::exactly(&[TokenKind::Any]),
+            handler: accept_candidate,
+        },
+        Command {
+            name: "declare_type",
+            aliases: &[],
+            usage: "declare_type NAME = CONSTRUCTOR [TYPE...] | ...",
+            help: "Declare a type and its constructors, e.g. `declare_type Color = Red | Green`",
+            args: ArgSpec::at_least(
+                3,
+                &[TokenKind::Name, TokenKind::Keyword("=")],
+                TokenKind::Any,
+            ),
+            handler: declare_type,
+        },
+        Command {
+            name: "alias",
+            aliases: &[],
+            usage: "alias NAME = TYPE",
+            help: "Declare another name for a type, e.g. `alias Age = Int`",
+            args: ArgSpec::at_least(
+                3,
+                &[TokenKind::Name, TokenKind::Keyword("=")],
+                TokenKind::Type,
+            ),
+            handler: alias_command,
+        },
+        Command {
+            name: "newtype",
+            aliases: &[],
+            usage: "newtype NAME = TYPE",
+            help: "Declare a distinct type wrapping another, built with `call NAME VALUE`",
+            args: ArgSpec::at_least(
+                3,
+                &[TokenKind::Name, TokenKind::Keyword("=")],
+                TokenKind::Type,
+            ),
+            handler: declare_newtype,
+        },
+        Command {
+            name: "field",
+            aliases: &[],
+            usage: "field RECORD FIELD",
+            help: "Show the type of a record field",
+            args: ArgSpec::exactly(&[TokenKind::Name, TokenKind::Name]),
+            handler: access_field,
+        },
+        Command {
+            name: "declare_vars",
+            aliases: &[],
+            usage: "declare_vars NAME,NAME... TYPE",
+            help: "Declare several variables of the same type",
+            args: ArgSpec::at_least(2, &[TokenKind::NameList], TokenKind::Type),
+            handler: |input, env| declare_many(input, env, declare_variable),
+        },
+        Command {
+            name: "declare_funcs",
+            aliases: &[],
+            usage: "declare_funcs NAME,NAME... TYPE... RETURN",
+            help: "Declare several functions with the same signature",
+            args: ArgSpec::at_least(3, &[TokenKind::NameList], TokenKind::Type),
+            handler: |input, env| declare_many(input, env, declare_function),
+        },
+        Command {
+            name: "call",
+            aliases: &[],
+            usage: "call NAME ARG...",
+            help: "Typecheck a call and bind its result type to `it`, arguments may be nested calls `(f ARG...)`",
+            args: ArgSpec::at_least(1, &[TokenKind::Name], TokenKind::Any),
+            handler: call_function,
+        },
+        Command {
+            name: "apply",
+            aliases: &[],
+            usage: "apply NAME ARG...",
+            help: "Like `call`, but fewer arguments than parameters give back the rest as a function",
+            args: ArgSpec::at_least(1, &[TokenKind::Name], TokenKind::Any),
+            handler: apply_function,
+        },
+        Command {
+            name: "check",
+            aliases: &[],
+            usage: "check EXPRESSION",
+            help: "Infer the type of an expression such as `add (mul x)` or `x + y * 2` without binding it",
+            args: ArgSpec::at_least(1, &[], TokenKind::Any),
+            handler: check_expression,
+        },
+        Command {
+            name: "assert_type",
+            aliases: &[],
+            usage: "assert_type EXPRESSION :: TYPE",
+            help: "Fail unless the expression has the given type, for test blocks",
+            args: ArgSpec::at_least(3, &[], TokenKind::Any),
+            handler: assert_type,
+        },
+        Command {
+            name: "assert_error",
+            aliases: &[],
+            usage: "assert_error CODE COMMAND...",
+            help: "Fail unless the command fails with an error of the given code, such as TypeMismatch",
+            args: ArgSpec::at_least(2, &[TokenKind::Name], TokenKind::Any),
+            handler: assert_error,
+        },
+        Command {
+            name: "run",
+            aliases: &[],
+            usage: "run FILE [--tests]",
+            help: "Run a script, or with `--tests` only report its `test \"name\"` ... `end` blocks",
+            args: ArgSpec::between(1, 2, &[TokenKind::Any, TokenKind::Keyword("--tests")]),
+            handler: run_script_file,
+        },
+        Command {
+            name: "compose",
+            aliases: &[],
+            usage: "compose F G [as NAME]",
+            help: "Check that `G`'s result fits `F` and show `F . G`, declaring it as `NAME` if given",
+            args: ArgSpec::at_least(2, &[TokenKind::Name, TokenKind::Name], TokenKind::Any),
+            handler: compose_functions,
+        },
+        Command {
+            name: "bind",
+            aliases: &[],
+            usage: "bind NAME = call NAME ARG...",
+            help: "Typecheck a call and declare NAME with its result type",
+            args: ArgSpec::at_least(
+                4,
+                &[
+                    TokenKind::Name,
+                    TokenKind::Keyword("="),
+                    TokenKind::Keyword("call"),
+                    TokenKind::Name,
+                ],
+                TokenKind::Any,
+            ),
+            handler: bind_result,
+        },
+        Command {
+            name: "import",
+            aliases: &[],
+            usage: "import sigs FILE",
+            help: "Declare every `name :: Sig` line of a signature file",
+            args: ArgSpec::exactly(&[TokenKind::Keyword("sigs"), TokenKind::Any]),
+            handler: import_signatures,
+        },
+        Command {
+            name: "export",
+            aliases: &[],
+            usage: "export md FILE [--tag TAG] | script FILE [--tag TAG] | callgraph dot [FILE] | stats json [FILE]",
+            help: "Write the declarations to a Markdown report or a replayable script, the calls made to a Graphviz graph, or declaration statistics to JSON",
+            args: ArgSpec::at_least(2, &[TokenKind::Any, TokenKind::Any], TokenKind::Any),
+            handler: export_environment,
+        },
+        Command {
+            name: "annotate",
+            aliases: &[],
+            usage: "annotate FILE [--lang pseudo]",
+            help: "Check the @type and @call annotations in a notes file",
+            args: ArgSpec::between(
+                1,
+                3,
+                &[
+                    TokenKind::Any,
+                    TokenKind::Keyword("--lang"),
+                    TokenKind::Name,
+                ],
+            ),
+            handler: annotate_file,
+        },
+        Command {
+            name: "session",
+            aliases: &[],
+            usage: "session save NAME | session load NAME [--force] [--yes] | session list",
+            help: "Save, load or list named sessions, `--force` loads one saved with another prelude",
+            args: ArgSpec::between(1, 4, &[TokenKind::Any, TokenKind::Name]),
+            handler: session_command,
+        },
+        Command {
+            name: "load",
+            aliases: &[],
+            usage: "load FILE [--only PATTERN] [--exclude-vars] [--replace] [--force] [--yes]",
+            help: "Merge declarations from a file written by `save` or a session snapshot, `--only` keeps names matching a `*` pattern, `--replace` restores it instead",
+            args: ArgSpec::at_least(1, &[TokenKind::Any], TokenKind::Any),
+            handler: load_file,
+        },
+        Command {
+            name: "save",
+            aliases: &[],
+            usage: "save FILE",
+            help: "Write every declaration, with its tags and values, and every operator to a JSON file that `load` reads back",
+            args: ArgSpec::exactly(&[TokenKind::Any]),
+            handler: save_file,
+        },
+        Command {
+            name: "definition",
+            aliases: &[],
+            usage: "definition NAME",
+            help: "Show where NAME is declared in the project in the current directory",
+            args: ArgSpec::exactly(&[TokenKind::Name]),
+            handler: find_definition,
+        },
+        Command {
+            name: "references",
+            aliases: &[],
+            usage: "references NAME",
+            help: "List the lines of the project in the current directory that mention NAME",
+            args: ArgSpec::exactly(&[TokenKind::Name]),
+            handler: find_references,
+        },
+        Command {
+            name: "preview",
+            aliases: &["--dry-run"],
+            usage: "preview COMMAND...",
+            help: "Show which declarations a command would add, replace or remove, without running it",
+            args: ArgSpec::at_least(1, &[TokenKind::Name], TokenKind::Any),
+            handler: preview_command,
+        },
+        Command {
+            name: "delete",
+            aliases: &["undeclare"],
+            usage: "delete NAME [--yes]",
+            help: "Remove a variable or function declaration",
+            args: ArgSpec::between(1, 2, &[TokenKind::Name, TokenKind::Keyword("--yes")]),
+            handler: delete_declaration,
+        },
+        Command {
+            name: "fuzz_sig",
+            aliases: &[],
+            usage: "fuzz_sig NAME",
+            help: "Perturb the recorded calls to a function and report which its signature still accepts",
+            args: ArgSpec::exactly(&[TokenKind::Name]),
+            handler: fuzz_signature,
+        },
+        Command {
+            name: "reset",
+            aliases: &[],
+            usage: "reset [--yes]",
+            help: "Discard every declaration, keeping the builtins",
+            args: ArgSpec::between(0, 1, &[TokenKind::Keyword("--yes")]),
+            handler: reset_environment,
+        },
+        Command {
+            name: "clear-all",
+            aliases: &[],
+            usage: "clear-all [--yes]",
+            help: "Discard every declaration, builtins included",
+            args: ArgSpec::between(0, 1, &[TokenKind::Keyword("--yes")]),
+            handler: clear_environment,
+        },
+        Command {
+            name: "rename",
+            aliases: &[],
+            usage: "rename OLD NEW",
+            help: "Move a variable or function declaration to a new name",
+            args: ArgSpec::exactly(&[TokenKind::Name, TokenKind::Name]),
+            handler: rename_declaration,
+        },
+        Command {
+            name: "tag",
+            aliases: &[],
+            usage: "tag NAME TAG,TAG...",
+            help: "Tag a declaration, for `list --tag` and `export ... --tag`",
+            args: ArgSpec::exactly(&[TokenKind::Name, TokenKind::NameList]),
+            handler: tag_declaration,
+        },
+        Command {
+            name: "list",
+            aliases: &[],
+            usage: "list [--tag TAG]",
+            help: "List the declarations made so far with their tags, or only those tagged TAG",
+            args: ArgSpec::between(0, 2, &[TokenKind::Keyword("--tag"), TokenKind::Name]),
+            handler: list_declarations,
+        },
+        Command {
+            name: "show",
+            aliases: &[],
+            usage: "show [--full] NAME",
+            help: "Show the type of a declaration or operator, `--full` skips elision",
+            args: ArgSpec::between(1, 2, &[TokenKind::Any, TokenKind::Name]),
+            handler: |input, env| show_declaration(input, env),
+        },
+        Command {
+            name: "types",
+            aliases: &[],
+            usage: "types",
+            help: "List every known type with its kind",
+            args: ArgSpec::exactly(&[]),
+            handler: |input, env| list_types(input, env),
+        },
+        Command {
+            name: "verify",
+            aliases: &[],
+            usage: "verify prelude PACK",
+            help: "Run a prelude pack's own test assertions",
+            args: ArgSpec::exactly(&[TokenKind::Keyword("prelude"), TokenKind::Name]),
+            handler: |input, env| templates::verify_template(input[1], env),
+        },
+        Command {
+            name: "begin",
+            aliases: &[],
+            usage: "begin",
+            help: "Open a scope whose declarations may shadow outer ones and vanish at `end`",
+            args: ArgSpec::exactly(&[]),
+            handler: begin_scope,
+        },
+        Command {
+            name: "end",
+            aliases: &[],
+            usage: "end",
+            help: "Close the innermost scope, undoing its declarations",
+            args: ArgSpec::exactly(&[]),
+            handler: end_scope,
+        },
+        Command {
+            name: "freeze",
+            aliases: &[],
+            usage: "freeze",
+            help: "Reject every further declaration in this environment",
+            args: ArgSpec::exactly(&[]),
+            handler: freeze_environment,
+        },
+        Command {
+            name: "declare_op",
+            aliases: &[],
+            usage: "declare_op SYMBOL PRECEDENCE left|right|none TYPE TYPE RETURN | SYMBOL TYPE TYPE RETURN infixl|infixr|infix PRECEDENCE",
+            help: "Declare an infix operator, usable in `eval` and `check`",
+            args: ArgSpec::exactly(&[
+                TokenKind::Any,
+                TokenKind::Any,
+                TokenKind::Any,
+                TokenKind::Type,
+                TokenKind::Type,
+                TokenKind::Type,
+            ]),
+            handler: declare_operator,
+        },
+        Command {
+            name: "expr",
+            aliases: &[],
+            usage: "expr EXPRESSION",
+            help: "Typecheck an infix expression and bind its type to `it`",
+            args: ArgSpec::at_least(1, &[], TokenKind::Any),
+            handler: evaluate_expression,
+        },
+        Command {
+            name: "ops",
+            aliases: &[],
+            usage: "ops",
+            help: "List infix operators with their fixity, signature and meaning",
+            args: ArgSpec::exactly(&[]),
+            handler: |input, env| list_operators(input, env),
+        },
+        Command {
+            name: "strict",
+            aliases: &[],
+            usage: "strict on|off",
+            help: "Make redeclaring a variable or function in the same scope an error",
+            args: ArgSpec::exactly(&[TokenKind::Any]),
+            handler: set_strict,
+        },
+        Command {
+            name: "timing",
+            aliases: &[],
+            usage: "timing on|off",
+            help: "Append each command's execution time to its output",
+            args: ArgSpec::exactly(&[TokenKind::Any]),
+            handler: set_timing,
+        },
+        Command {
+            name: "depth",
+            aliases: &[],
+            usage: "depth N|off",
+            help: "Elide printed types nested deeper than N",
+            args: ArgSpec::exactly(&[TokenKind::Any]),
+            handler: set_display_depth,
+        },
+        Command {
+            name: "canon",
+            aliases: &[],
+            usage: "canon NAME [--fold]",
+            help: "Print a declaration's type in a canonical form that compares as text",
+            args: ArgSpec::between(1, 2, &[TokenKind::Any, TokenKind::Any]),
+            handler: canonical_signature,
+        },
+        Command {
+            name: "width",
+            aliases: &[],
+            usage: "width N|off",
+            help: "Wrap declarations in show, list and exports wider than N columns",
+            args: ArgSpec::exactly(&[TokenKind::Any]),
+            handler: set_display_width,
+        },
+        Command {
+            name: "fold",
+            aliases: &[],
+            usage: "fold on|off",
+            help: "Write parts of printed types that match an alias as the alias in show, list, call and check",
+            args: ArgSpec::exactly(&[TokenKind::Any]),
+            handler: set_fold_aliases,
+        },
+        Command {
+            name: "profile",
+            aliases: &[],
+            usage: "profile",
+            help: "Summarize cumulative time by command",
+            args: ArgSpec::exactly(&[]),
+            handler: |input, env| show_profile(input, env),
+        },
+        Command {
+            name: "help",
+            aliases: &["?"],
+            usage: "help [COMMAND]",
+            help: "List commands or describe one",
+            args: ArgSpec::between(0, 1, &[TokenKind::Any]),
+            handler: show_help,
+        },
+    ];
+    for command in commands {
+        registry.register(command);
+    }
+    registry
+}
+
+/// Commands that touch nothing outside the environment, or only read files,
+/// so running them on a copy has no lasting effect. `run` and `assert_error`
+/// are left out since the script or command they run may save or export, as
+/// are commands registered by a host.
+const PREVIEWABLE: &[&str] = &[
+    "declare_var",
+    "declare_func",
+    "infer_from_calls",
+    "deferred",
+    "obligations",
+    "accept",
+    "declare_type",
+    "alias",
+    "newtype",
+    "field",
+    "declare_vars",
+    "declare_funcs",
+    "call",
+    "apply",
+    "check",
+    "assert_type",
+    "compose",
+    "bind",
+    "import",
+    "annotate",
+    "load",
+    "definition",
+    "references",
+    "delete",
+    "fuzz_sig",
+    "reset",
+    "clear-all",
+    "rename",
+    "tag",
+    "list",
+    "show",
+    "types",
+    "verify",
+    "begin",
+    "end",
+    "freeze",
+    "declare_op",
+    "expr",
+    "ops",
+    "strict",
+    "depth",
+    "canon",
+    "width",
+    "fold",
+    "help",
+];
+
+/// `preview COMMAND...`: runs the command on a copy of the environment and
+/// reports the declarations it would add, replace or remove.
+fn preview_command(input: &[&str], env: &mut Environment) -> Result<String, Error> {
+    let command = find_command(input[0], env)?;
+    let previewable = match command.name {
+        "session" => input.get(1) == Some(&"load"),
+        name => PREVIEWABLE.contains(&name),
+    };
+    if !previewable {
+        return Err(usage_error(
+            env,
+            "preview",
+            format!("`{}` cannot be previewed", input.join(" ")),
+        ));
+    }
+
+    let mut scratch = env.clone();
+    let output = process_input(&input.join(" "), &mut scratch)?;
+    let (before, after) = (declarations(env, None), declarations(&scratch, None));
+    let mut changes = Vec::new();
+    for (name, declaration) in &after {
+        match before.get(name) {
+            None => changes.push(format!("would add      {}", declaration)),
+            Some(old) if old != declaration => {
+                changes.push(format!("would replace  {}", old));
+                changes.push(format!("          with {}", declaration));
+            }
+            Some(_) => {}
+        }
+    }
+    for (name, declaration) in &before {
+        if !after.contains_key(name) {
+            changes.push(format!("would remove   {}", declaration));
+        }
+    }
+    if changes.is_empty() {
+        return Ok(format!("{}\n(no declarations would change)", output));
+    }
+    Ok(changes.join("\n"))
+}
+
+/// Every declaration of `env` rendered the way `show` prints it, keyed so
+/// two environments can be compared. Constructors are left to their type.
+fn declarations(env: &Environment, width: Option<usize>) -> BTreeMap<String, String> {
+    let view = env.view();
+    let mut declarations = BTreeMap::new();
+    for (name, definition) in view.types() {
+        declarations.insert(
+            format!("type {}", name),
+            pretty::type_declaration(definition.keyword(), name, definition, width),
+        );
+    }
+    for (name, target) in view.aliases() {
+        declarations.insert(
+            format!("type {}", name),
+            format!("alias {} = {}", name, target),
+        );
+    }
+    for (name, var_type) in view.variables() {
+        if !view.is_constructor(name) {
+            let var_type = displayed(&var_type, env);
+            declarations.insert(name.to_string(), format!("{} :: {}", name, var_type));
+        }
+    }
+    for (name, input_types, output_type) in view.functions() {
+        if !view.is_constructor(name) {
+            let input_types = input_types
+                .iter()
+                .map(|t| displayed(t, env))
+                .collect::<Vec<Type>>();
+            declarations.insert(
+                name.to_string(),
+                pretty::signature(
+                    name,
+                    &pretty::signature_parts(&input_types, &displayed(&output_type, env), None),
+                    width,
+                ),
+            );
+        }
+    }
+    for operator in &env.operators {
+        declarations.insert(
+            format!("({})", operator.symbol),
+            format!("({}) :: {}", operator.symbol, operator.signature()),
+        );
+    }
+    declarations
+}
+
 pub fn process_input(input: &str, env: &mut Environment) -> Result<String, Error> {
-    let tokens: Vec<&str> = input.split_whitespace().collect();
-    if tokens.is_empty() {
+    let translated = locale::translate(input, &env.keywords);
+    let input = translated.as_str();
+    let Some(invocation) = parser::parse_command(input)? else {
         return Ok(String::new());
+    };
+
+    env.limits.check(input)?;
+    let command = find_command(invocation.name, env)?;
+    command.validate(&invocation.args)?;
+    let (name, handler) = (command.name, command.handler);
+
+    let start = Instant::now();
+    let result = handler(&invocation.args, env);
+    let elapsed = start.elapsed();
+
+    let entry = env
+        .profile
+        .entry(name.to_string())
+        .or_insert((0, Duration::ZERO));
+    entry.0 += 1;
+    entry.1 += elapsed;
+
+    match result {
+        Ok(output) if env.timing => Ok(format!("{}\n({:.3?})", output, elapsed)),
+        result => result,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn preview_refuses_commands_that_may_write_files() {
+        let path =
+            std::env::temp_dir().join(format!("typecalc-preview-{}.json", std::process::id()));
+        let _ = fs::remove_file(&path);
+        let mut env = Environment::new();
+        for command in [
+            format!("preview save {}", path.display()),
+            format!("preview assert_error Foo save {}", path.display()),
+            format!("preview run {}", path.display()),
+        ] {
+            assert!(process_input(&command, &mut env).is_err(), "{}", command);
+        }
+        assert!(!path.exists());
+
+        let output = process_input("preview declare_var x Int", &mut env).unwrap();
+        assert_eq!(output, "would add      x :: Int");
+        assert!(env.variable("x").is_none());
+    }
+
+    #[test]
+    fn import_declares_nothing_when_a_declaration_is_rejected() {
+        let path =
+            std::env::temp_dir().join(format!("typecalc-import-{}.hsig", std::process::id()));
+        fs::write(&path, "x :: Int\ny :: Bool\nbad :: a -> b\n").unwrap();
+        let mut env = Environment::new();
+        let result = process_input(&format!("import sigs {}", path.display()), &mut env);
+        let _ = fs::remove_file(&path);
+        assert!(matches!(result, Err(Error::ImportFailed { .. })));
+        assert!(env.variable("x").is_none());
+        assert!(env.variable("y").is_none());
+    }
+
+    #[test]
+    fn expr_agrees_with_check_on_polymorphic_operands() {
+        let mut env = Environment::new();
+        process_input("declare_func first [a] a", &mut env).unwrap();
+        process_input("declare_var xs [Int]", &mut env).unwrap();
+        assert_eq!(
+            process_input("expr first xs + 1", &mut env),
+            Ok("first xs + 1 :: Int".to_string())
+        );
+        assert_eq!(
+            process_input("check first xs + 1", &mut env),
+            Ok("first xs + 1 :: Int".to_string())
+        );
+        assert_eq!(env.variable(&env.result_variable), Some(Type::Int));
+        assert!(process_input("expr first xs == true", &mut env).is_err());
+    }
+
+    #[test]
+    fn elision_reaches_inside_functions() {
+        let nested = |depth: usize| (0..depth).fold(Type::Int, |t, _| Type::List(Box::new(t)));
+        let function = unify::curried(
+            &[Type::Function(Box::new(nested(3)), Box::new(Type::Bool))],
+            &Type::Either(
+                Box::new(Type::Function(Box::new(Type::Int), Box::new(nested(3)))),
+                Box::new(nested(1)),
+            ),
+        );
+        assert_eq!(
+            format_elided(&function, Some(2)),
+            "([[…]] -> Bool) -> Either (Int -> […]) [Int]"
+        );
+        assert_eq!(format_elided(&function, None), function.to_string());
+    }
+
+    #[test]
+    fn rename_rejects_type_names_keywords_and_taken_names() {
+        let mut env = Environment::new();
+        process_input("declare_var s Int", &mut env).unwrap();
+        process_input("declare_var t Int", &mut env).unwrap();
+        for new in ["Float", "Shape", "then", "t"] {
+            let result = process_input(&format!("rename s {}", new), &mut env);
+            assert!(result.is_err(), "rename s {}", new);
+        }
+        assert_eq!(env.variable("s"), Some(Type::Int));
+        assert!(env.variable("Float").is_none());
+        process_input("rename s u", &mut env).unwrap();
+        assert_eq!(env.variable("u"), Some(Type::Int));
+    }
+
+    #[test]
+    fn declare_vars_declares_nothing_when_a_name_fails() {
+        let mut env = Environment::new();
+        process_input("strict on", &mut env).unwrap();
+        process_input("declare_var y Bool", &mut env).unwrap();
+        assert!(process_input("declare_vars x,y,z Int", &mut env).is_err());
+        assert!(env.variable("x").is_none());
+        assert!(env.variable("z").is_none());
+        assert_eq!(env.variable("y"), Some(Type::Bool));
+    }
+
+    #[test]
+    fn the_result_variable_is_not_written_out() {
+        let mut env = Environment::new();
+        process_input("declare_var x Int", &mut env).unwrap();
+        process_input("check x + 1", &mut env).unwrap();
+        process_input("expr x + 1", &mut env).unwrap();
+        assert_eq!(env.variable("it"), Some(Type::Int));
+        let view = env.view();
+        for written in [
+            export::script(view),
+            export::markdown(view, None),
+            session::snapshot(view),
+            env.to_json(),
+        ] {
+            assert!(written.contains("x"), "{}", written);
+            assert!(!written.contains("it "), "{}", written);
+            assert!(!written.contains("\"it\""), "{}", written);
+        }
+        assert!(export::stats_json(view, &env.operators).contains("\"variables\":1,"));
+    }
+
+    #[test]
+    fn scopes_shadow_until_they_close() {
+        let mut env = Environment::new();
+        process_input("declare_var x Int", &mut env).unwrap();
+        process_input("begin", &mut env).unwrap();
+        process_input("declare_var x Bool", &mut env).unwrap();
+        process_input("declare_var y Int", &mut env).unwrap();
+        assert_eq!(env.variable("x"), Some(Type::Bool));
+        process_input("end", &mut env).unwrap();
+        assert_eq!(env.variable("x"), Some(Type::Int));
+        assert!(env.variable("y").is_none());
+        assert_eq!(process_input("end", &mut env), Err(Error::NoOpenScope));
     }
 
-    match tokens[0] {
-        "declare_var" => declare_variable(&tokens[1..], env),
-        "declare_func" => declare_function(&tokens[1..], env),
-        "call" => call_function(&tokens[1..], env),
-        "show" => show_declaration(&tokens[1..], &*env),
-        _ => Err(Error::TypeError),
+    #[test]
+    fn strict_mode_refuses_redeclaring_in_the_same_scope() {
+        let mut env = Environment::new();
+        process_input("strict on", &mut env).unwrap();
+        process_input("declare_var x Int", &mut env).unwrap();
+        assert_eq!(
+            process_input("declare_var x Bool", &mut env),
+            Err(Error::Redeclaration {
+                name: "x".to_string(),
+                previous: Type::Int,
+            })
+        );
+        process_input("begin", &mut env).unwrap();
+        process_input("declare_var x Bool", &mut env).unwrap();
+        process_input("end", &mut env).unwrap();
+        process_input("strict off", &mut env).unwrap();
+        process_input("declare_var x Bool", &mut env).unwrap();
+        assert_eq!(env.variable("x"), Some(Type::Bool));
+    }
+
+    #[test]
+    fn registered_commands_are_validated_and_run() {
+        fn shout(input: &[&str], _: &mut Environment) -> Result<String, Error> {
+            Ok(input[0].to_uppercase())
+        }
+        let mut env = Environment::new();
+        env.commands.register(Command {
+            name: "shout",
+            aliases: &["yell"],
+            usage: "shout NAME",
+            help: "Print a name in capitals",
+            args: ArgSpec::exactly(&[TokenKind::Name]),
+            handler: shout,
+        });
+        assert_eq!(
+            process_input("yell hello", &mut env),
+            Ok("HELLO".to_string())
+        );
+        assert!(matches!(
+            process_input("shout 1x", &mut env),
+            Err(Error::Usage { .. })
+        ));
+        assert!(matches!(
+            process_input("shoutt x", &mut env),
+            Err(Error::UnknownCommand { suggestions, .. }) if suggestions.contains(&"shout".to_string())
+        ));
     }
 }
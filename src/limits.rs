@@ -0,0 +1,82 @@
+use crate::types::type_error::Error;
+use std::time::{Duration, Instant};
+
+/// Caps on what a single environment may be asked to do, for hosts that run
+/// untrusted input. Every limit is off unless set.
+#[derive(Debug, Clone, Default)]
+pub struct Limits {
+    pub requests_per_second: Option<u32>,
+    pub max_tokens: Option<usize>,
+    pub max_depth: Option<usize>,
+    window_start: Option<Instant>,
+    window_requests: u32,
+}
+
+impl Limits {
+    pub fn new() -> Self {
+        Limits::default()
+    }
+
+    /// Counts one request against the rate limit and checks its size.
+    pub fn check(&mut self, input: &str) -> Result<(), Error> {
+        if let Some(max) = self.max_tokens {
+            let tokens = input.split_whitespace().count();
+            if tokens > max {
+                return Err(Error::LimitExceeded {
+                    limit: "max_tokens",
+                    max,
+                    actual: tokens,
+                });
+            }
+        }
+
+        if let Some(max) = self.max_depth {
+            let depth = nesting_depth(input);
+            if depth > max {
+                return Err(Error::LimitExceeded {
+                    limit: "max_depth",
+                    max,
+                    actual: depth,
+                });
+            }
+        }
+
+        if let Some(max) = self.requests_per_second {
+            let now = Instant::now();
+            match self.window_start {
+                Some(start) if now.duration_since(start) < Duration::from_secs(1) => {
+                    self.window_requests += 1;
+                }
+                _ => {
+                    self.window_start = Some(now);
+                    self.window_requests = 1;
+                }
+            }
+            if self.window_requests > max {
+                return Err(Error::LimitExceeded {
+                    limit: "requests_per_second",
+                    max: max as usize,
+                    actual: self.window_requests as usize,
+                });
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn nesting_depth(input: &str) -> usize {
+    let mut depth = 0usize;
+    let mut deepest = 0;
+    for c in input.chars() {
+        match c {
+            '(' | '[' => {
+                depth += 1;
+                deepest = deepest.max(depth);
+            }
+            ')' | ']' => depth = depth.saturating_sub(1),
+            _ => {}
+        }
+    }
+    deepest
+}
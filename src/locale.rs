@@ -0,0 +1,78 @@
+use crate::paths;
+use crate::types::type_error::Error;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// `keywords.txt` in the config directory, read at startup when present.
+pub fn keywords_path() -> Option<PathBuf> {
+    paths::config_dir().map(|dir| dir.join("keywords.txt"))
+}
+
+/// Reads a translation table of `word = keyword` lines, such as
+/// `declarar_var = declare_var` or `Entero = Int`. Blank lines and lines
+/// starting with `--` are ignored.
+pub fn load_keywords(path: &Path) -> Result<HashMap<String, String>, Error> {
+    let source = fs::read_to_string(path).map_err(|err| Error::Io {
+        path: path.display().to_string(),
+        message: err.to_string(),
+    })?;
+    let mut table = HashMap::new();
+    for (number, line) in source.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with("--") {
+            continue;
+        }
+        let entry = line
+            .split_once('=')
+            .map(|(word, keyword)| (word.trim(), keyword.trim()))
+            .filter(|(word, keyword)| is_word(word) && is_word(keyword));
+        let Some((word, keyword)) = entry else {
+            return Err(Error::Syntax(format!(
+                "{} line {}: expected `word = keyword`, found `{}`",
+                path.display(),
+                number + 1,
+                line
+            )));
+        };
+        table.insert(word.to_string(), keyword.to_string());
+    }
+    Ok(table)
+}
+
+fn is_word(text: &str) -> bool {
+    !text.is_empty() && text.chars().all(|c| c.is_alphanumeric() || c == '_')
+}
+
+/// Replaces every word of `input` found in `table` by its keyword, leaving
+/// string literals alone, so `declarar_var x Entero` reads as
+/// `declare_var x Int`.
+pub fn translate(input: &str, table: &HashMap<String, String>) -> String {
+    if table.is_empty() {
+        return input.to_string();
+    }
+    let mut out = String::with_capacity(input.len());
+    let mut rest = input;
+    while let Some(c) = rest.chars().next() {
+        let len = if c == '"' {
+            crate::parser::string_length(rest).unwrap_or(rest.len())
+        } else if c.is_alphanumeric() || c == '_' {
+            let len = rest
+                .find(|c: char| !(c.is_alphanumeric() || c == '_'))
+                .unwrap_or(rest.len());
+            match table.get(&rest[..len]) {
+                Some(keyword) => {
+                    out.push_str(keyword);
+                    rest = &rest[len..];
+                    continue;
+                }
+                None => len,
+            }
+        } else {
+            c.len_utf8()
+        };
+        out.push_str(&rest[..len]);
+        rest = &rest[len..];
+    }
+    out
+}
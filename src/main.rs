@@ -1,29 +1,411 @@
+use rust_type_calculator::locale;
 use rust_type_calculator::process_input;
+use rust_type_calculator::project::{self, ModuleStatus};
+use rust_type_calculator::script::{
+    opens_block, process_decl_block, process_literate_with, process_script_with,
+};
+use rust_type_calculator::session;
+use rust_type_calculator::templates::load_template;
+use rust_type_calculator::types::type_error::Error;
+use rust_type_calculator::Diagnostic;
 use rust_type_calculator::Environment;
-use std::io::{self, Write};
+use std::env;
+use std::fs;
+use std::io::{self, BufRead, IsTerminal, Write};
+use std::path::Path;
+use std::process;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+/// Set when the REPL reads piped input, so errors and warnings go to stderr
+/// and stdout carries only results.
+static DIAGNOSTICS_TO_STDERR: AtomicBool = AtomicBool::new(false);
+
+fn print_diagnostic(text: &str) {
+    if DIAGNOSTICS_TO_STDERR.load(Ordering::Relaxed) {
+        eprintln!("{}", text);
+    } else {
+        println!("{}", text);
+    }
+}
+
+fn print_error(err: &Error, line: Option<usize>, json: bool) {
+    let mut diagnostic = Diagnostic::from(err);
+    diagnostic.line = line;
+    if json {
+        print_diagnostic(&diagnostic.to_json());
+    } else if DIAGNOSTICS_TO_STDERR.load(Ordering::Relaxed) {
+        print_diagnostic(&diagnostic.render(io::stderr().is_terminal()));
+    } else {
+        print_diagnostic(&diagnostic.render(io::stdout().is_terminal()));
+    }
+}
+
+fn print_warnings(env: &mut Environment, line: Option<usize>, json: bool) {
+    for warning in env.take_warnings() {
+        if json {
+            print_diagnostic(&warning.to_json(line));
+        } else if let Some(line) = line {
+            print_diagnostic(&format!("line {}: Warning: {}", line, warning));
+        } else {
+            print_diagnostic(&format!("Warning: {}", warning));
+        }
+    }
+}
+
+/// Set while blocked reading a line, when Ctrl-C only discards that line.
+static READING: AtomicBool = AtomicBool::new(false);
+
+/// The prompts Ctrl-C can redraw, and the index of the one last shown.
+const PROMPTS: [&str; 3] = ["", "> ", "... "];
+static PROMPT: AtomicUsize = AtomicUsize::new(0);
+
+/// The next line of input, trimmed, or `None` at end of input. Bytes that
+/// are not UTF-8 read as U+FFFD, so a stray byte fails one command rather
+/// than the session; an input that cannot be read at all ends it.
+fn read_line() -> Option<String> {
+    let mut input = Vec::new();
+    READING.store(true, Ordering::Relaxed);
+    let read = io::stdin().lock().read_until(b'\n', &mut input);
+    READING.store(false, Ordering::Relaxed);
+    match read {
+        Ok(0) => None,
+        Ok(_) => Some(String::from_utf8_lossy(&input).trim().to_string()),
+        Err(err) => {
+            eprintln!("Error: cannot read input: {}", err);
+            None
+        }
+    }
+}
+
+/// Prints a prompt, but only to a terminal: piped input gets bare results.
+fn prompt(text: &str, interactive: bool) {
+    if interactive {
+        let index = PROMPTS.iter().position(|p| *p == text).unwrap_or(0);
+        PROMPT.store(index, Ordering::Relaxed);
+        print!("{}", text);
+        io::stdout().flush().unwrap();
+    }
+}
+
+/// Ctrl-C while a line is being typed discards it and redraws the prompt;
+/// the terminal has already thrown the typed text away. Any other time,
+/// such as during a runaway command, it stops the process as usual.
+#[cfg(unix)]
+mod interrupt {
+    use super::{PROMPT, PROMPTS, READING};
+    use std::sync::atomic::Ordering;
+
+    const SIGINT: i32 = 2;
+    const SIG_DFL: usize = 0;
+
+    extern "C" {
+        fn signal(signum: i32, handler: usize) -> usize;
+        fn raise(signum: i32) -> i32;
+        fn write(fd: i32, buf: *const u8, count: usize) -> isize;
+    }
+
+    extern "C" fn handle(_: i32) {
+        // Only async-signal-safe calls in here.
+        unsafe {
+            if READING.load(Ordering::Relaxed) {
+                let prompt = PROMPTS[PROMPT.load(Ordering::Relaxed)];
+                write(1, b"\n".as_ptr(), 1);
+                write(1, prompt.as_ptr(), prompt.len());
+            } else {
+                // Leave bracketed paste off for the shell we hand back to.
+                let off = b"\x1b[?2004l";
+                write(1, off.as_ptr(), off.len());
+                signal(SIGINT, SIG_DFL);
+                raise(SIGINT);
+            }
+        }
+    }
+
+    pub fn install() {
+        unsafe {
+            signal(SIGINT, handle as extern "C" fn(i32) as usize);
+        }
+    }
+}
+
+#[cfg(not(unix))]
+mod interrupt {
+    pub fn install() {}
+}
+
+// Terminals wrap pasted text in these markers once bracketed paste mode is on.
+const PASTE_START: &str = "\x1b[200~";
+const PASTE_END: &str = "\x1b[201~";
+
+/// Runs a pasted batch of lines as one script, attributing every output and
+/// error to its line within the batch.
+fn run_batch(source: &str, env: &mut Environment, json: bool) {
+    process_script_with(source, env, |line, result, env| {
+        match result {
+            Ok(output) if !output.is_empty() => println!("line {}: {}", line, output),
+            Ok(_) => {}
+            Err(err) => print_error(&err, Some(line), json),
+        }
+        print_warnings(env, Some(line), json);
+    });
+}
+
+/// `rust-type-calculator build [DIR|MANIFEST]`: checks a whole project and
+/// writes its exports, exiting non-zero when any source failed.
+fn build(path: &str) -> ! {
+    let mut env = Environment::new();
+    match project::build(Path::new(path), &mut env) {
+        Ok(build) => {
+            for (source, line, err) in &build.failures {
+                print!("{}: ", source);
+                print_error(err, *line, false);
+            }
+            for (module, status) in &build.modules {
+                match status {
+                    ModuleStatus::Checked { errors: 0 } => println!("module {}: ok", module),
+                    ModuleStatus::Cached => println!("module {}: ok (cached)", module),
+                    ModuleStatus::Checked { errors } => {
+                        println!("module {}: {} error(s)", module, errors)
+                    }
+                    ModuleStatus::Skipped { failed_import } => {
+                        println!(
+                            "module {}: skipped, imports failed `{}`",
+                            module, failed_import
+                        )
+                    }
+                }
+            }
+            for target in &build.exported {
+                println!("Exported to {}", target);
+            }
+            if build.failures.is_empty() {
+                println!("Build succeeded");
+                process::exit(0);
+            }
+            println!("Build failed with {} error(s)", build.failures.len());
+            process::exit(1);
+        }
+        Err(err) => {
+            print_error(&err, None, false);
+            process::exit(1);
+        }
+    }
+}
 
 fn main() {
+    let mut json = false;
+    let mut script = None;
+    let mut autosave_every = None;
     let mut env = Environment::new();
+    session::install_crash_hook();
+    if let Some(path) = locale::keywords_path().filter(|path| path.exists()) {
+        match locale::load_keywords(&path) {
+            Ok(keywords) => env.keywords = keywords,
+            Err(err) => print_error(&err, None, false),
+        }
+    }
+
+    if env::args().nth(1).as_deref() == Some("build") {
+        build(env::args().nth(2).as_deref().unwrap_or("."));
+    }
+
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--json" => json = true,
+            "--strict" => env.strict_redeclaration = true,
+            "--result-name" => {
+                if let Some(name) = args.next() {
+                    env.result_variable = name;
+                }
+            }
+            "--template" => {
+                if let Some(name) = args.next() {
+                    if let Err(err) = load_template(&name, &mut env) {
+                        print_error(&err, None, json);
+                        process::exit(1);
+                    }
+                }
+            }
+            "--autosave" => {
+                autosave_every = args.next().and_then(|n| n.parse::<usize>().ok());
+            }
+            // A script path runs the file instead of starting the REPL.
+            _ => script = Some(arg),
+        }
+    }
+
+    if let Some(path) = script {
+        let source = fs::read_to_string(&path).unwrap_or_else(|err| {
+            eprintln!("Error: cannot read {}: {}", path, err);
+            process::exit(1);
+        });
+        let mut failed = false;
+        let report = |line, result: Result<String, Error>, env: &mut Environment| {
+            match result {
+                Ok(output) if !output.is_empty() => println!("{}", output),
+                Ok(_) => {}
+                Err(err) => {
+                    failed = true;
+                    print_error(&err, Some(line), json);
+                }
+            }
+            print_warnings(env, Some(line), json);
+        };
+        if path.ends_with(".tc.md") {
+            process_literate_with(&source, &mut env, report);
+        } else {
+            process_script_with(&source, &mut env, report);
+        }
+        process::exit(if failed { 1 } else { 0 });
+    }
+
+    let interactive = io::stdin().is_terminal();
+    DIAGNOSTICS_TO_STDERR.store(!interactive, Ordering::Relaxed);
+
+    // Piped input has no one to answer, and its first line is a command.
+    if interactive && session::recovery_path().is_ok_and(|path| path.exists()) {
+        prompt(
+            "A previous session did not exit cleanly. Restore it? [y/N] ",
+            interactive,
+        );
+        if read_line().is_some_and(|answer| answer.eq_ignore_ascii_case("y")) {
+            match session::restore_recovery(&mut env) {
+                Ok(()) => println!("Restored previous session"),
+                Err(err) => print_error(&err, None, json),
+            }
+        }
+        session::discard_recovery();
+    }
 
+    env.confirm_destructive = interactive;
+    session::record_environment(&env);
+    if interactive {
+        interrupt::install();
+        print!("\x1b[?2004h");
+    }
+
+    let mut commands = 0;
     loop {
-        print!("> ");
-        io::stdout().flush().unwrap();
+        if let Some(every) = autosave_every.filter(|every| *every > 0) {
+            if commands > 0 && commands % every == 0 {
+                if let Err(err) = session::save_recovery(&env) {
+                    print_error(&err, None, json);
+                }
+            }
+        }
+        commands += 1;
 
-        let mut input = String::new();
-        io::stdin().read_line(&mut input).unwrap();
-        let input = input.trim();
+        prompt("> ", interactive);
 
-        if input == "quit" || input == "exit" {
+        // End of input quits like `quit` does.
+        let input = read_line();
+        let Some(input) = input.filter(|input| input != "quit" && input != "exit") else {
+            session::discard_recovery();
+            if interactive {
+                // Ctrl-D leaves the cursor after the prompt.
+                println!("\x1b[?2004l");
+            }
             break;
+        };
+
+        if input == "paste" || input.starts_with(PASTE_START) {
+            let bracketed = input.starts_with(PASTE_START);
+            let mut batch = Vec::new();
+            let mut line = Some(input.trim_start_matches(PASTE_START).to_string());
+            if !bracketed {
+                line = read_line();
+            }
+            let mut cut_short = false;
+            // Blocks pasted inside the batch have `end`s of their own.
+            let mut depth = 0;
+            loop {
+                let Some(text) = line else {
+                    cut_short = true;
+                    break;
+                };
+                if bracketed && text.contains(PASTE_END) {
+                    batch.push(text.replace(PASTE_END, ""));
+                    break;
+                }
+                if !bracketed && text.trim() == "end" {
+                    if depth == 0 {
+                        break;
+                    }
+                    depth -= 1;
+                } else if opens_block(&text) {
+                    depth += 1;
+                }
+                batch.push(text);
+                line = read_line();
+            }
+            // Ctrl-D at a terminal abandons the batch; piped input cut short
+            // runs as far as it got.
+            if cut_short && interactive {
+                println!("\nCancelled");
+                continue;
+            }
+            session::record_command(&input);
+            run_batch(&batch.join("\n"), &mut env, json);
+            session::record_environment(&env);
+            continue;
         }
 
-        match process_input(input, &mut env) {
+        if input == "begin decls" {
+            let mut block = Vec::new();
+            let mut cancelled = false;
+            loop {
+                prompt("... ", interactive);
+                match read_line() {
+                    Some(line) if line != "end" => block.push((block.len() + 1, line)),
+                    Some(_) => break,
+                    // As with a batch, Ctrl-D abandons the block.
+                    None => {
+                        cancelled = interactive;
+                        break;
+                    }
+                }
+            }
+            if cancelled {
+                println!("\nCancelled");
+                continue;
+            }
+            let lines: Vec<(usize, &str)> = block.iter().map(|(n, l)| (*n, l.as_str())).collect();
+            session::record_command(&input);
+            match process_decl_block(&lines, &mut env) {
+                Ok(output) => {
+                    session::record_environment(&env);
+                    println!("{}", output)
+                }
+                Err(errors) => {
+                    for (line, err) in errors {
+                        print_error(&err, Some(line), json);
+                    }
+                }
+            }
+            print_warnings(&mut env, None, json);
+            continue;
+        }
+
+        session::record_command(&input);
+        let mut result = process_input(&input, &mut env);
+        if let Err(Error::ConfirmationRequired { question }) = &result {
+            prompt(&format!("{} [y/N] ", question), interactive);
+            if !read_line().is_some_and(|answer| answer.eq_ignore_ascii_case("y")) {
+                println!("Cancelled");
+                continue;
+            }
+            result = process_input(&format!("{} --yes", input), &mut env);
+        }
+        match result {
             Ok(output) => {
+                session::record_environment(&env);
                 if !output.is_empty() {
                     println!("{}", output);
                 }
             }
-            Err(err) => println!("Error: {}", err),
+            Err(err) => print_error(&err, None, json),
         }
+        print_warnings(&mut env, None, json);
     }
 }
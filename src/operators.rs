@@ -0,0 +1,44 @@
+use crate::types::type_enum::Type;
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Associativity {
+    Left,
+    Right,
+    None,
+}
+
+/// An infix operator: its fixity and the types of both operands and the
+/// result. Higher precedence binds tighter, as in Haskell (0 to 9).
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Operator {
+    pub symbol: String,
+    pub precedence: u8,
+    pub associativity: Associativity,
+    pub left: Type,
+    pub right: Type,
+    pub output: Type,
+    pub note: String,
+}
+
+impl Operator {
+    pub fn signature(&self) -> String {
+        format!("{} -> {} -> {}", self.left, self.right, self.output)
+    }
+
+    /// The operator as a declarations-block line, `infixl 6 (+) :: Int ->
+    /// Int -> Int`, which snapshots store it as.
+    pub fn declaration(&self) -> String {
+        let fixity = match self.associativity {
+            Associativity::Left => "infixl",
+            Associativity::Right => "infixr",
+            Associativity::None => "infix",
+        };
+        format!(
+            "{} {} ({}) :: {}",
+            fixity,
+            self.precedence,
+            self.symbol,
+            self.signature()
+        )
+    }
+}
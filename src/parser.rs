@@ -0,0 +1,709 @@
+use crate::expr::{conflicts, is_operator_symbol};
+use crate::inference::ast::Expr;
+use crate::operators::{Associativity, Operator};
+use crate::types::type_enum::Type;
+use crate::types::type_error::Error;
+use crate::{parse_type, Environment};
+
+/// A command line split into its name and argument words.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Invocation<'a> {
+    pub name: &'a str,
+    pub args: Vec<&'a str>,
+}
+
+/// Splits a command line into words. A double-quoted string is one word,
+/// quotes included, and a `--` word starts a comment running to the end of
+/// the line. Returns `None` for a line with no command on it.
+pub fn parse_command(input: &str) -> Result<Option<Invocation<'_>>, Error> {
+    let mut words = Vec::new();
+    let mut rest = input.trim_start();
+    while !rest.is_empty() {
+        let start = input.len() - rest.len();
+        let len = if rest.starts_with('"') {
+            string_length(rest).ok_or_else(|| {
+                Error::Syntax(format!(
+                    "unterminated string starting at column {}",
+                    start + 1
+                ))
+            })?
+        } else {
+            rest.find(char::is_whitespace).unwrap_or(rest.len())
+        };
+        let word = &rest[..len];
+        if word == "--" {
+            break;
+        }
+        words.push(word);
+        rest = rest[len..].trim_start();
+    }
+    Ok(words.split_first().map(|(name, args)| Invocation {
+        name,
+        args: args.to_vec(),
+    }))
+}
+
+/// Length of the string literal `source` starts with, closing quote
+/// included, if it is closed.
+pub(crate) fn string_length(source: &str) -> Option<usize> {
+    let mut escaped = false;
+    for (i, c) in source.char_indices().skip(1) {
+        match c {
+            '\\' if !escaped => escaped = true,
+            '"' if !escaped => return Some(i + 1),
+            _ => escaped = false,
+        }
+    }
+    None
+}
+
+/// Length of the character literal `source` starts with, quotes included,
+/// if it is closed.
+pub(crate) fn char_length(source: &str) -> Option<usize> {
+    let mut chars = source.char_indices().skip(1);
+    let (_, c) = chars.next()?;
+    if c == '\\' {
+        chars.next()?;
+    }
+    let (i, close) = chars.next()?;
+    (close == '\'').then_some(i + 1)
+}
+
+/// The type of a literal: `3` is an `Int`, `3.14` a `Float`, `true` and
+/// `false` are `Bool`s, `"text"` is a `String` and `'c'` a `Char`.
+pub fn literal_type(token: &str) -> Option<Type> {
+    let digits = token.strip_prefix('-').unwrap_or(token);
+    let is_digits = |s: &str| !s.is_empty() && s.chars().all(|c| c.is_ascii_digit());
+    match token {
+        "true" | "false" => return Some(Type::Bool),
+        _ if is_digits(digits) => return Some(Type::Int),
+        _ => {}
+    }
+    if let Some((whole, fraction)) = digits.split_once('.') {
+        if is_digits(whole) && is_digits(fraction) {
+            return Some(Type::Float);
+        }
+    }
+    if token.len() >= 2 && token.starts_with('"') && string_length(token) == Some(token.len()) {
+        return Some(Type::String);
+    }
+    let quoted = token
+        .strip_prefix('\'')
+        .and_then(|rest| rest.strip_suffix('\''))?;
+    let mut chars = quoted.chars();
+    match (chars.next(), chars.next(), chars.next()) {
+        (Some(c), None, _) if c != '\\' => Some(Type::Char),
+        (Some('\\'), Some(_), None) => Some(Type::Char),
+        _ => None,
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum TokenKind {
+    /// An identifier such as `x`, `Int` or `add_one`.
+    Name,
+    Number,
+    Str,
+    Char,
+    Open,
+    Close,
+    OpenBracket,
+    CloseBracket,
+    Comma,
+    Arrow,
+    Backslash,
+    /// Any other run of symbol characters, such as `+` or `::`.
+    Symbol,
+}
+
+/// A token of an expression, with its byte range in the source so error
+/// messages and sub-parsers can point back into it.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Token {
+    pub kind: TokenKind,
+    pub start: usize,
+    pub end: usize,
+}
+
+impl Token {
+    pub fn text<'a>(&self, source: &'a str) -> &'a str {
+        &source[self.start..self.end]
+    }
+}
+
+pub fn lex(source: &str) -> Result<Vec<Token>, Error> {
+    let mut tokens = Vec::new();
+    let mut position = 0;
+    while let Some(c) = source[position..].chars().next() {
+        let rest = &source[position..];
+        let (kind, len) = if c.is_whitespace() {
+            position += c.len_utf8();
+            continue;
+        } else if rest.starts_with("--") {
+            break;
+        } else if c.is_alphabetic() || c == '_' {
+            let len = rest
+                .find(|c: char| !(c.is_alphanumeric() || c == '_'))
+                .unwrap_or(rest.len());
+            (TokenKind::Name, len)
+        } else if c.is_ascii_digit() {
+            let len = rest
+                .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+                .unwrap_or(rest.len());
+            (TokenKind::Number, len)
+        } else if c == '"' {
+            let len = string_length(rest).ok_or_else(|| {
+                Error::Syntax(format!(
+                    "unterminated string starting at column {}",
+                    position + 1
+                ))
+            })?;
+            (TokenKind::Str, len)
+        } else if c == '\'' {
+            let len = char_length(rest)
+                .filter(|len| literal_type(&rest[..*len]).is_some())
+                .ok_or_else(|| {
+                    Error::Syntax(format!("bad character literal at column {}", position + 1))
+                })?;
+            (TokenKind::Char, len)
+        } else if rest.starts_with("->") {
+            (TokenKind::Arrow, 2)
+        } else {
+            match c {
+                '(' => (TokenKind::Open, 1),
+                ')' => (TokenKind::Close, 1),
+                '[' => (TokenKind::OpenBracket, 1),
+                ']' => (TokenKind::CloseBracket, 1),
+                ',' => (TokenKind::Comma, 1),
+                '\\' => (TokenKind::Backslash, 1),
+                _ => {
+                    let len = rest
+                        .find(|c: char| {
+                            c.is_alphanumeric() || c.is_whitespace() || "()[],\\\"_".contains(c)
+                        })
+                        .unwrap_or(rest.len());
+                    (TokenKind::Symbol, len)
+                }
+            }
+        };
+        tokens.push(Token {
+            kind,
+            start: position,
+            end: position + len,
+        });
+        position += len;
+    }
+    Ok(tokens)
+}
+
+/// Parses an expression for `check`: names and literals applied to
+/// arguments, with parentheses for grouping. A capitalized name that is not
+/// a declared value, or a bracketed or parenthesized type, stands for a
+/// value of that type, so `f Int [Char]` applies `f` to an `Int` and a
+/// `[Char]`.
+///
+/// Applications can be joined by the infix operators in the environment's
+/// fixity table, `f x + y * 2`, which become applications of the operator
+/// itself: `(+) (f x) ((*) y 2)`.
+pub fn parse_expr(source: &str, env: &Environment) -> Result<Expr, Error> {
+    let tokens = lex(source)?;
+    let mut parser = ExprParser {
+        source,
+        tokens,
+        position: 0,
+        env,
+    };
+    let expr = parser.infix(0, None)?;
+    match parser.peek() {
+        None => Ok(expr),
+        Some(token) => Err(parser.unexpected(token)),
+    }
+}
+
+struct ExprParser<'a> {
+    source: &'a str,
+    tokens: Vec<Token>,
+    position: usize,
+    env: &'a Environment,
+}
+
+impl ExprParser<'_> {
+    fn peek(&self) -> Option<Token> {
+        self.tokens.get(self.position).copied()
+    }
+
+    fn unexpected(&self, token: Token) -> Error {
+        Error::Syntax(format!(
+            "unexpected `{}` at column {} of the expression",
+            token.text(self.source),
+            token.start + 1
+        ))
+    }
+
+    /// Precedence climbing over applications: parses operators binding at
+    /// least as tightly as `min_precedence`. `parent` is the operator whose
+    /// right operand this is, if any.
+    fn infix(&mut self, min_precedence: u8, parent: Option<&Operator>) -> Result<Expr, Error> {
+        let env = self.env;
+        let mut left = self.application()?;
+        let mut previous = parent;
+        while let Some(token) = self.peek().filter(|token| token.kind == TokenKind::Symbol) {
+            let symbol = token.text(self.source);
+            if !is_operator_symbol(symbol) {
+                return Err(self.unexpected(token));
+            }
+            let operator = env
+                .operator(symbol)
+                .ok_or_else(|| Error::UnknownOperator(symbol.to_string()))?;
+            if let Some(neighbour) = previous {
+                if conflicts(neighbour, operator) {
+                    return Err(Error::AmbiguousOperators {
+                        left: neighbour.symbol.clone(),
+                        right: operator.symbol.clone(),
+                        precedence: operator.precedence,
+                    });
+                }
+            }
+            if operator.precedence < min_precedence {
+                break;
+            }
+            self.position += 1;
+
+            let next_precedence = match operator.associativity {
+                Associativity::Right => operator.precedence,
+                Associativity::Left | Associativity::None => operator.precedence + 1,
+            };
+            let right = self.infix(next_precedence, Some(operator))?;
+            left = Expr::apply(Expr::name(symbol), vec![left, right]);
+            previous = Some(operator);
+        }
+        Ok(left)
+    }
+
+    /// One or more atoms, the first applied to the rest, or an `if`, `let`
+    /// or lambda, which extend as far right as they can.
+    fn application(&mut self) -> Result<Expr, Error> {
+        if self
+            .peek()
+            .is_some_and(|token| token.kind == TokenKind::Backslash)
+        {
+            return self.lambda();
+        }
+        if self.at_keyword("if") {
+            return self.conditional();
+        }
+        if self.at_keyword("let") {
+            return self.binding();
+        }
+        let function = self.atom()?;
+        let mut arguments = Vec::new();
+        while self.peek().is_some_and(|token| self.starts_atom(token)) {
+            arguments.push(self.atom()?);
+        }
+        Ok(Expr::apply(function, arguments))
+    }
+
+    /// `if CONDITION then EXPRESSION else EXPRESSION`.
+    fn conditional(&mut self) -> Result<Expr, Error> {
+        self.position += 1;
+        let condition = self.infix(0, None)?;
+        self.keyword("then")?;
+        let then_branch = self.infix(0, None)?;
+        self.keyword("else")?;
+        let else_branch = self.infix(0, None)?;
+        Ok(Expr::If(
+            Box::new(condition),
+            Box::new(then_branch),
+            Box::new(else_branch),
+        ))
+    }
+
+    /// `let NAME = EXPRESSION in EXPRESSION`, or `let NAME :: TYPE in
+    /// EXPRESSION` to stand for some value of the type. The name is only in
+    /// scope in the body.
+    fn binding(&mut self) -> Result<Expr, Error> {
+        self.position += 1;
+        let name = match self.peek() {
+            Some(token) if token.kind == TokenKind::Name && self.starts_atom(token) => {
+                self.position += 1;
+                token.text(self.source).to_string()
+            }
+            Some(token) => return Err(self.unexpected(token)),
+            None => return Err(Error::Syntax("an expression ended early".to_string())),
+        };
+        let value = match self.peek() {
+            Some(token) if token.kind == TokenKind::Symbol && token.text(self.source) == "=" => {
+                self.position += 1;
+                self.infix(0, None)?
+            }
+            Some(token) if token.kind == TokenKind::Symbol && token.text(self.source) == "::" => {
+                self.position += 1;
+                let start = self.position;
+                while self.peek().is_some() && !self.at_keyword("in") {
+                    self.position += 1;
+                }
+                let (Some(first), Some(last)) =
+                    (self.tokens.get(start), self.tokens.get(self.position - 1))
+                else {
+                    return Err(Error::Syntax(format!(
+                        "expected a type after `::` at column {} of the expression",
+                        token.start + 1
+                    )));
+                };
+                if start == self.position {
+                    return Err(self.unexpected(*first));
+                }
+                Expr::Typed(annotation(&self.source[first.start..last.end], self.env)?)
+            }
+            Some(token) => return Err(self.unexpected(token)),
+            None => return Err(Error::Syntax("an expression ended early".to_string())),
+        };
+        self.keyword("in")?;
+        let body = self.infix(0, None)?;
+        Ok(Expr::Let(name, Box::new(value), Box::new(body)))
+    }
+
+    /// `\x (y :: Int) -> EXPRESSION`: one or more parameters, each a name or
+    /// a parenthesized name with its type.
+    fn lambda(&mut self) -> Result<Expr, Error> {
+        let backslash = self.tokens[self.position];
+        self.position += 1;
+        let mut parameters = Vec::new();
+        loop {
+            match self.peek() {
+                Some(token) if token.kind == TokenKind::Arrow => {
+                    self.position += 1;
+                    break;
+                }
+                Some(token) if token.kind == TokenKind::Name && self.starts_atom(token) => {
+                    self.position += 1;
+                    parameters.push((token.text(self.source).to_string(), None));
+                }
+                Some(token) if token.kind == TokenKind::Open => {
+                    parameters.push(self.annotated_parameter(token)?);
+                }
+                Some(token) => return Err(self.unexpected(token)),
+                None => {
+                    return Err(Error::Syntax(format!(
+                        "missing `->` for the lambda at column {} of the expression",
+                        backslash.start + 1
+                    )))
+                }
+            }
+        }
+        if parameters.is_empty() {
+            return Err(Error::Syntax(format!(
+                "the lambda at column {} of the expression has no parameters",
+                backslash.start + 1
+            )));
+        }
+        let body = self.infix(0, None)?;
+        Ok(parameters
+            .into_iter()
+            .rev()
+            .fold(body, |body, (parameter, annotation)| {
+                Expr::Lambda(parameter, annotation, Box::new(body))
+            }))
+    }
+
+    /// `(x :: TYPE)`, the type running to the matching `)`.
+    fn annotated_parameter(&mut self, open: Token) -> Result<(String, Option<Type>), Error> {
+        let name = self.tokens.get(self.position + 1).copied();
+        let colons = self.tokens.get(self.position + 2).copied();
+        let (Some(name), Some(colons)) = (name, colons) else {
+            return Err(Error::Syntax("an expression ended early".to_string()));
+        };
+        if name.kind != TokenKind::Name || !self.starts_atom(name) {
+            return Err(self.unexpected(name));
+        }
+        if colons.text(self.source) != "::" {
+            return Err(self.unexpected(colons));
+        }
+        let mut depth = 0;
+        for index in self.position + 3..self.tokens.len() {
+            let token = self.tokens[index];
+            match token.kind {
+                TokenKind::Open | TokenKind::OpenBracket => depth += 1,
+                TokenKind::CloseBracket => depth -= 1,
+                TokenKind::Close if depth == 0 => {
+                    let start = colons.end;
+                    let annotation = annotation(&self.source[start..token.start], self.env)?;
+                    self.position = index + 1;
+                    return Ok((name.text(self.source).to_string(), Some(annotation)));
+                }
+                TokenKind::Close => depth -= 1,
+                _ => {}
+            }
+        }
+        Err(Error::Syntax(format!(
+            "missing `)` for the `(` at column {} of the expression",
+            open.start + 1
+        )))
+    }
+
+    fn at_keyword(&self, keyword: &str) -> bool {
+        self.peek().is_some_and(|token| {
+            token.kind == TokenKind::Name && token.text(self.source) == keyword
+        })
+    }
+
+    fn keyword(&mut self, keyword: &str) -> Result<(), Error> {
+        match self.peek() {
+            _ if self.at_keyword(keyword) => {
+                self.position += 1;
+                Ok(())
+            }
+            Some(token) => Err(Error::Syntax(format!(
+                "expected `{}` at column {} of the expression, found `{}`",
+                keyword,
+                token.start + 1,
+                token.text(self.source)
+            ))),
+            None => Err(Error::Syntax(format!(
+                "expected `{}` before the end of the expression",
+                keyword
+            ))),
+        }
+    }
+
+    /// Whether `token` can begin an argument. Keywords cannot, so `then`
+    /// ends the condition of an `if`.
+    fn starts_atom(&self, token: Token) -> bool {
+        match token.kind {
+            TokenKind::Name => !KEYWORDS.contains(&token.text(self.source)),
+            TokenKind::Number
+            | TokenKind::Str
+            | TokenKind::Char
+            | TokenKind::Open
+            | TokenKind::OpenBracket => true,
+            _ => false,
+        }
+    }
+
+    fn atom(&mut self) -> Result<Expr, Error> {
+        let Some(token) = self.peek() else {
+            return Err(Error::Syntax("an expression ended early".to_string()));
+        };
+        match token.kind {
+            TokenKind::Number | TokenKind::Str | TokenKind::Char => {
+                self.position += 1;
+                literal_type(token.text(self.source))
+                    .map(Expr::Typed)
+                    .ok_or_else(|| self.unexpected(token))
+            }
+            TokenKind::Name if KEYWORDS.contains(&token.text(self.source)) => {
+                Err(self.unexpected(token))
+            }
+            TokenKind::Name => {
+                let name = token.text(self.source);
+                self.position += 1;
+                if let Some(literal) = literal_type(name) {
+                    Ok(Expr::Typed(literal))
+                } else if self.is_value(name) {
+                    Ok(Expr::name(name))
+                } else {
+                    parse_type(name, self.env).map(Expr::Typed)
+                }
+            }
+            TokenKind::OpenBracket => self.type_group(token, TokenKind::CloseBracket),
+            TokenKind::Open => {
+                let first = self.tokens.get(self.position + 1).copied();
+                let is_type = first.is_some_and(|first| {
+                    first.kind == TokenKind::OpenBracket
+                        || (first.kind == TokenKind::Name
+                            && !self.is_value(first.text(self.source)))
+                });
+                if is_type {
+                    return self.type_group(token, TokenKind::Close);
+                }
+                self.position += 1;
+                let inner = self.infix(0, None)?;
+                match self.peek() {
+                    Some(close) if close.kind == TokenKind::Close => {
+                        self.position += 1;
+                        Ok(inner)
+                    }
+                    Some(other) => Err(self.unexpected(other)),
+                    None => Err(Error::Syntax(format!(
+                        "missing `)` for the `(` at column {} of the expression",
+                        token.start + 1
+                    ))),
+                }
+            }
+            _ => Err(self.unexpected(token)),
+        }
+    }
+
+    /// A type written in brackets or parentheses, handed to the type parser
+    /// whole.
+    fn type_group(&mut self, open: Token, close: TokenKind) -> Result<Expr, Error> {
+        let mut depth = 0;
+        for index in self.position..self.tokens.len() {
+            let token = self.tokens[index];
+            match token.kind {
+                TokenKind::Open | TokenKind::OpenBracket => depth += 1,
+                TokenKind::Close | TokenKind::CloseBracket => depth -= 1,
+                _ => {}
+            }
+            if depth == 0 {
+                if token.kind != close {
+                    return Err(self.unexpected(token));
+                }
+                self.position = index + 1;
+                let text = &self.source[open.start..token.end];
+                return parse_type(text, self.env).map(Expr::Typed);
+            }
+        }
+        Err(Error::Syntax(format!(
+            "missing a closing bracket for the one at column {} of the expression",
+            open.start + 1
+        )))
+    }
+
+    /// Lowercase names are always values, capitalized ones only when
+    /// declared, like the constructor `Red`.
+    fn is_value(&self, name: &str) -> bool {
+        !name.starts_with(char::is_uppercase)
+            || self.env.variable(name).is_some()
+            || self.env.function(name).is_some()
+    }
+}
+
+/// A type written after `::`, where a function type needs no parentheses.
+pub(crate) fn annotation(text: &str, env: &Environment) -> Result<Type, Error> {
+    parse_type(&format!("({})", text.trim()), env)
+}
+
+/// Words that cannot name a value in an expression.
+pub(crate) const KEYWORDS: &[&str] = &["if", "then", "else", "let", "in"];
+
+/// Renders an expression back as source, for messages.
+pub fn show_expr(expr: &Expr) -> String {
+    if let Some((symbol, left, right)) = infix_parts(expr) {
+        return format!("{} {} {}", operand(left), symbol, operand(right));
+    }
+    match expr {
+        Expr::Name(name) if is_operator_symbol(name) => format!("({})", name),
+        Expr::Name(name) => name.clone(),
+        Expr::Typed(t @ (Type::Either(..) | Type::Function(..))) => format!("({})", t),
+        Expr::Typed(t) => t.to_string(),
+        Expr::Apply(function, argument) => {
+            let argument = match **argument {
+                Expr::Apply(..) | Expr::Lambda(..) | Expr::Let(..) | Expr::If(..) => {
+                    format!("({})", show_expr(argument))
+                }
+                _ => show_expr(argument),
+            };
+            format!("{} {}", show_expr(function), argument)
+        }
+        Expr::Lambda(parameter, Some(t), body) => {
+            format!("\\({} :: {}) -> {}", parameter, t, show_expr(body))
+        }
+        Expr::Lambda(parameter, None, body) => format!("\\{} -> {}", parameter, show_expr(body)),
+        Expr::Let(name, value, body) => match &**value {
+            Expr::Typed(t) => format!("let {} :: {} in {}", name, t, show_expr(body)),
+            value => format!("let {} = {} in {}", name, show_expr(value), show_expr(body)),
+        },
+        Expr::If(condition, then_branch, else_branch) => format!(
+            "if {} then {} else {}",
+            show_expr(condition),
+            show_expr(then_branch),
+            show_expr(else_branch)
+        ),
+    }
+}
+
+/// An operator applied to both its operands, as `parse_expr` builds them.
+fn infix_parts(expr: &Expr) -> Option<(&str, &Expr, &Expr)> {
+    match expr {
+        Expr::Apply(function, right) => match &**function {
+            Expr::Apply(operator, left) => match &**operator {
+                Expr::Name(symbol) if is_operator_symbol(symbol) => Some((symbol, left, right)),
+                _ => None,
+            },
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// An operand of an infix operator, parenthesized unless it is an atom or
+/// an application.
+fn operand(expr: &Expr) -> String {
+    if infix_parts(expr).is_some()
+        || matches!(expr, Expr::Lambda(..) | Expr::Let(..) | Expr::If(..))
+    {
+        format!("({})", show_expr(expr))
+    } else {
+        show_expr(expr)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn same_parse(source: &str, grouped: &str, env: &Environment) {
+        assert_eq!(parse_expr(source, env), parse_expr(grouped, env));
+    }
+
+    #[test]
+    fn operators_follow_precedence_and_associativity() {
+        let env = Environment::new();
+        same_parse("1 + 2 * 3", "1 + (2 * 3)", &env);
+        same_parse("f 1 * 2", "(f 1) * 2", &env);
+        same_parse("1 - 2 - 3", "(1 - 2) - 3", &env);
+        same_parse("s ++ t ++ u", "s ++ (t ++ u)", &env);
+        assert_eq!(
+            parse_expr("1 + 2", &env),
+            Ok(Expr::apply(
+                Expr::name("+"),
+                vec![Expr::Typed(Type::Int), Expr::Typed(Type::Int)]
+            ))
+        );
+    }
+
+    #[test]
+    fn equal_precedence_operators_that_disagree_are_rejected() {
+        let mut env = Environment::new();
+        assert!(matches!(
+            parse_expr("1 == 2 == 3", &env),
+            Err(Error::AmbiguousOperators { .. })
+        ));
+        env.declare_operator(Operator {
+            symbol: "<+>".to_string(),
+            precedence: 6,
+            associativity: Associativity::Right,
+            left: Type::Int,
+            right: Type::Int,
+            output: Type::Int,
+            note: String::new(),
+        })
+        .unwrap();
+        assert_eq!(
+            parse_expr("1 + 2 <+> 3", &env),
+            Err(Error::AmbiguousOperators {
+                left: "+".to_string(),
+                right: "<+>".to_string(),
+                precedence: 6,
+            })
+        );
+        same_parse("1 * 2 <+> 3", "(1 * 2) <+> 3", &env);
+        assert!(parse_expr("(1 + 2) <+> 3", &env).is_ok());
+    }
+
+    #[test]
+    fn character_literals_may_escape_their_quote() {
+        let env = Environment::new();
+        for literal in ["'a'", "'\\''", "'\\n'", "'\\\\'"] {
+            assert_eq!(
+                parse_expr(literal, &env),
+                Ok(Expr::Typed(Type::Char)),
+                "{}",
+                literal
+            );
+        }
+        assert!(parse_expr("''", &env).is_err());
+        assert!(parse_expr("'ab'", &env).is_err());
+    }
+}
@@ -0,0 +1,22 @@
+use std::env;
+use std::path::PathBuf;
+
+const APP_DIR: &str = "rust-type-calculator";
+
+/// `$XDG_CONFIG_HOME/rust-type-calculator`, falling back to
+/// `~/.config/rust-type-calculator`.
+pub fn config_dir() -> Option<PathBuf> {
+    env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))
+        .map(|dir| dir.join(APP_DIR))
+}
+
+/// `$XDG_DATA_HOME/rust-type-calculator`, falling back to
+/// `~/.local/share/rust-type-calculator`.
+pub fn data_dir() -> Option<PathBuf> {
+    env::var_os("XDG_DATA_HOME")
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".local/share")))
+        .map(|dir| dir.join(APP_DIR))
+}
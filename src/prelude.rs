@@ -0,0 +1,306 @@
+use crate::operators::{Associativity, Operator};
+use crate::types::type_enum::Type;
+
+pub const PRELUDE_NAME: &str = "core";
+pub const PRELUDE_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// The types every environment knows, with a one-line description.
+pub const BUILTIN_TYPES: &[(&str, &str)] = &[
+    ("Int", "signed integer"),
+    ("Float", "floating point number"),
+    ("Bool", "truth value"),
+    ("String", "text"),
+    ("Char", "single character"),
+    ("Unit", "no meaningful value, the result of a procedure"),
+];
+
+/// A function every environment starts with, plus a short note on what it
+/// means beyond its signature.
+pub struct Builtin {
+    pub name: &'static str,
+    pub input_types: &'static [Type],
+    pub output_type: Type,
+    pub note: &'static str,
+}
+
+pub const BUILTINS: &[Builtin] = &[
+    Builtin {
+        name: "add",
+        input_types: &[Type::Int],
+        output_type: Type::Int,
+        note: "integer addition, one operand at a time",
+    },
+    Builtin {
+        name: "sub",
+        input_types: &[Type::Int],
+        output_type: Type::Int,
+        note: "integer subtraction, one operand at a time",
+    },
+    Builtin {
+        name: "mul",
+        input_types: &[Type::Int],
+        output_type: Type::Int,
+        note: "integer multiplication, one operand at a time",
+    },
+    Builtin {
+        name: "div",
+        input_types: &[Type::Int],
+        output_type: Type::Float,
+        note: "true division, so the quotient of two Ints is a Float",
+    },
+    Builtin {
+        name: "and",
+        input_types: &[Type::Bool],
+        output_type: Type::Bool,
+        note: "logical conjunction",
+    },
+    Builtin {
+        name: "length",
+        input_types: &[Type::String],
+        output_type: Type::Int,
+        note: "number of characters in a string",
+    },
+    Builtin {
+        name: "concat",
+        input_types: &[Type::String, Type::String],
+        output_type: Type::String,
+        note: "joins two strings",
+    },
+    Builtin {
+        name: "ord",
+        input_types: &[Type::Char],
+        output_type: Type::Int,
+        note: "code point of a character",
+    },
+];
+
+pub fn builtin(name: &str) -> Option<&'static Builtin> {
+    BUILTINS.iter().find(|builtin| builtin.name == name)
+}
+
+/// Whether `name` is a builtin still carrying its original signature, i.e.
+/// one the user has not redeclared.
+pub fn is_builtin(name: &str, input_types: &[Type], output_type: &Type) -> bool {
+    builtin(name).is_some_and(|builtin| {
+        builtin.input_types == input_types && builtin.output_type == *output_type
+    })
+}
+
+/// An infix operator every environment starts with.
+pub struct BuiltinOperator {
+    pub symbol: &'static str,
+    pub precedence: u8,
+    pub associativity: Associativity,
+    pub left: Type,
+    pub right: Type,
+    pub output: Type,
+    pub note: &'static str,
+}
+
+const fn operator(
+    symbol: &'static str,
+    precedence: u8,
+    associativity: Associativity,
+    left: Type,
+    right: Type,
+    output: Type,
+    note: &'static str,
+) -> BuiltinOperator {
+    BuiltinOperator {
+        symbol,
+        precedence,
+        associativity,
+        left,
+        right,
+        output,
+        note,
+    }
+}
+
+pub const BUILTIN_OPERATORS: &[BuiltinOperator] = &[
+    operator(
+        "||",
+        2,
+        Associativity::Right,
+        Type::Bool,
+        Type::Bool,
+        Type::Bool,
+        "logical disjunction",
+    ),
+    operator(
+        "&&",
+        3,
+        Associativity::Right,
+        Type::Bool,
+        Type::Bool,
+        Type::Bool,
+        "logical conjunction",
+    ),
+    operator(
+        "==",
+        4,
+        Associativity::None,
+        Type::Int,
+        Type::Int,
+        Type::Bool,
+        "integer equality",
+    ),
+    operator(
+        "<",
+        4,
+        Associativity::None,
+        Type::Int,
+        Type::Int,
+        Type::Bool,
+        "integer ordering",
+    ),
+    operator(
+        "++",
+        5,
+        Associativity::Right,
+        Type::String,
+        Type::String,
+        Type::String,
+        "string concatenation",
+    ),
+    operator(
+        "+",
+        6,
+        Associativity::Left,
+        Type::Int,
+        Type::Int,
+        Type::Int,
+        "integer addition",
+    ),
+    operator(
+        "-",
+        6,
+        Associativity::Left,
+        Type::Int,
+        Type::Int,
+        Type::Int,
+        "integer subtraction",
+    ),
+    operator(
+        "*",
+        7,
+        Associativity::Left,
+        Type::Int,
+        Type::Int,
+        Type::Int,
+        "integer multiplication",
+    ),
+    operator(
+        "/",
+        7,
+        Associativity::Left,
+        Type::Int,
+        Type::Int,
+        Type::Float,
+        "true division, so the quotient of two Ints is a Float",
+    ),
+];
+
+/// A named prelude, for choosing one without building a [`BuiltinSet`] by
+/// hand.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Prelude {
+    /// The `core` prelude, what `Environment::new()` starts with.
+    Standard,
+    /// No builtin functions or operators, like `Environment::empty()`.
+    Empty,
+}
+
+impl Prelude {
+    pub fn builtins(self) -> BuiltinSet {
+        match self {
+            Prelude::Standard => BuiltinSet::core(),
+            Prelude::Empty => BuiltinSet::default(),
+        }
+    }
+}
+
+/// The builtin functions and operators an environment starts with, for
+/// library users who want a prelude other than `core`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BuiltinSet {
+    /// `(name, input types, output type)` of each function.
+    pub functions: Vec<(String, Vec<Type>, Type)>,
+    pub operators: Vec<Operator>,
+}
+
+impl BuiltinSet {
+    /// The `core` prelude every `Environment::new()` starts with.
+    pub fn core() -> Self {
+        BuiltinSet {
+            functions: BUILTINS
+                .iter()
+                .map(|builtin| {
+                    (
+                        builtin.name.to_string(),
+                        builtin.input_types.to_vec(),
+                        builtin.output_type.clone(),
+                    )
+                })
+                .collect(),
+            operators: BUILTIN_OPERATORS
+                .iter()
+                .map(|op| Operator {
+                    symbol: op.symbol.to_string(),
+                    precedence: op.precedence,
+                    associativity: op.associativity,
+                    left: op.left.clone(),
+                    right: op.right.clone(),
+                    output: op.output.clone(),
+                    note: op.note.to_string(),
+                })
+                .collect(),
+        }
+    }
+
+    /// Adds a function, replacing any with the same name.
+    pub fn function(mut self, name: &str, input_types: Vec<Type>, output_type: Type) -> Self {
+        self.functions.retain(|(existing, _, _)| existing != name);
+        self.functions
+            .push((name.to_string(), input_types, output_type));
+        self
+    }
+
+    /// Adds an operator, replacing any with the same symbol.
+    pub fn operator(mut self, operator: Operator) -> Self {
+        self.operators.retain(|op| op.symbol != operator.symbol);
+        self.operators.push(operator);
+        self
+    }
+
+    /// The name snapshots and reports pin the set by: `core` and `empty`
+    /// for the named preludes, and for any other a name derived from its
+    /// signatures, so two different custom sets never pass for each other.
+    pub fn name(&self) -> String {
+        if *self == BuiltinSet::core() {
+            PRELUDE_NAME.to_string()
+        } else if *self == BuiltinSet::default() {
+            "empty".to_string()
+        } else {
+            let signatures = self
+                .functions
+                .iter()
+                .map(|(name, input_types, output_type)| {
+                    format!("{} :: {:?} -> {:?}\n", name, input_types, output_type)
+                })
+                .chain(self.operators.iter().map(|op| format!("{:?}\n", op)))
+                .collect::<String>();
+            format!(
+                "custom-{:08x}",
+                crate::session::fnv1a(signatures.as_bytes()) as u32
+            )
+        }
+    }
+
+    /// Whether `name` is in the set with exactly this signature.
+    pub fn contains(&self, name: &str, input_types: &[Type], output_type: &Type) -> bool {
+        self.functions.iter().any(|(builtin, inputs, output)| {
+            builtin == name && inputs == input_types && output == output_type
+        })
+    }
+}
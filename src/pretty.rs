@@ -0,0 +1,103 @@
+use crate::types::type_definition::TypeDefinition;
+use crate::types::type_enum::Type;
+use crate::{format_elided, Argument};
+
+/// The width `Environment::new()` wraps printed declarations at.
+pub const DEFAULT_WIDTH: usize = 80;
+
+const INDENT: &str = "    ";
+
+fn fits(line: &str, width: Option<usize>) -> bool {
+    width.is_none_or(|width| line.chars().count() <= width)
+}
+
+/// Renders each parameter and the result of a signature, parenthesizing
+/// function types and eliding nesting past `depth`.
+pub fn signature_parts(
+    input_types: &[Type],
+    output_type: &Type,
+    depth: Option<usize>,
+) -> Vec<String> {
+    input_types
+        .iter()
+        .chain(std::iter::once(output_type))
+        .map(|t| match t {
+            Type::Function(..) => format!("({})", format_elided(t, depth)),
+            _ => format_elided(t, depth),
+        })
+        .collect()
+}
+
+/// `name :: A -> B -> C` when it fits in `width` columns, otherwise one
+/// type per line under the name:
+///
+/// ```text
+/// name
+///     :: A
+///     -> B
+///     -> C
+/// ```
+pub fn signature(name: &str, parts: &[String], width: Option<usize>) -> String {
+    let line = format!("{} :: {}", name, parts.join(" -> "));
+    if fits(&line, width) || parts.len() < 2 {
+        return line;
+    }
+    let mut out = name.to_string();
+    for (i, part) in parts.iter().enumerate() {
+        let arrow = if i == 0 { "::" } else { "->" };
+        out.push_str(&format!("\n{}{} {}", INDENT, arrow, part));
+    }
+    out
+}
+
+/// `keyword name = definition` when it fits in `width` columns. Otherwise
+/// a sum type puts each constructor on its own line and a record each
+/// field:
+///
+/// ```text
+/// type Shape
+///     = Circle Float
+///     | Rect Float Float
+///
+/// type Point =
+///     { x: Int
+///     , y: Int
+///     }
+/// ```
+pub fn type_declaration(
+    keyword: &str,
+    name: &str,
+    definition: &TypeDefinition,
+    width: Option<usize>,
+) -> String {
+    let line = format!("{} {} = {}", keyword, name, definition);
+    if fits(&line, width) {
+        return line;
+    }
+    match definition {
+        TypeDefinition::Sum(constructors) if constructors.len() > 1 => {
+            let mut out = format!("{} {}", keyword, name);
+            for (i, (constructor, fields)) in constructors.iter().enumerate() {
+                let separator = if i == 0 { "=" } else { "|" };
+                out.push_str(&format!("\n{}{} {}", INDENT, separator, constructor));
+                for field in fields {
+                    out.push_str(&format!(" {}", Argument(field)));
+                }
+            }
+            out
+        }
+        TypeDefinition::Record(fields) if !fields.is_empty() => {
+            let mut out = format!("{} {} =", keyword, name);
+            for (i, (field, field_type)) in fields.iter().enumerate() {
+                let separator = if i == 0 { "{" } else { "," };
+                out.push_str(&format!(
+                    "\n{}{} {}: {}",
+                    INDENT, separator, field, field_type
+                ));
+            }
+            out.push_str(&format!("\n{}}}", INDENT));
+            out
+        }
+        _ => line,
+    }
+}
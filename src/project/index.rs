@@ -0,0 +1,128 @@
+use crate::suggest::suggestions;
+use crate::types::type_error::Error;
+use std::collections::HashMap;
+
+/// A place in a project's sources.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Location {
+    pub file: String,
+    /// 1-based.
+    pub line: usize,
+    pub text: String,
+}
+
+/// Every name declared across a project, with where it is declared and
+/// every other line that mentions it.
+#[derive(Debug, Default)]
+pub struct SymbolIndex {
+    definitions: HashMap<String, Location>,
+    references: HashMap<String, Vec<Location>>,
+}
+
+impl SymbolIndex {
+    /// Indexes `(file, text)` sources: definitions first, so a name may be
+    /// referenced above its declaration, then references.
+    pub fn build(sources: &[(String, String)]) -> Self {
+        let mut index = SymbolIndex::default();
+        for (file, text) in sources {
+            for (i, line) in text.lines().enumerate() {
+                for name in declared_names(line) {
+                    index.definitions.entry(name).or_insert(Location {
+                        file: file.clone(),
+                        line: i + 1,
+                        text: line.trim().to_string(),
+                    });
+                }
+            }
+        }
+        for (file, text) in sources {
+            for (i, line) in text.lines().enumerate() {
+                for word in words(line) {
+                    let Some(definition) = index.definitions.get(word) else {
+                        continue;
+                    };
+                    if definition.file == *file && definition.line == i + 1 {
+                        continue;
+                    }
+                    let references = index.references.entry(word.to_string()).or_default();
+                    if !references
+                        .iter()
+                        .any(|seen| seen.file == *file && seen.line == i + 1)
+                    {
+                        references.push(Location {
+                            file: file.clone(),
+                            line: i + 1,
+                            text: line.trim().to_string(),
+                        });
+                    }
+                }
+            }
+        }
+        index
+    }
+
+    pub fn definition(&self, name: &str) -> Result<&Location, Error> {
+        self.definitions.get(name).ok_or_else(|| self.unknown(name))
+    }
+
+    pub fn references(&self, name: &str) -> Result<&[Location], Error> {
+        self.definition(name)?;
+        Ok(self.references.get(name).map_or(&[], Vec::as_slice))
+    }
+
+    fn unknown(&self, name: &str) -> Error {
+        Error::UnknownSymbol {
+            name: name.to_string(),
+            suggestions: suggestions(name, self.definitions.keys().map(String::as_str)),
+        }
+    }
+}
+
+/// The identifiers of a line, comments excluded.
+fn words(line: &str) -> impl Iterator<Item = &str> {
+    let code = line.split("--").next().unwrap_or("");
+    code.split(|c: char| !(c.is_alphanumeric() || c == '_'))
+        .filter(|word| word.starts_with(|c: char| c.is_alphabetic() || c == '_'))
+}
+
+/// The names a line declares, whether it is a command such as
+/// `declare_func f Int Int` or a declaration such as `f :: Int -> Int`.
+fn declared_names(line: &str) -> Vec<String> {
+    let line = line.trim();
+    if let Some((name, _)) = line.split_once("::") {
+        let name = name.trim();
+        if !name.is_empty() && !name.contains(char::is_whitespace) {
+            return vec![name.to_string()];
+        }
+    }
+    let tokens: Vec<&str> = line.split_whitespace().collect();
+    match tokens.as_slice() {
+        ["declare_var" | "declare_func" | "bind", name, ..] => vec![name.to_string()],
+        ["declare_vars" | "declare_funcs", names, ..] => {
+            names.split(',').map(str::to_string).collect()
+        }
+        ["alias", name, ..] => vec![name.to_string()],
+        ["newtype", name, ..] => vec![name.to_string()],
+        ["type" | "declare_type", ..] => type_names(line),
+        _ => Vec::new(),
+    }
+}
+
+/// A type and its constructors, from `type Name = A Int | B` or a record.
+fn type_names(line: &str) -> Vec<String> {
+    let Some((head, body)) = line.split_once('=') else {
+        return Vec::new();
+    };
+    let Some(name) = head.split_whitespace().nth(1) else {
+        return Vec::new();
+    };
+    let mut names = vec![name.to_string()];
+    if !body.trim_start().starts_with('{') {
+        names.extend(
+            body.split('|')
+                .filter_map(|constructor| constructor.split_whitespace().next())
+                .map(str::to_string),
+        );
+    }
+    names
+}
@@ -0,0 +1,712 @@
+mod index;
+
+pub use index::{Location, SymbolIndex};
+
+use crate::export;
+use crate::script::{process_decl_block, process_literate, process_script};
+use crate::session;
+use crate::suggest::suggestions;
+use crate::templates::load_template;
+use crate::types::type_definition::TypeDefinition;
+use crate::types::type_enum::Type;
+use crate::types::type_error::Error;
+use crate::Environment;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+pub const MANIFEST_NAME: &str = "typecalc.toml";
+
+/// A `typecalc.toml` project: the scripts to check, in order, the prelude
+/// pack they build on, how strictly to check them and what to export.
+///
+/// Modules are ordered by `import NAME` lines in the sources, where `NAME`
+/// is another source's file name up to its first `.`.
+///
+/// ```toml
+/// [project]
+/// name = "shop"
+/// prelude = "webapi"
+/// sources = ["types.hsig", "checkout.tc", "notes.tc.md"]
+///
+/// [check]
+/// strict = true
+/// deferred = false
+///
+/// [export]
+/// markdown = "out/api.md"
+/// script = "out/replay.tc"
+/// callgraph = "out/calls.dot"
+/// ```
+#[derive(Debug, Default, PartialEq, Eq, Clone)]
+pub struct Manifest {
+    pub name: Option<String>,
+    /// A builtin template or user pack loaded before any source.
+    pub prelude: Option<String>,
+    /// Paths relative to the manifest's directory.
+    pub sources: Vec<String>,
+    /// Whether any error fails the build and skips the exports.
+    pub strict: bool,
+    /// Whether calls to undeclared functions are recorded instead of
+    /// rejected, as with the `deferred` command.
+    pub deferred: bool,
+    /// `(format, path)` pairs, where format is `markdown`, `script` or
+    /// `callgraph`.
+    pub exports: Vec<(String, String)>,
+}
+
+/// A value in the subset of TOML manifests use.
+enum Value {
+    String(String),
+    Bool(bool),
+    Array(Vec<String>),
+}
+
+impl Manifest {
+    /// Parses a manifest. Only tables, comments and `key = value` lines with
+    /// string, boolean or string-array values are understood; arrays may
+    /// span several lines.
+    pub fn parse(source: &str, path: &str) -> Result<Manifest, Error> {
+        let error = |line: usize, message: String| Error::InvalidManifest {
+            path: path.to_string(),
+            line,
+            message,
+        };
+        let mut manifest = Manifest {
+            strict: true,
+            ..Manifest::default()
+        };
+        let mut section = String::new();
+        let mut lines = source.lines().enumerate().map(|(i, l)| (i + 1, l));
+        while let Some((number, line)) = lines.next() {
+            let line = strip_comment(line).trim();
+            if line.is_empty() {
+                continue;
+            }
+            if let Some(name) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+                section = name.trim().to_string();
+                if !matches!(section.as_str(), "project" | "check" | "export") {
+                    return Err(error(number, format!("unknown table `[{}]`", section)));
+                }
+                continue;
+            }
+            let (key, value) = line
+                .split_once('=')
+                .ok_or_else(|| error(number, format!("expected `key = value`, got `{}`", line)))?;
+            let mut value = value.trim().to_string();
+            // An array continues until its closing bracket.
+            if value.starts_with('[') {
+                while !value.ends_with(']') {
+                    let Some((_, next)) = lines.next() else {
+                        return Err(error(number, "array without a closing `]`".to_string()));
+                    };
+                    value.push(' ');
+                    value.push_str(strip_comment(next).trim());
+                }
+            }
+            let value = parse_value(&value).map_err(|message| error(number, message))?;
+            manifest
+                .set(&section, key.trim(), value)
+                .map_err(|message| error(number, message))?;
+        }
+        if manifest.sources.is_empty() {
+            return Err(error(0, "`[project]` lists no `sources`".to_string()));
+        }
+        Ok(manifest)
+    }
+
+    fn set(&mut self, section: &str, key: &str, value: Value) -> Result<(), String> {
+        match (section, key, value) {
+            ("project", "name", Value::String(name)) => self.name = Some(name),
+            ("project", "prelude", Value::String(prelude)) => self.prelude = Some(prelude),
+            ("project", "sources", Value::Array(sources)) => self.sources = sources,
+            ("check", "strict", Value::Bool(strict)) => self.strict = strict,
+            ("check", "deferred", Value::Bool(deferred)) => self.deferred = deferred,
+            ("export", "markdown" | "script" | "callgraph", Value::String(path)) => {
+                self.exports.push((key.to_string(), path))
+            }
+            ("", _, _) => return Err(format!("`{}` must be inside a table", key)),
+            (_, _, _) => return Err(format!("unexpected `{}` in `[{}]`", key, section)),
+        }
+        Ok(())
+    }
+}
+
+fn strip_comment(line: &str) -> &str {
+    let mut quoted = false;
+    for (i, c) in line.char_indices() {
+        match c {
+            '"' => quoted = !quoted,
+            '#' if !quoted => return &line[..i],
+            _ => {}
+        }
+    }
+    line
+}
+
+fn parse_value(value: &str) -> Result<Value, String> {
+    match value {
+        "true" => return Ok(Value::Bool(true)),
+        "false" => return Ok(Value::Bool(false)),
+        _ => {}
+    }
+    if let Some(items) = value.strip_prefix('[').and_then(|v| v.strip_suffix(']')) {
+        return items
+            .split(',')
+            .map(str::trim)
+            .filter(|item| !item.is_empty())
+            .map(parse_string)
+            .collect::<Result<Vec<String>, String>>()
+            .map(Value::Array);
+    }
+    parse_string(value).map(Value::String)
+}
+
+fn parse_string(value: &str) -> Result<String, String> {
+    value
+        .strip_prefix('"')
+        .and_then(|v| v.strip_suffix('"'))
+        .map(str::to_string)
+        .ok_or_else(|| {
+            format!(
+                "expected a quoted string, `true`, `false` or a list, got `{}`",
+                value
+            )
+        })
+}
+
+/// How one module of a build fared.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum ModuleStatus {
+    Checked {
+        errors: usize,
+    },
+    /// Unchanged since the last build, so its declarations came from the
+    /// cache.
+    Cached,
+    /// Not checked because the named module it imports failed.
+    Skipped {
+        failed_import: String,
+    },
+}
+
+/// The outcome of checking a project: every error with the source file and
+/// line it came from, a summary per module in the order they were checked,
+/// and the exports that were written.
+#[derive(Debug, Default)]
+pub struct Build {
+    pub failures: Vec<(String, Option<usize>, Error)>,
+    pub modules: Vec<(String, ModuleStatus)>,
+    pub exported: Vec<String>,
+}
+
+/// A source file of a project. Its name is the file name up to the first
+/// `.`, so `types.hsig` is the module `types`.
+struct Module {
+    name: String,
+    source: String,
+    text: String,
+    /// `import NAME` lines, with their line numbers.
+    imports: Vec<(usize, String)>,
+}
+
+impl Module {
+    fn new(source: &str, text: String) -> Self {
+        let file_name = Path::new(source)
+            .file_name()
+            .map_or(source.to_string(), |name| {
+                name.to_string_lossy().to_string()
+            });
+        let name = file_name.split('.').next().unwrap_or("").to_string();
+        let imports = text
+            .lines()
+            .enumerate()
+            .filter_map(
+                |(i, line)| match line.split_whitespace().collect::<Vec<&str>>()[..] {
+                    ["import", module] => Some((i + 1, module.to_string())),
+                    _ => None,
+                },
+            )
+            .collect();
+        Module {
+            name,
+            source: source.to_string(),
+            text,
+            imports,
+        }
+    }
+
+    /// The module's text with its imports blanked out, keeping the line
+    /// numbers; they only decide the order modules are checked in.
+    fn body(&self) -> String {
+        self.text
+            .lines()
+            .enumerate()
+            .map(|(i, line)| {
+                if self.imports.iter().any(|(number, _)| *number == i + 1) {
+                    ""
+                } else {
+                    line
+                }
+            })
+            .collect::<Vec<&str>>()
+            .join("\n")
+    }
+
+    fn check(&self, env: &mut Environment) -> Vec<(usize, Error)> {
+        let body = self.body();
+        let results = if self.source.ends_with(".hsig") {
+            let lines: Vec<(usize, &str)> =
+                body.lines().enumerate().map(|(i, l)| (i + 1, l)).collect();
+            match process_decl_block(&lines, env) {
+                Ok(_) => Vec::new(),
+                Err(errors) => errors.into_iter().map(|(n, e)| (n, Err(e))).collect(),
+            }
+        } else if self.source.ends_with(".tc.md") {
+            process_literate(&body, env)
+        } else {
+            process_script(&body, env)
+        };
+        results
+            .into_iter()
+            .filter_map(|(line, result)| result.err().map(|err| (line, err)))
+            .collect()
+    }
+}
+
+pub const CACHE_DIR: &str = ".typecalc-cache";
+
+/// The declarations of an environment, to tell what a module added.
+struct Declarations {
+    variables: HashMap<String, Type>,
+    functions: HashMap<String, (Type, Vec<Type>)>,
+    types: HashMap<String, TypeDefinition>,
+    aliases: HashMap<String, Type>,
+}
+
+impl Declarations {
+    fn of(env: &Environment) -> Self {
+        Declarations {
+            variables: env.variables.clone(),
+            functions: env.functions.clone(),
+            types: env.types.clone(),
+            aliases: env.aliases.clone(),
+        }
+    }
+
+    /// The declarations `env` has that differ from these, rendered as a
+    /// snapshot that can be loaded back.
+    fn added_in(&self, env: &Environment) -> String {
+        fn changed<V: Clone + PartialEq>(
+            before: &HashMap<String, V>,
+            after: &HashMap<String, V>,
+        ) -> HashMap<String, V> {
+            after
+                .iter()
+                .filter(|(name, value)| before.get(*name) != Some(value))
+                .map(|(name, value)| (name.clone(), value.clone()))
+                .collect()
+        }
+        let mut added = Environment::with_builtins(env.builtins().clone());
+        added.variables = changed(&self.variables, &env.variables);
+        added.functions = changed(&self.functions, &env.functions);
+        added.types = changed(&self.types, &env.types);
+        added.aliases = changed(&self.aliases, &env.aliases);
+        session::snapshot(added.view())
+    }
+}
+
+/// Per-module results of earlier builds under `.typecalc-cache/`. A module
+/// that checked cleanly is stored as the declarations it added, keyed by a
+/// hash of its text, the interfaces of every module checked before it and
+/// the manifest settings that affect checking. Every earlier module counts,
+/// not just the imported ones, since a module sees everything declared
+/// before it in manifest order. Modules with errors are always checked
+/// again, as is everything after one, and so is everything when a call
+/// graph is exported, since the cache does not keep the calls a module
+/// made.
+struct Cache {
+    enabled: bool,
+    dir: PathBuf,
+    settings: String,
+    /// The modules handled so far this build, in order, each with the hash
+    /// of its interface, or `None` when it failed or was skipped.
+    earlier: Vec<(String, Option<u64>)>,
+}
+
+const CACHE_KEY_PREFIX: &str = "-- cache key ";
+
+impl Cache {
+    fn new(dir: PathBuf, manifest: &Manifest) -> Self {
+        Cache {
+            enabled: !manifest
+                .exports
+                .iter()
+                .any(|(format, _)| format == "callgraph"),
+            dir,
+            settings: format!("{:?} {}", manifest.prelude, manifest.deferred),
+            earlier: Vec::new(),
+        }
+    }
+
+    /// Whether entries may be used now: not once a module failed, since
+    /// what it left declared is not captured by any interface.
+    fn usable(&self) -> bool {
+        self.enabled
+            && self
+                .earlier
+                .iter()
+                .all(|(_, interface)| interface.is_some())
+    }
+
+    fn key(&self, module: &Module) -> String {
+        let earlier: Vec<String> = self
+            .earlier
+            .iter()
+            .map(|(name, interface)| format!("{}={:?}", name, interface))
+            .collect();
+        let keyed = format!("{}\n{}\n{}", self.settings, earlier.join(" "), module.text);
+        format!(
+            "{}{:016x}\n",
+            CACHE_KEY_PREFIX,
+            session::fnv1a(keyed.as_bytes())
+        )
+    }
+
+    fn path(&self, module: &Module) -> PathBuf {
+        self.dir.join(format!("{}.hsig", module.name))
+    }
+
+    /// Loads a module's cached declarations into `env` if its entry is
+    /// current.
+    fn restore(&mut self, module: &Module, env: &mut Environment) -> bool {
+        if !self.usable() {
+            return false;
+        }
+        let Ok(cached) = fs::read_to_string(self.path(module)) else {
+            return false;
+        };
+        let Some(interface) = cached.strip_prefix(&self.key(module)) else {
+            return false;
+        };
+        let lines: Vec<(usize, &str)> = interface
+            .lines()
+            .enumerate()
+            .map(|(i, l)| (i + 1, l))
+            .collect();
+        if process_decl_block(&lines, env).is_err() {
+            return false;
+        }
+        self.earlier.push((
+            module.name.clone(),
+            Some(session::fnv1a(interface.as_bytes())),
+        ));
+        true
+    }
+
+    /// Best effort: a cache that cannot be written only makes the next
+    /// build slower.
+    fn store(&mut self, module: &Module, interface: String) {
+        if self.usable() {
+            let contents = self.key(module) + &interface;
+            if fs::create_dir_all(&self.dir).is_ok() {
+                let _ = fs::write(self.path(module), contents);
+            }
+        }
+        self.earlier.push((
+            module.name.clone(),
+            Some(session::fnv1a(interface.as_bytes())),
+        ));
+    }
+
+    /// Records a module that failed or was skipped.
+    fn fail(&mut self, module: &Module) {
+        self.earlier.push((module.name.clone(), None));
+    }
+}
+
+/// Orders modules so each comes after the modules it imports, otherwise
+/// keeping the manifest's order. Fails on the first import cycle found.
+fn dependency_order(modules: &[Module]) -> Result<Vec<usize>, Error> {
+    fn visit(
+        index: usize,
+        modules: &[Module],
+        state: &mut [u8],
+        path: &mut Vec<usize>,
+        order: &mut Vec<usize>,
+    ) -> Result<(), Error> {
+        match state[index] {
+            2 => return Ok(()),
+            1 => {
+                let start = path.iter().position(|i| *i == index).unwrap_or(0);
+                let cycle = path[start..]
+                    .iter()
+                    .chain(std::iter::once(&index))
+                    .map(|i| modules[*i].name.clone())
+                    .collect();
+                return Err(Error::ImportCycle(cycle));
+            }
+            _ => {}
+        }
+        state[index] = 1;
+        path.push(index);
+        for (_, import) in &modules[index].imports {
+            if let Some(dependency) = modules.iter().position(|m| m.name == *import) {
+                visit(dependency, modules, state, path, order)?;
+            }
+        }
+        path.pop();
+        state[index] = 2;
+        order.push(index);
+        Ok(())
+    }
+
+    let mut state = vec![0; modules.len()];
+    let mut order = Vec::new();
+    for index in 0..modules.len() {
+        visit(index, modules, &mut state, &mut Vec::new(), &mut order)?;
+    }
+    Ok(order)
+}
+
+/// Indexes the declarations and references in every source of the project
+/// at `path`.
+pub fn index(path: &Path) -> Result<SymbolIndex, Error> {
+    let manifest_path = manifest_path(path);
+    let display = manifest_path.display().to_string();
+    let source = fs::read_to_string(&manifest_path).map_err(|err| Error::Io {
+        path: display.clone(),
+        message: err.to_string(),
+    })?;
+    let manifest = Manifest::parse(&source, &display)?;
+    let root = manifest_path.parent().unwrap_or(Path::new("."));
+    let sources = manifest
+        .sources
+        .iter()
+        .map(|source| {
+            let path = root.join(source);
+            fs::read_to_string(&path)
+                .map(|text| (source.clone(), text))
+                .map_err(|err| Error::Io {
+                    path: path.display().to_string(),
+                    message: err.to_string(),
+                })
+        })
+        .collect::<Result<Vec<(String, String)>, Error>>()?;
+    Ok(SymbolIndex::build(&sources))
+}
+
+/// Finds the manifest for `path`, which may name the manifest itself or the
+/// directory holding it.
+pub fn manifest_path(path: &Path) -> PathBuf {
+    if path.is_dir() {
+        path.join(MANIFEST_NAME)
+    } else {
+        path.to_path_buf()
+    }
+}
+
+/// Checks every module of the project at `path` in one environment, each
+/// after the modules it imports and otherwise in the order the manifest
+/// lists them, then writes the configured exports. A module importing one
+/// that failed is skipped, and one unchanged since the last build, along
+/// with the interfaces it imports, is restored from the cache. A strict
+/// build writes nothing when anything failed.
+pub fn build(path: &Path, env: &mut Environment) -> Result<Build, Error> {
+    let manifest_path = manifest_path(path);
+    let display = manifest_path.display().to_string();
+    let source = fs::read_to_string(&manifest_path).map_err(|err| Error::Io {
+        path: display.clone(),
+        message: err.to_string(),
+    })?;
+    let manifest = Manifest::parse(&source, &display)?;
+    let root = manifest_path.parent().unwrap_or(Path::new("."));
+
+    let mut build = Build::default();
+    if let Some(prelude) = &manifest.prelude {
+        load_template(prelude, env)?;
+    }
+    env.deferred = manifest.deferred;
+
+    let mut modules = Vec::new();
+    for source in &manifest.sources {
+        let path = root.join(source);
+        match fs::read_to_string(&path) {
+            Ok(text) => modules.push(Module::new(source, text)),
+            Err(err) => {
+                let err = Error::Io {
+                    path: path.display().to_string(),
+                    message: err.to_string(),
+                };
+                build.failures.push((source.clone(), None, err));
+            }
+        }
+    }
+    let names: Vec<&str> = modules.iter().map(|m| m.name.as_str()).collect();
+    let mut failed: Vec<&str> = Vec::new();
+    let mut cache = Cache::new(root.join(CACHE_DIR), &manifest);
+    for index in dependency_order(&modules)? {
+        let module = &modules[index];
+        let skipped = module
+            .imports
+            .iter()
+            .find(|(_, import)| failed.contains(&import.as_str()));
+        let status = match skipped {
+            Some((_, import)) => ModuleStatus::Skipped {
+                failed_import: import.clone(),
+            },
+            None if cache.restore(module, env) => ModuleStatus::Cached,
+            None => {
+                let before = Declarations::of(env);
+                let mut errors: Vec<(usize, Error)> = module
+                    .imports
+                    .iter()
+                    .filter(|(_, import)| !names.contains(&import.as_str()))
+                    .map(|(line, import)| {
+                        let err = Error::UnknownModule {
+                            name: import.clone(),
+                            suggestions: suggestions(import, names.iter().copied()),
+                        };
+                        (*line, err)
+                    })
+                    .collect();
+                errors.extend(module.check(env));
+                errors.sort_by_key(|(line, _)| *line);
+                if errors.is_empty() {
+                    cache.store(module, before.added_in(env));
+                }
+                let count = errors.len();
+                for (line, err) in errors {
+                    build
+                        .failures
+                        .push((module.source.clone(), Some(line), err));
+                }
+                ModuleStatus::Checked { errors: count }
+            }
+        };
+        if !matches!(
+            status,
+            ModuleStatus::Checked { errors: 0 } | ModuleStatus::Cached
+        ) {
+            cache.fail(module);
+            failed.push(&module.name);
+        }
+        build.modules.push((module.name.clone(), status));
+    }
+
+    if manifest.strict && !build.failures.is_empty() {
+        return Ok(build);
+    }
+    for (format, target) in &manifest.exports {
+        let contents = match format.as_str() {
+            "markdown" => export::markdown(env.view(), env.display_width),
+            "script" => export::script(env.view()),
+            _ => export::callgraph_dot(env.view(), &env.audit),
+        };
+        let target = root.join(target);
+        if let Some(dir) = target.parent() {
+            let _ = fs::create_dir_all(dir);
+        }
+        fs::write(&target, contents).map_err(|err| Error::Io {
+            path: target.display().to_string(),
+            message: err.to_string(),
+        })?;
+        build.exported.push(target.display().to_string());
+    }
+    Ok(build)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A fresh project directory under the system temp dir.
+    fn project(name: &str, files: &[(&str, &str)]) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("typecalc-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        for (file, text) in files {
+            fs::write(dir.join(file), text).unwrap();
+        }
+        dir
+    }
+
+    #[test]
+    fn manifest_reads_tables_comments_and_multiline_arrays() {
+        let source = "\
+# a shop
+[project]
+name = \"shop\" # trailing comment
+sources = [
+    \"types.hsig\",  # the model
+    \"checkout.tc\",
+]
+
+[check]
+deferred = true
+
+[export]
+markdown = \"out/#api.md\"
+";
+        let manifest = Manifest::parse(source, MANIFEST_NAME).unwrap();
+        assert_eq!(manifest.name.as_deref(), Some("shop"));
+        assert_eq!(manifest.prelude, None);
+        assert_eq!(manifest.sources, ["types.hsig", "checkout.tc"]);
+        assert!(manifest.strict);
+        assert!(manifest.deferred);
+        assert_eq!(
+            manifest.exports,
+            [("markdown".to_string(), "out/#api.md".to_string())]
+        );
+    }
+
+    #[test]
+    fn manifest_errors_name_the_line() {
+        let line_of = |source: &str| match Manifest::parse(source, MANIFEST_NAME) {
+            Err(Error::InvalidManifest { line, .. }) => line,
+            other => panic!("expected an invalid manifest, got {:?}", other),
+        };
+        assert_eq!(line_of("name = \"shop\"\n"), 1);
+        assert_eq!(line_of("[project]\nsources = [\"a.tc\"]\n[build]\n"), 3);
+        assert_eq!(
+            line_of("[project]\nsources = [\"a.tc\"]\nstrict = true\n"),
+            3
+        );
+        assert_eq!(line_of("[project]\nsources = [\"a.tc\",\n"), 2);
+        assert_eq!(line_of("[project]\nname = shop\n"), 2);
+        assert_eq!(line_of("[project]\nname = \"shop\"\n"), 0);
+    }
+
+    #[test]
+    fn cache_is_invalidated_by_an_earlier_module_without_an_import() {
+        let manifest = "[project]\nsources = [\"a.tc\", \"b.tc\"]\n";
+        let dir = project(
+            "cache",
+            &[
+                (MANIFEST_NAME, manifest),
+                ("a.tc", "declare_func f Int Int\n"),
+                ("b.tc", "call f 1\n"),
+            ],
+        );
+        let first = build(&dir, &mut Environment::new()).unwrap();
+        assert!(first.failures.is_empty());
+
+        fs::write(dir.join("a.tc"), "declare_func f Bool Int\n").unwrap();
+        let second = build(&dir, &mut Environment::new()).unwrap();
+        assert_eq!(
+            second.modules[1],
+            ("b".to_string(), ModuleStatus::Checked { errors: 1 })
+        );
+        assert_eq!(second.failures.len(), 1);
+
+        // Unchanged sources come from the cache again.
+        fs::write(dir.join("b.tc"), "call f true\n").unwrap();
+        build(&dir, &mut Environment::new()).unwrap();
+        let fourth = build(&dir, &mut Environment::new()).unwrap();
+        assert!(fourth
+            .modules
+            .iter()
+            .all(|(_, status)| *status == ModuleStatus::Cached));
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,506 @@
+use crate::types::type_enum::Type;
+use crate::types::type_error::Error;
+use crate::value::Value;
+use crate::{
+    check_call, declare_alias, declare_signature, declare_type_definition, is_operator_line,
+    newtype_definition, parse_alias_line, parse_operator_line, parse_signature_with,
+    parse_type_line, process_input, tag_declaration, Environment,
+};
+
+/// Runs a whole script, one command per line. Lines inside a
+/// `begin decls` ... `end` block are `name :: Sig` declarations handled as a
+/// unit by [`process_decl_block`], and a `test "name"` ... `end` block runs
+/// as a [`TestOutcome`]. Every outcome carries its 1-based line.
+pub fn process_script(source: &str, env: &mut Environment) -> Vec<(usize, Result<String, Error>)> {
+    run_script(source, env, &mut Vec::new())
+}
+
+/// Runs a script like [`process_script`], but hands each outcome to
+/// `report` as soon as its line has run, with the environment as that line
+/// left it, so warnings can be taken line by line.
+pub fn process_script_with(
+    source: &str,
+    env: &mut Environment,
+    report: impl FnMut(usize, Result<String, Error>, &mut Environment),
+) {
+    run_script_with(source, env, &mut Vec::new(), report)
+}
+
+/// Runs a script in a copy of the environment and returns the outcomes of
+/// its test blocks; the rest of the script is their setup, and only its
+/// errors are kept.
+pub fn process_tests(source: &str, env: &Environment) -> TestReport {
+    let mut tests = Vec::new();
+    let results = run_script(source, &mut env.clone(), &mut tests);
+    let in_tests = tests
+        .iter()
+        .flat_map(|test| test.failures.iter().map(|(line, _)| *line))
+        .collect::<Vec<usize>>();
+    let errors = results
+        .into_iter()
+        .filter_map(|(line, result)| result.err().map(|err| (line, err)))
+        .filter(|(line, _)| !in_tests.contains(line))
+        .collect();
+    TestReport { tests, errors }
+}
+
+/// What running a script's tests found.
+#[derive(Debug)]
+pub struct TestReport {
+    pub tests: Vec<TestOutcome>,
+    /// Errors outside any test block, such as a failing setup command or a
+    /// `test` without its `end`. Any of them fails the file as a whole.
+    pub errors: Vec<(usize, Error)>,
+}
+
+impl TestReport {
+    pub fn passed(&self) -> bool {
+        self.errors.is_empty() && self.tests.iter().all(TestOutcome::passed)
+    }
+}
+
+/// The outcome of a `test "name"` ... `end` block.
+#[derive(Debug)]
+pub struct TestOutcome {
+    pub name: String,
+    /// Line of the `test` header.
+    pub line: usize,
+    /// How many assertions the block made.
+    pub assertions: usize,
+    /// Every command of the block that failed, assertion or not.
+    pub failures: Vec<(usize, Error)>,
+}
+
+impl TestOutcome {
+    pub fn passed(&self) -> bool {
+        self.failures.is_empty()
+    }
+
+    pub fn summary(&self) -> String {
+        let failed = self
+            .failures
+            .iter()
+            .filter(|(_, err)| {
+                matches!(
+                    err,
+                    Error::AssertionFailed { .. } | Error::ExpectedError { .. }
+                )
+            })
+            .count();
+        format!(
+            "test \"{}\": {} of {} assertion(s) passed{}",
+            self.name,
+            self.assertions - failed,
+            self.assertions,
+            if self.passed() { "" } else { ", FAILED" }
+        )
+    }
+}
+
+/// Commands that make an assertion counted by a test block.
+const ASSERTIONS: &[&str] = &["assert_type", "assert_error"];
+
+fn run_script(
+    source: &str,
+    env: &mut Environment,
+    tests: &mut Vec<TestOutcome>,
+) -> Vec<(usize, Result<String, Error>)> {
+    let mut results = Vec::new();
+    run_script_with(source, env, tests, |number, result, _| {
+        results.push((number, result))
+    });
+    results
+}
+
+/// Runs a script, handing each outcome to `report` as soon as its line has
+/// run, with the environment as that line left it.
+fn run_script_with(
+    source: &str,
+    env: &mut Environment,
+    tests: &mut Vec<TestOutcome>,
+    mut report: impl FnMut(usize, Result<String, Error>, &mut Environment),
+) {
+    let mut lines = source.lines().enumerate().map(|(i, line)| (i + 1, line));
+
+    while let Some((number, line)) = lines.next() {
+        let line = line.trim();
+        if let Some(name) = test_name(line) {
+            let (block, closed) = test_block(&mut lines);
+            if !closed {
+                let err = Error::Syntax("`test` without a matching `end`".to_string());
+                report(number, Err(err), env);
+                continue;
+            }
+            let test = run_test_block(name, number, &block, env);
+            report(number, Ok(test.summary()), env);
+            for (line, err) in &test.failures {
+                report(*line, Err(err.clone()), env);
+            }
+            tests.push(test);
+            continue;
+        }
+        if line != "begin decls" {
+            let result = process_input(line, env);
+            report(number, result, env);
+            continue;
+        }
+
+        let mut block = Vec::new();
+        let mut closed = false;
+        for (inner_number, inner) in lines.by_ref() {
+            if inner.trim() == "end" {
+                closed = true;
+                break;
+            }
+            block.push((inner_number, inner));
+        }
+
+        if !closed {
+            let err = Error::Syntax("`begin decls` without a matching `end`".to_string());
+            report(number, Err(err), env);
+        } else {
+            match process_decl_block(&block, env) {
+                Ok(output) => report(number, Ok(output), env),
+                Err(errors) => {
+                    for (line, err) in errors {
+                        report(line, Err(err), env);
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// The name in a `test "name"` header.
+fn test_name(line: &str) -> Option<&str> {
+    let name = line.strip_prefix("test ")?.trim();
+    name.strip_prefix('"')?.strip_suffix('"')
+}
+
+/// The lines up to the `end` closing a test block, skipping over the `end`
+/// of any block nested inside it, and whether that `end` was found.
+fn test_block<'a>(lines: &mut impl Iterator<Item = (usize, &'a str)>) -> (Vec<String>, bool) {
+    let mut block = Vec::new();
+    let mut depth = 0;
+    for (_, line) in lines {
+        match line.trim() {
+            "end" if depth == 0 => return (block, true),
+            "end" => depth -= 1,
+            line if opens_block(line) => depth += 1,
+            _ => {}
+        }
+        block.push(line.to_string());
+    }
+    (block, false)
+}
+
+/// Whether `line` opens a block closed by its own `end`: a scope, a
+/// `begin decls` block or a test.
+pub fn opens_block(line: &str) -> bool {
+    let line = line.trim();
+    line == "begin" || line == "begin decls" || test_name(line).is_some()
+}
+
+/// Runs a test block in a fork of the environment, so nothing it declares
+/// outlives it.
+fn run_test_block(name: &str, line: usize, block: &[String], env: &Environment) -> TestOutcome {
+    let mut fork = env.clone();
+    let results = run_script(&block.join("\n"), &mut fork, &mut Vec::new());
+    let assertions = block
+        .iter()
+        .filter(|line| {
+            line.split_whitespace()
+                .next()
+                .is_some_and(|command| ASSERTIONS.contains(&command))
+        })
+        .count();
+    TestOutcome {
+        name: name.to_string(),
+        line,
+        assertions,
+        failures: results
+            .into_iter()
+            .filter_map(|(number, result)| result.err().map(|err| (line + number, err)))
+            .collect(),
+    }
+}
+
+/// Runs a literate `.tc.md` script: only fenced code blocks are executed,
+/// prose is ignored. Blocks tagged `haskell` or `hsig` hold `name :: Sig`
+/// declarations, any other block holds commands. Line numbers refer to the
+/// Markdown file.
+pub fn process_literate(
+    source: &str,
+    env: &mut Environment,
+) -> Vec<(usize, Result<String, Error>)> {
+    process_script(&literate_script(source), env)
+}
+
+/// Runs a literate script like [`process_literate`], handing each outcome
+/// to `report` as [`process_script_with`] does.
+pub fn process_literate_with(
+    source: &str,
+    env: &mut Environment,
+    report: impl FnMut(usize, Result<String, Error>, &mut Environment),
+) {
+    process_script_with(&literate_script(source), env, report)
+}
+
+/// The script a literate file runs, one line per line of the file.
+fn literate_script(source: &str) -> String {
+    // Prose becomes blank lines so the script keeps the file's numbering.
+    let mut script = Vec::new();
+    let mut fence: Option<bool> = None;
+    for line in source.lines() {
+        let trimmed = line.trim();
+        match (fence, trimmed.strip_prefix("```")) {
+            (None, Some(info)) => {
+                let declarations = matches!(info.trim(), "haskell" | "hsig");
+                script.push(if declarations { "begin decls" } else { "" });
+                fence = Some(declarations);
+            }
+            (Some(declarations), Some(_)) => {
+                script.push(if declarations { "end" } else { "" });
+                fence = None;
+            }
+            (Some(_), None) => script.push(line),
+            (None, None) => script.push(""),
+        }
+    }
+    script.join("\n")
+}
+
+/// Declares every `name :: Sig`, `type Name = Body`, `newtype Name = Type`
+/// and `alias Name = Type` line of a block, or nothing at all: when any line
+/// fails to parse, all of the failures are returned together, and when a
+/// declaration is rejected the environment is left as it was. Types and
+/// then aliases are declared first, so any line may use them; an alias may
+/// only use aliases above it. Operators, declared with a line such as
+/// `infixl 6 (<+>) :: Int -> Int -> Int`, come next. A variable may hold a
+/// literal value, as in `x :: Int = 5`. `tag NAME TAGS` lines apply last,
+/// so they may tag anything in the block.
+pub fn process_decl_block(
+    lines: &[(usize, &str)],
+    env: &mut Environment,
+) -> Result<String, Vec<(usize, Error)>> {
+    let mut types = Vec::new();
+    let mut aliases = Vec::new();
+    let mut operators = Vec::new();
+    let mut signatures = Vec::new();
+    let mut tags = Vec::new();
+    let mut errors = Vec::new();
+    if env.is_frozen() {
+        let number = lines.first().map_or(0, |(number, _)| *number);
+        return Err(vec![(number, Error::EnvironmentFrozen)]);
+    }
+
+    let lines: Vec<(usize, &str)> = lines
+        .iter()
+        .map(|(number, line)| (*number, line.trim()))
+        .filter(|(_, line)| !line.is_empty() && !line.starts_with("--"))
+        .collect();
+    let mut pending: Vec<(&str, Type)> = lines
+        .iter()
+        .filter_map(|(_, line)| {
+            line.strip_prefix("type ")
+                .or_else(|| line.strip_prefix("newtype "))
+        })
+        .filter_map(|line| line.split_once('=').map(|(name, _)| name.trim()))
+        .map(|name| (name, Type::Named(name.to_string())))
+        .collect();
+    for (number, line) in &lines {
+        if let Some(line) = line.strip_prefix("alias ") {
+            match parse_alias_line(line, env, &pending) {
+                Ok((name, target)) => {
+                    pending.push((name, target.clone()));
+                    aliases.push((*number, name, target));
+                }
+                Err(err) => errors.push((*number, err)),
+            }
+        }
+    }
+
+    for (number, line) in &lines {
+        let parsed = if line.starts_with("alias ") {
+            continue;
+        } else if let Some(rest) = tag_line(line) {
+            match rest.split_whitespace().collect::<Vec<&str>>()[..] {
+                [name, names] => {
+                    tags.push((*number, name, names));
+                    Ok(())
+                }
+                _ => Err(Error::Syntax(format!(
+                    "expected `tag NAME TAG,...`, got `{}`",
+                    line
+                ))),
+            }
+        } else if is_operator_line(line) {
+            parse_operator_line(line, env, &pending)
+                .map(|operator| operators.push((*number, operator)))
+        } else if let Some(line) = line.strip_prefix("type ") {
+            parse_type_line(line, env, &pending)
+                .map(|(name, definition)| types.push((*number, name, definition)))
+        } else if let Some(line) = line.strip_prefix("newtype ") {
+            parse_alias_line(line, env, &pending).map(|(name, wrapped)| {
+                types.push((*number, name, newtype_definition(name, wrapped)))
+            })
+        } else {
+            parse_valued_signature(line, env, &pending)
+                .map(|(name, types, value)| signatures.push((*number, name, types, value)))
+        };
+        if let Err(err) = parsed {
+            errors.push((*number, err));
+        }
+    }
+    errors.sort_by_key(|(number, _)| *number);
+
+    if !errors.is_empty() {
+        return Err(errors);
+    }
+
+    // Declared into a copy, kept only once every declaration succeeds.
+    let mut declared = env.clone();
+    let mut reports = Vec::new();
+    for (number, name, definition) in types {
+        reports.push(
+            declare_type_definition(name, definition, &mut declared)
+                .map_err(|err| vec![(number, err)])?,
+        );
+    }
+    for (number, name, target) in aliases {
+        reports
+            .push(declare_alias(name, target, &mut declared).map_err(|err| vec![(number, err)])?);
+    }
+    for (number, operator) in operators {
+        let report = format!("({}) :: {}", operator.symbol, operator.signature());
+        declared
+            .declare_operator(operator)
+            .map_err(|err| vec![(number, err)])?;
+        reports.push(report);
+    }
+    for (number, name, mut types, value) in signatures {
+        let report = match value {
+            Some(value) => {
+                let var_type = types.pop().unwrap();
+                declared
+                    .define_variable(&name, var_type.clone(), value)
+                    .map(|()| {
+                        format!(
+                            "{} :: {} = {}",
+                            name,
+                            var_type,
+                            declared.value(&name).unwrap()
+                        )
+                    })
+            }
+            None => declare_signature(&name, types, &mut declared),
+        };
+        reports.push(report.map_err(|err| vec![(number, err)])?);
+    }
+    for (number, name, names) in tags {
+        reports.push(
+            tag_declaration(&[name, names], &mut declared).map_err(|err| vec![(number, err)])?,
+        );
+    }
+    *env = declared;
+    Ok(reports.join("\n"))
+}
+
+/// Parses a `name :: Sig` line, or `name :: Type = value` for a variable
+/// holding a literal value.
+fn parse_valued_signature(
+    line: &str,
+    env: &Environment,
+    pending: &[(&str, Type)],
+) -> Result<(String, Vec<Type>, Option<Value>), Error> {
+    // Types never contain `=`, so the first one after `::` starts the value.
+    let (signature, literal) = match line.split_once("::") {
+        Some((name, rest)) if rest.contains('=') => {
+            let at = name.len() + 2 + rest.find('=').unwrap();
+            (&line[..at], Some(&line[at + 1..]))
+        }
+        _ => (line, None),
+    };
+    let (name, types) = parse_signature_with(signature, env, pending)?;
+    let Some(literal) = literal else {
+        return Ok((name, types, None));
+    };
+    if types.len() != 1 {
+        return Err(Error::Syntax(format!(
+            "only a variable can hold a value, `{}` is a function",
+            name
+        )));
+    }
+    let value = Value::from_literal(literal)
+        .ok_or_else(|| Error::Syntax(format!("`{}` is not a literal value", literal.trim())))?;
+    Ok((name, types, Some(value)))
+}
+
+/// What follows `tag` on a `tag NAME TAGS` line, which a signature of
+/// something named `tag` is not.
+fn tag_line(line: &str) -> Option<&str> {
+    line.strip_prefix("tag ")
+        .filter(|rest| !rest.trim_start().starts_with("::"))
+}
+
+/// Typechecks the `@type name :: Sig` and `@call f ARG...` annotations found
+/// anywhere in a free-form notes file. Every `@type` is declared first, so
+/// calls may refer to names annotated further down. Returns one report line
+/// per annotation, prefixed with its line number.
+pub fn annotate(source: &str, env: &mut Environment) -> Result<String, Error> {
+    let mut types = Vec::new();
+    let mut calls = Vec::new();
+    for (number, line) in source.lines().enumerate().map(|(i, l)| (i + 1, l)) {
+        if let Some((_, annotation)) = line.split_once("@type ") {
+            types.push((number, annotation.trim()));
+        } else if let Some((_, annotation)) = line.split_once("@call ") {
+            calls.push((number, annotation.trim()));
+        }
+    }
+
+    if let Err(errors) = process_decl_block(&types, env) {
+        return Err(Error::ImportFailed {
+            path: "@type annotations".to_string(),
+            errors,
+        });
+    }
+
+    let mut report: Vec<(usize, String)> = types
+        .iter()
+        .map(|(number, annotation)| (*number, format!("@type {}  -- ok", annotation)))
+        .collect();
+    for (number, annotation) in calls {
+        let tokens: Vec<&str> = annotation.split_whitespace().collect();
+        let outcome = match check_call(&tokens, env) {
+            Ok(return_type) => format!("{}", return_type),
+            // Only the headline of multi-line errors fits on the line.
+            Err(err) => format!("error: {}", err.to_string().lines().next().unwrap_or("")),
+        };
+        report.push((number, format!("@call {}  -- {}", annotation, outcome)));
+    }
+
+    report.sort_by_key(|(number, _)| *number);
+    Ok(report
+        .into_iter()
+        .map(|(number, line)| format!("{}: {}", number, line))
+        .collect::<Vec<String>>()
+        .join("\n"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn a_rejected_declaration_leaves_the_block_undeclared() {
+        let mut env = Environment::new();
+        let source = "strict on\ndeclare_var y Int\nbegin decls\nx :: Int\ny :: Bool\nend\n";
+        let results = process_script(source, &mut env);
+        assert!(matches!(
+            results.last(),
+            Some((5, Err(Error::Redeclaration { .. })))
+        ));
+        assert!(env.variable("x").is_none());
+        assert_eq!(env.variable("y"), Some(Type::Int));
+    }
+}
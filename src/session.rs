@@ -0,0 +1,678 @@
+use crate::export::named_types;
+use crate::json::Json;
+use crate::paths;
+use crate::prelude::{BuiltinSet, PRELUDE_VERSION};
+use crate::script::process_decl_block;
+use crate::types::type_definition::TypeDefinition;
+use crate::types::type_error::Error;
+use crate::value::Value;
+use crate::{format_signature, Environment, EnvironmentView};
+use std::collections::{BTreeMap, VecDeque};
+use std::fs;
+use std::panic::{self, PanicHookInfo};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Renders every declaration as a `name :: Sig` line, sorted by name, after
+/// the user types as `type Name = Body` lines and the user operators as
+/// `infixl 6 (<+>) :: Int -> Int -> Int` lines, and before a `tag NAME TAGS`
+/// line for each tagged declaration, which is the format snapshots are
+/// stored in. A variable holding a literal value is `name :: Type = value`.
+/// Constructors are left to their type's line. A leading comment pins the
+/// prelude the builtins came from, and a checksum of everything below it
+/// comes first.
+pub fn snapshot(env: EnvironmentView) -> String {
+    let header = prelude_header(&env.builtins().name(), PRELUDE_VERSION);
+    let types = env
+        .types()
+        .into_iter()
+        .map(|(name, definition)| format!("{} {} = {}\n", definition.keyword(), name, definition))
+        .chain(
+            env.aliases()
+                .into_iter()
+                .map(|(name, target)| format!("alias {} = {}\n", name, target)),
+        )
+        .chain(
+            env.user_operators()
+                .into_iter()
+                .map(|operator| operator.declaration() + "\n"),
+        )
+        .collect::<String>();
+    let mut lines = Vec::new();
+    for (name, var_type) in env.user_variables() {
+        // Only values written as literals can be read back.
+        match env
+            .value(name)
+            .filter(|value| Value::from_literal(&value.to_string()).as_ref() == Some(*value))
+        {
+            Some(value) => lines.push(format!("{} :: {} = {}", name, var_type, value)),
+            None => lines.push(format!("{} :: {}", name, var_type)),
+        }
+    }
+    for (name, input_types, output_type) in env.functions() {
+        if env.is_constructor(name) {
+            continue;
+        }
+        lines.push(format!(
+            "{} :: {}",
+            name,
+            format_signature(input_types, &output_type)
+        ));
+    }
+    lines.sort();
+    for (name, tags) in env.tags() {
+        let tags = tags.into_iter().collect::<Vec<String>>();
+        lines.push(format!("tag {} {}", name, tags.join(",")));
+    }
+    let body = header + &types + &lines.join("\n") + "\n";
+    format!(
+        "{}{:016x}\n{}",
+        CHECKSUM_PREFIX,
+        fnv1a(body.as_bytes()),
+        body
+    )
+}
+
+const CHECKSUM_PREFIX: &str = "-- checksum fnv1a64 ";
+
+/// 64-bit FNV-1a, enough to notice a truncated or hand-edited file.
+pub(crate) fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ *byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+/// Checks a snapshot against its leading checksum and returns the body
+/// below it. A snapshot without one, such as one saved before checksums
+/// existed, is refused unless `force` is set.
+fn verify_checksum<'a>(path: &Path, source: &'a str, force: bool) -> Result<&'a str, Error> {
+    let Some(rest) = source.strip_prefix(CHECKSUM_PREFIX) else {
+        if force {
+            return Ok(source);
+        }
+        return Err(Error::MissingChecksum(path.display().to_string()));
+    };
+    let (expected, body) = rest.split_once('\n').unwrap_or((rest, ""));
+    let actual = format!("{:016x}", fnv1a(body.as_bytes()));
+    if expected.trim() != actual {
+        return Err(Error::CorruptSnapshot {
+            path: path.display().to_string(),
+            expected: expected.trim().to_string(),
+            actual,
+        });
+    }
+    Ok(body)
+}
+
+fn prelude_header(name: &str, version: &str) -> String {
+    format!("-- prelude {} {}\n", name, version)
+}
+
+/// The `(name, version)` of the prelude a snapshot was saved with, if it
+/// records one.
+fn pinned_prelude(source: &str) -> Option<(&str, &str)> {
+    let rest = source.lines().next()?.strip_prefix("-- prelude ")?;
+    rest.trim().split_once(' ')
+}
+
+fn sessions_dir() -> Result<PathBuf, Error> {
+    paths::data_dir()
+        .map(|dir| dir.join("sessions"))
+        .ok_or_else(|| Error::Io {
+            path: "sessions".to_string(),
+            message: "no data directory, set HOME or XDG_DATA_HOME".to_string(),
+        })
+}
+
+fn session_path(name: &str) -> Result<PathBuf, Error> {
+    if name.is_empty() || name.contains(['/', '\\']) || name.starts_with('.') {
+        return Err(Error::InvalidName(name.to_string()));
+    }
+    Ok(sessions_dir()?.join(format!("{}.hsig", name)))
+}
+
+fn io_error(path: &Path, err: std::io::Error) -> Error {
+    Error::Io {
+        path: path.display().to_string(),
+        message: err.to_string(),
+    }
+}
+
+pub fn save_session(name: &str, env: &Environment) -> Result<String, Error> {
+    let path = session_path(name)?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(|err| io_error(&path, err))?;
+    }
+    fs::write(&path, snapshot(env.view())).map_err(|err| io_error(&path, err))?;
+    Ok(format!("Saved session {}", name))
+}
+
+/// Writes the environment to `path` as JSON, see [`Environment::to_json`],
+/// which `load` reads back. Like a snapshot, the file pins the prelude and
+/// carries a checksum of the prelude and the environment, see
+/// [`json_checksum`]:
+///
+/// ```json
+/// {"checksum":"…","prelude":"core 0.1.0","environment":{…}}
+/// ```
+pub fn save_file(path: &str, env: &Environment) -> Result<String, Error> {
+    let path = Path::new(path);
+    let prelude = format!("{} {}", env.builtins().name(), PRELUDE_VERSION);
+    let environment = Json::parse(&env.to_json())?;
+    let saved = Json::object(vec![
+        (
+            "checksum",
+            Json::String(json_checksum(&prelude, &environment)),
+        ),
+        ("prelude", Json::String(prelude)),
+        ("environment", environment),
+    ]);
+    fs::write(path, saved.to_string() + "\n").map_err(|err| io_error(path, err))?;
+    Ok(format!("Saved to {}", path.display()))
+}
+
+/// The checksum a saved JSON file records, over its prelude and its
+/// environment written compactly, so reformatting the file keeps it valid
+/// but changing either does not.
+fn json_checksum(prelude: &str, environment: &Json) -> String {
+    let contents = format!("{}\n{}", prelude, environment);
+    format!("{:016x}", fnv1a(contents.as_bytes()))
+}
+
+/// Reads the snapshot at `path` into a fresh environment starting from
+/// `builtins`. A snapshot pinned to another prelude is refused unless
+/// `force` is set, since its builtins may have different signatures.
+fn read_snapshot(path: &Path, force: bool, builtins: &BuiltinSet) -> Result<Environment, Error> {
+    let file = fs::read_to_string(path).map_err(|err| io_error(path, err))?;
+    let source = verify_checksum(path, &file, force)?;
+    // Keep line numbers relative to the file when the checksum was stripped.
+    let first_line = if source.len() == file.len() { 1 } else { 2 };
+    if let Some((name, version)) = pinned_prelude(source) {
+        let current = builtins.name();
+        if !force && (name != current || version != PRELUDE_VERSION) {
+            return Err(Error::PreludeMismatch {
+                saved: format!("{} {}", name, version),
+                current: format!("{} {}", current, PRELUDE_VERSION),
+            });
+        }
+    }
+    let lines: Vec<(usize, &str)> = source
+        .lines()
+        .enumerate()
+        .map(|(i, l)| (i + first_line, l))
+        .collect();
+
+    let mut loaded = Environment::with_builtins(builtins.clone());
+    process_decl_block(&lines, &mut loaded).map_err(|errors| Error::ImportFailed {
+        path: path.display().to_string(),
+        errors,
+    })?;
+    Ok(loaded)
+}
+
+/// Reads a file written by `save`, or a snapshot, into a fresh
+/// environment.
+fn read_file(path: &Path, force: bool, builtins: &BuiltinSet) -> Result<Environment, Error> {
+    let file = fs::read_to_string(path).map_err(|err| io_error(path, err))?;
+    if !file.trim_start().starts_with('{') {
+        return read_snapshot(path, force, builtins);
+    }
+    let invalid = |err| match err {
+        Error::Syntax(message) => Error::Io {
+            path: path.display().to_string(),
+            message,
+        },
+        err => err,
+    };
+    let saved = Json::parse(&file).map_err(invalid)?;
+    let pinned = saved
+        .field("prelude")
+        .and_then(Json::as_str)
+        .map_err(invalid)?;
+    let environment = saved.field("environment").map_err(invalid)?;
+    match saved.optional_field("checksum").map_err(invalid)? {
+        Some(expected) => {
+            let expected = expected.as_str().map_err(invalid)?;
+            let actual = json_checksum(pinned, environment);
+            if expected != actual {
+                return Err(Error::CorruptSnapshot {
+                    path: path.display().to_string(),
+                    expected: expected.to_string(),
+                    actual,
+                });
+            }
+        }
+        None if !force => return Err(Error::MissingChecksum(path.display().to_string())),
+        None => {}
+    }
+    let current = format!("{} {}", builtins.name(), PRELUDE_VERSION);
+    if !force && pinned != current {
+        return Err(Error::PreludeMismatch {
+            saved: pinned.to_string(),
+            current,
+        });
+    }
+    Environment::from_json(&environment.to_string()).map_err(invalid)
+}
+
+/// Replaces the environment with the declarations stored at `path`.
+fn load_snapshot(path: &Path, force: bool, env: &mut Environment) -> Result<(), Error> {
+    env.ensure_mutable()?;
+    let loaded = read_file(path, force, env.builtins())?;
+    env.variables = loaded.variables;
+    env.functions = loaded.functions;
+    env.types = loaded.types;
+    env.aliases = loaded.aliases;
+    env.operators = loaded.operators;
+    env.tags = loaded.tags;
+    env.values = loaded.values;
+    Ok(())
+}
+
+/// Replaces the environment with the declarations of a saved session.
+pub fn load_session(name: &str, force: bool, env: &mut Environment) -> Result<String, Error> {
+    load_snapshot(&session_path(name)?, force, env)?;
+    Ok(format!("Loaded session {}", name))
+}
+
+/// Replaces the environment with the declarations of the file at `path`,
+/// as `load --replace` does.
+pub fn restore_file(path: &str, force: bool, env: &mut Environment) -> Result<String, Error> {
+    load_snapshot(Path::new(path), force, env)?;
+    Ok(format!("Restored declarations from {}", path))
+}
+
+/// Which declarations of a snapshot `load` pulls in.
+#[derive(Debug, Default, Clone)]
+pub struct Selection {
+    /// Only names matching this pattern, where `*` matches any run of
+    /// characters.
+    pub only: Option<String>,
+    pub exclude_variables: bool,
+    pub force: bool,
+}
+
+impl Selection {
+    fn includes(&self, name: &str) -> bool {
+        self.only
+            .as_deref()
+            .is_none_or(|pattern| matches_pattern(pattern, name))
+    }
+}
+
+fn matches_pattern(pattern: &str, name: &str) -> bool {
+    match pattern.split_once('*') {
+        None => pattern == name,
+        Some((prefix, rest)) => {
+            name.starts_with(prefix)
+                && (prefix.len()..=name.len())
+                    .filter(|i| name.is_char_boundary(*i))
+                    .any(|i| matches_pattern(rest, &name[i..]))
+        }
+    }
+}
+
+/// Merges the selected declarations of the file at `path`, written by
+/// `save` or a snapshot, into the environment, keeping everything already
+/// declared that it does not redeclare. Types, aliases and operators are
+/// selected by name like the rest, and a type any selected declaration
+/// mentions comes along with it. A type or
+/// alias already declared differently is refused rather than replaced, and
+/// nothing is merged unless everything is.
+pub fn load_file(
+    path: &str,
+    selection: &Selection,
+    env: &mut Environment,
+) -> Result<String, Error> {
+    env.ensure_mutable()?;
+    let loaded = read_file(Path::new(path), selection.force, env.builtins())?;
+    let constructor = |name: &str| loaded.is_constructor(name);
+    let mut variables = Vec::new();
+    if !selection.exclude_variables {
+        variables.extend(
+            loaded
+                .variables
+                .iter()
+                .filter(|(name, _)| !constructor(name) && selection.includes(name)),
+        );
+    }
+    let functions = loaded
+        .functions
+        .iter()
+        .filter(|(name, (output_type, input_types))| {
+            !loaded.is_builtin(name, input_types, output_type)
+                && !constructor(name)
+                && selection.includes(name)
+        })
+        .collect::<Vec<_>>();
+
+    let mut needed = Vec::new();
+    for (_, var_type) in &variables {
+        named_types(var_type, &mut needed);
+    }
+    for (_, (output_type, input_types)) in &functions {
+        for t in input_types.iter().chain(std::iter::once(output_type)) {
+            named_types(t, &mut needed);
+        }
+    }
+    let mut types = BTreeMap::new();
+    needed.extend(
+        loaded
+            .types
+            .keys()
+            .filter(|name| selection.includes(name))
+            .cloned(),
+    );
+    while let Some(name) = needed.pop() {
+        let Some(definition) = loaded.types.get(&name) else {
+            continue;
+        };
+        if types.insert(name, definition).is_none() {
+            for (_, fields) in definition.constructors() {
+                for field in fields {
+                    named_types(field, &mut needed);
+                }
+            }
+            if let TypeDefinition::Record(fields) = definition {
+                for (_, field_type) in fields {
+                    named_types(field_type, &mut needed);
+                }
+            }
+        }
+    }
+
+    let mut merged = env.clone();
+    for (name, definition) in types {
+        match env.type_definition(&name) {
+            Some(existing) if existing == definition => {}
+            Some(_) => return Err(Error::NameTaken(name)),
+            None => merged.declare_type(&name, definition.clone())?,
+        }
+    }
+    for (name, target) in &loaded.aliases {
+        if !selection.includes(name) {
+            continue;
+        }
+        match env.alias(name) {
+            Some(existing) if existing == target => {}
+            Some(_) => return Err(Error::NameTaken(name.clone())),
+            None => merged.declare_alias(name, target.clone())?,
+        }
+    }
+    for (name, var_type) in &variables {
+        match loaded.values.get(*name) {
+            Some(value) => merged.define_variable(name, (*var_type).clone(), value.clone())?,
+            None => merged.declare_variable(name, (*var_type).clone())?,
+        }
+    }
+    for (name, (output_type, input_types)) in &functions {
+        merged.declare_function(name, input_types.clone(), output_type.clone())?;
+    }
+    for operator in loaded.view().user_operators() {
+        if selection.includes(&operator.symbol) {
+            merged.declare_operator(operator.clone())?;
+        }
+    }
+    for (name, tags) in &loaded.tags {
+        if merged.is_declared(name) && loaded.is_declared(name) && selection.includes(name) {
+            merged
+                .tags
+                .entry(name.clone())
+                .or_default()
+                .extend(tags.clone());
+        }
+    }
+    *env = merged;
+    Ok(format!(
+        "Loaded {} declaration(s) from {}",
+        variables.len() + functions.len(),
+        path
+    ))
+}
+
+pub fn list_sessions() -> Result<String, Error> {
+    let dir = sessions_dir()?;
+    let mut names = match fs::read_dir(&dir) {
+        Ok(entries) => entries
+            .filter_map(|entry| entry.ok())
+            .filter_map(|entry| {
+                let file_name = entry.file_name().to_string_lossy().to_string();
+                file_name
+                    .strip_suffix(".hsig")
+                    .filter(|name| !name.starts_with('.'))
+                    .map(str::to_string)
+            })
+            .collect::<Vec<String>>(),
+        Err(_) => Vec::new(),
+    };
+    names.sort();
+    Ok(names.join("\n"))
+}
+
+/// File the REPL auto-saves to, left behind only if it did not exit cleanly.
+pub fn recovery_path() -> Result<PathBuf, Error> {
+    Ok(sessions_dir()?.join(".recovery.hsig"))
+}
+
+pub fn save_recovery(env: &Environment) -> Result<(), Error> {
+    let path = recovery_path()?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(|err| io_error(&path, err))?;
+    }
+    fs::write(&path, snapshot(env.view())).map_err(|err| io_error(&path, err))
+}
+
+pub fn restore_recovery(env: &mut Environment) -> Result<(), Error> {
+    load_snapshot(&recovery_path()?, false, env)
+}
+
+pub fn discard_recovery() {
+    if let Ok(path) = recovery_path() {
+        let _ = fs::remove_file(path);
+    }
+}
+
+/// How many of the most recent commands a crash report keeps.
+const CRASH_HISTORY: usize = 20;
+
+/// What a crash report is built from. The environment may be mid-update
+/// when a command panics, so the report keeps its snapshot from after the
+/// last command that succeeded instead.
+struct CrashState {
+    snapshot: String,
+    commands: VecDeque<String>,
+}
+
+static CRASH_STATE: Mutex<CrashState> = Mutex::new(CrashState {
+    snapshot: String::new(),
+    commands: VecDeque::new(),
+});
+
+/// Notes that `input` is about to run, for the crash report.
+pub fn record_command(input: &str) {
+    if let Ok(mut state) = CRASH_STATE.lock() {
+        if state.commands.len() == CRASH_HISTORY {
+            state.commands.pop_front();
+        }
+        state.commands.push_back(input.to_string());
+    }
+}
+
+/// Keeps `env` as the environment a crash report saves, once a command
+/// has left it in a consistent state.
+pub fn record_environment(env: &Environment) {
+    if let Ok(mut state) = CRASH_STATE.lock() {
+        state.snapshot = snapshot(env.view());
+    }
+}
+
+/// Installs a panic hook that, after the usual panic message, writes the
+/// environment as it was after the last successful command and the commands
+/// leading up to it to a crash file and prints its path. The file is a
+/// snapshot, so `load` brings the work back.
+pub fn install_crash_hook() {
+    let default_hook = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        default_hook(info);
+        match write_crash_report(info) {
+            Ok(path) => eprintln!(
+                "The session was saved to {}, restore it with `load {}`",
+                path.display(),
+                path.display()
+            ),
+            Err(err) => eprintln!("Could not save the session: {}", err),
+        }
+    }));
+}
+
+fn write_crash_report(info: &PanicHookInfo) -> Result<PathBuf, Error> {
+    let seconds = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs());
+    let path = sessions_dir()?.join(format!("crash-{}.hsig", seconds));
+    // The hook may run while the lock is held, so never wait for it.
+    let report = match CRASH_STATE.try_lock() {
+        Ok(state) => {
+            // Drop the checksum, which would not cover the lines added below.
+            let body = state.snapshot.split_once('\n').map_or("", |(_, body)| body);
+            let commands = state
+                .commands
+                .iter()
+                .map(|command| format!("--   {}\n", command))
+                .collect::<String>();
+            format!(
+                "{}-- panicked: {}\n-- last commands, oldest first:\n{}",
+                body,
+                info.to_string().replace('\n', " "),
+                commands
+            )
+        }
+        Err(_) => format!("-- panicked: {}\n", info.to_string().replace('\n', " ")),
+    };
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(|err| io_error(&path, err))?;
+    }
+    fs::write(&path, report).map_err(|err| io_error(&path, err))?;
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::process_input;
+    use crate::types::type_enum::Type;
+
+    /// A path under the system temp dir, removed first.
+    fn temp_file(name: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("typecalc-{}-{}", std::process::id(), name));
+        let _ = fs::remove_file(&path);
+        path
+    }
+
+    #[test]
+    fn snapshot_without_its_checksum_needs_force() {
+        let mut env = Environment::new();
+        env.declare_variable("x", Type::Int).unwrap();
+        let saved = snapshot(env.view());
+        let (_, body) = saved.split_once('\n').unwrap();
+        let path = temp_file("unchecked.hsig");
+        fs::write(&path, body).unwrap();
+        let file = path.display().to_string();
+
+        let mut loaded = Environment::new();
+        assert_eq!(
+            restore_file(&file, false, &mut loaded),
+            Err(Error::MissingChecksum(file.clone()))
+        );
+        assert!(loaded.variable("x").is_none());
+        restore_file(&file, true, &mut loaded).unwrap();
+        assert_eq!(loaded.variable("x"), Some(Type::Int));
+        let _ = fs::remove_file(&path);
+    }
+
+    #[test]
+    fn saved_json_is_checked_and_pinned() {
+        let mut env = Environment::new();
+        env.declare_variable("x", Type::Int).unwrap();
+        let path = temp_file("saved.json");
+        let file = path.display().to_string();
+        save_file(&file, &env).unwrap();
+        let saved = fs::read_to_string(&path).unwrap();
+
+        let mut loaded = Environment::new();
+        restore_file(&file, false, &mut loaded).unwrap();
+        assert_eq!(loaded.variable("x"), Some(Type::Int));
+
+        fs::write(&path, saved.replace("\"Int\"", "\"Bool\"")).unwrap();
+        assert!(matches!(
+            restore_file(&file, false, &mut Environment::new()),
+            Err(Error::CorruptSnapshot { .. })
+        ));
+
+        let unchecked = saved.replacen("\"checksum\"", "\"unchecked\"", 1);
+        fs::write(&path, unchecked).unwrap();
+        assert_eq!(
+            restore_file(&file, false, &mut Environment::new()),
+            Err(Error::MissingChecksum(file.clone()))
+        );
+
+        let environment = Json::parse(&env.to_json()).unwrap();
+        let older = Json::object(vec![
+            (
+                "checksum",
+                Json::String(json_checksum("core 0.0.0", &environment)),
+            ),
+            ("prelude", Json::String("core 0.0.0".to_string())),
+            ("environment", environment),
+        ]);
+        fs::write(&path, older.to_string()).unwrap();
+        assert!(matches!(
+            restore_file(&file, false, &mut Environment::new()),
+            Err(Error::PreludeMismatch { saved, .. }) if saved == "core 0.0.0"
+        ));
+        restore_file(&file, true, &mut loaded).unwrap();
+        let _ = fs::remove_file(&path);
+    }
+
+    #[test]
+    fn snapshots_keep_user_operators() {
+        let mut env = Environment::new();
+        process_input("declare_op <+> 6 right Int Int Int", &mut env).unwrap();
+        process_input("declare_op + 6 left Float Float Float", &mut env).unwrap();
+        let path = temp_file("operators.hsig");
+        fs::write(&path, snapshot(env.view())).unwrap();
+
+        let mut loaded = Environment::new();
+        restore_file(&path.display().to_string(), false, &mut loaded).unwrap();
+        assert_eq!(loaded.operator("<+>"), env.operator("<+>"));
+        assert_eq!(loaded.operator("+"), env.operator("+"));
+        assert_eq!(loaded.operator("*"), env.operator("*"));
+        let _ = fs::remove_file(&path);
+    }
+
+    #[test]
+    fn snapshots_round_trip() {
+        let mut env = Environment::new();
+        for command in [
+            "declare_type Shape = Circle Float | Square Float",
+            "alias Pair = (Int, Int)",
+            "declare_var origin Pair",
+            "declare_var n Int = 5",
+            "declare_func area Shape Float",
+            "tag area geometry,public",
+        ] {
+            process_input(command, &mut env).unwrap();
+        }
+        let path = temp_file("round-trip.hsig");
+        fs::write(&path, snapshot(env.view())).unwrap();
+
+        let mut loaded = Environment::new();
+        restore_file(&path.display().to_string(), false, &mut loaded).unwrap();
+        assert_eq!(snapshot(loaded.view()), snapshot(env.view()));
+        assert_eq!(loaded.value("n"), Some(&Value::Int(5)));
+        assert_eq!(loaded.tags_of("area"), env.tags_of("area"));
+        assert!(loaded.is_constructor("Circle"));
+        let _ = fs::remove_file(&path);
+    }
+}
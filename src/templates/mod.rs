@@ -0,0 +1,103 @@
+use crate::paths;
+use crate::script::process_decl_block;
+use crate::types::type_error::Error;
+use crate::{check_call, format_signature, parse_signature, parse_type, Environment};
+use std::fs;
+
+/// Templates shipped with the crate, as `(name, signature file)` pairs.
+pub const BUILTIN_TEMPLATES: &[(&str, &str)] = &[
+    ("webapi", include_str!("webapi.hsig")),
+    ("numerics", include_str!("numerics.hsig")),
+    ("teaching", include_str!("teaching.hsig")),
+];
+
+/// Looks a template up by name. A `<name>.hsig` file in the user's
+/// `templates` config directory takes precedence over a builtin one.
+pub fn find_template(name: &str) -> Option<String> {
+    let user_template = paths::config_dir()
+        .map(|dir| dir.join("templates").join(format!("{}.hsig", name)))
+        .and_then(|path| fs::read_to_string(path).ok());
+
+    user_template.or_else(|| {
+        BUILTIN_TEMPLATES
+            .iter()
+            .find(|(builtin, _)| *builtin == name)
+            .map(|(_, source)| source.to_string())
+    })
+}
+
+pub fn load_template(name: &str, env: &mut Environment) -> Result<String, Error> {
+    let source = find_template(name).ok_or_else(|| Error::UnknownTemplate(name.to_string()))?;
+    let lines: Vec<(usize, &str)> = source
+        .lines()
+        .enumerate()
+        .map(|(i, l)| (i + 1, l))
+        .collect();
+    process_decl_block(&lines, env).map_err(|errors| Error::ImportFailed {
+        path: format!("template {}", name),
+        errors,
+    })
+}
+
+/// Prefix of the comment lines a pack uses to declare its own assertions:
+/// `-- test: name :: Sig` or `-- test: call f ARG... => Type`.
+const TEST_PREFIX: &str = "-- test:";
+
+fn run_test(test: &str, env: &Environment) -> Result<(), String> {
+    if let Some(call) = test.strip_prefix("call ") {
+        let (call, expected) = call.split_once("=>").ok_or("missing `=> Type`")?;
+        let expected = parse_type(expected.trim(), env).map_err(|err| err.to_string())?;
+        let tokens: Vec<&str> = call.split_whitespace().collect();
+        let actual = check_call(&tokens, env).map_err(|err| err.to_string())?;
+        if actual != expected {
+            return Err(format!("returned {}", actual));
+        }
+        return Ok(());
+    }
+
+    let (name, expected) = parse_signature(test, env).map_err(|err| err.to_string())?;
+    let actual = if let Some(var_type) = env.variable(&name) {
+        vec![var_type]
+    } else if let Some((output_type, input_types)) = env.function(&name) {
+        input_types.iter().chain([output_type]).cloned().collect()
+    } else {
+        return Err("not declared".to_string());
+    };
+    if actual != expected {
+        let (output_type, input_types) = actual.split_last().unwrap();
+        return Err(format!(
+            "declared as {}",
+            format_signature(input_types, output_type)
+        ));
+    }
+    Ok(())
+}
+
+/// Runs a pack's `-- test:` assertions against the current environment.
+pub fn verify_template(name: &str, env: &Environment) -> Result<String, Error> {
+    let source = find_template(name).ok_or_else(|| Error::UnknownTemplate(name.to_string()))?;
+    let tests: Vec<&str> = source
+        .lines()
+        .filter_map(|line| line.trim().strip_prefix(TEST_PREFIX))
+        .map(str::trim)
+        .collect();
+
+    let failures: Vec<String> = tests
+        .iter()
+        .filter_map(|test| {
+            run_test(test, env)
+                .err()
+                .map(|reason| format!("{}: {}", test, reason))
+        })
+        .collect();
+
+    if failures.is_empty() {
+        Ok(format!("{}: {} test(s) passed", name, tests.len()))
+    } else {
+        Err(Error::VerificationFailed {
+            pack: name.to_string(),
+            total: tests.len(),
+            failures,
+        })
+    }
+}
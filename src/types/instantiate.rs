@@ -0,0 +1,67 @@
+use crate::types::type_enum::Type;
+use std::collections::HashMap;
+
+/// Matches a declared parameter type against the type of an argument,
+/// binding the parameter's type variables as it goes. A variable already
+/// bound must match what it was bound to; variables in `actual` are rigid
+/// and only match themselves.
+pub fn match_type(pattern: &Type, actual: &Type, bindings: &mut HashMap<String, Type>) -> bool {
+    match (pattern, actual) {
+        (Type::Var(name), _) => match bindings.get(name) {
+            Some(bound) => bound == actual,
+            None => {
+                bindings.insert(name.clone(), actual.clone());
+                true
+            }
+        },
+        (Type::List(pattern), Type::List(actual)) => match_type(pattern, actual, bindings),
+        (Type::Either(p1, p2), Type::Either(a1, a2))
+        | (Type::Function(p1, p2), Type::Function(a1, a2)) => {
+            match_type(p1, a1, bindings) && match_type(p2, a2, bindings)
+        }
+        (Type::Tuple(patterns), Type::Tuple(actuals)) => {
+            patterns.len() == actuals.len()
+                && patterns
+                    .iter()
+                    .zip(actuals)
+                    .all(|(pattern, actual)| match_type(pattern, actual, bindings))
+        }
+        (pattern, actual) => pattern == actual,
+    }
+}
+
+/// Replaces every bound type variable in `t`; unbound ones stay as they are.
+pub fn substitute(t: &Type, bindings: &HashMap<String, Type>) -> Type {
+    match t {
+        Type::Var(name) => bindings.get(name).cloned().unwrap_or_else(|| t.clone()),
+        Type::List(element) => Type::List(Box::new(substitute(element, bindings))),
+        Type::Either(left, right) => Type::Either(
+            Box::new(substitute(left, bindings)),
+            Box::new(substitute(right, bindings)),
+        ),
+        Type::Function(argument, result) => Type::Function(
+            Box::new(substitute(argument, bindings)),
+            Box::new(substitute(result, bindings)),
+        ),
+        Type::Tuple(elements) => Type::Tuple(
+            elements
+                .iter()
+                .map(|element| substitute(element, bindings))
+                .collect(),
+        ),
+        other => other.clone(),
+    }
+}
+
+/// Whether `t` mentions any type variable.
+pub fn is_polymorphic(t: &Type) -> bool {
+    match t {
+        Type::Var(_) => true,
+        Type::List(element) => is_polymorphic(element),
+        Type::Either(left, right) | Type::Function(left, right) => {
+            is_polymorphic(left) || is_polymorphic(right)
+        }
+        Type::Tuple(elements) => elements.iter().any(is_polymorphic),
+        _ => false,
+    }
+}
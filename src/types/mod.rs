@@ -1,2 +1,6 @@
+pub mod instantiate;
+pub mod signature;
+pub mod type_definition;
 pub mod type_enum;
 pub mod type_error;
+pub mod type_parser;
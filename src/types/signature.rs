@@ -0,0 +1,79 @@
+use crate::environment::Environment;
+use crate::inference::unify::{canonical, curried};
+use crate::session::fnv1a;
+use crate::types::type_enum::Type;
+
+/// A function's parameter types and result, for comparing declarations
+/// from outside the REPL.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Signature {
+    pub input_types: Vec<Type>,
+    pub output_type: Type,
+}
+
+impl Signature {
+    pub fn new(input_types: Vec<Type>, output_type: Type) -> Self {
+        Signature {
+            input_types,
+            output_type,
+        }
+    }
+
+    /// The signature `name` is declared with in `env`, if it is a function.
+    pub fn of(name: &str, env: &Environment) -> Option<Self> {
+        env.function(name).map(|(output_type, input_types)| {
+            Signature::new(input_types.clone(), output_type.clone())
+        })
+    }
+
+    /// The whole signature as one curried function type.
+    pub fn to_type(&self) -> Type {
+        curried(&self.input_types, &self.output_type)
+    }
+
+    /// Whether both signatures mean the same once aliases known to `env`
+    /// are expanded and type variables renamed consistently, so
+    /// `Money -> a` equals `(Int, String) -> b` under `alias Money =
+    /// (Int, String)`.
+    pub fn structurally_equal(&self, other: &Signature, env: &Environment) -> bool {
+        self.structure(env) == other.structure(env)
+    }
+
+    /// A hash of the structure [`structurally_equal`](Self::structurally_equal)
+    /// compares, equal for equal signatures and the same across runs and
+    /// platforms, so it can be stored to deduplicate signatures later.
+    pub fn structural_hash(&self, env: &Environment) -> u64 {
+        fnv1a(self.structure(env).to_string().as_bytes())
+    }
+
+    fn structure(&self, env: &Environment) -> Type {
+        canonical(&expand_aliases(&self.to_type(), env), env, false)
+    }
+}
+
+/// Replaces every alias in `t` by what it stands for, recursively, since a
+/// hand-built type may still name one.
+fn expand_aliases(t: &Type, env: &Environment) -> Type {
+    match t {
+        Type::Named(name) => match env.alias(name) {
+            Some(target) => expand_aliases(target, env),
+            None => t.clone(),
+        },
+        Type::List(element) => Type::List(Box::new(expand_aliases(element, env))),
+        Type::Either(left, right) => Type::Either(
+            Box::new(expand_aliases(left, env)),
+            Box::new(expand_aliases(right, env)),
+        ),
+        Type::Function(argument, result) => Type::Function(
+            Box::new(expand_aliases(argument, env)),
+            Box::new(expand_aliases(result, env)),
+        ),
+        Type::Tuple(elements) => Type::Tuple(
+            elements
+                .iter()
+                .map(|element| expand_aliases(element, env))
+                .collect(),
+        ),
+        other => other.clone(),
+    }
+}
@@ -0,0 +1,51 @@
+use crate::types::type_enum::Type;
+
+/// The body of a user-declared nominal type.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum TypeDefinition {
+    /// Alternatives, each a constructor name with its field types, as in
+    /// `Shape = Circle Float | Rect Float Float`.
+    Sum(Vec<(String, Vec<Type>)>),
+    /// Named fields, as in `Point = { x: Int, y: Int }`.
+    Record(Vec<(String, Type)>),
+    /// A distinct type wrapping another, as in `newtype UserId = Int`. Its
+    /// one constructor shares the type's name.
+    Newtype { constructor: String, wrapped: Type },
+}
+
+impl TypeDefinition {
+    /// Every constructor with its field types. A constructor without fields
+    /// is a value of the type, the others are functions returning it.
+    pub fn constructors(&self) -> Vec<(&str, &[Type])> {
+        match self {
+            TypeDefinition::Sum(constructors) => constructors
+                .iter()
+                .map(|(name, fields)| (name.as_str(), fields.as_slice()))
+                .collect(),
+            TypeDefinition::Record(_) => Vec::new(),
+            TypeDefinition::Newtype {
+                constructor,
+                wrapped,
+            } => vec![(constructor.as_str(), std::slice::from_ref(wrapped))],
+        }
+    }
+
+    /// The keyword declaring this kind of type in a `begin decls` block.
+    pub fn keyword(&self) -> &'static str {
+        match self {
+            TypeDefinition::Newtype { .. } => "newtype",
+            _ => "type",
+        }
+    }
+
+    /// The type of field `name`, for records.
+    pub fn field(&self, name: &str) -> Option<&Type> {
+        match self {
+            TypeDefinition::Sum(_) | TypeDefinition::Newtype { .. } => None,
+            TypeDefinition::Record(fields) => fields
+                .iter()
+                .find(|(field, _)| field == name)
+                .map(|(_, field_type)| field_type),
+        }
+    }
+}
@@ -1,6 +1,24 @@
-#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[derive(Debug, PartialEq, Eq, Clone)]
 pub enum Type {
     Int,
     Float,
     Bool,
+    String,
+    Char,
+    Unit,
+    /// A homogeneous list, written `[Int]`.
+    List(Box<Type>),
+    /// One of two alternatives, written `Either Left Right`; by convention
+    /// `Left` carries the error of a fallible result.
+    Either(Box<Type>, Box<Type>),
+    /// A nominal type declared with `declare_type`.
+    Named(String),
+    /// A fixed-size product, written `(Int, Bool)`.
+    Tuple(Vec<Type>),
+    /// A type variable such as `a`, standing for any type in a generic
+    /// signature.
+    Var(String),
+    /// A function taking one argument, written `(Int -> Bool)`. Functions of
+    /// several arguments are curried: `Int -> Int -> Int`.
+    Function(Box<Type>, Box<Type>),
 }
@@ -1,6 +1,210 @@
-#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+use crate::types::type_enum::Type;
+
+#[derive(Debug, PartialEq, Eq, Clone)]
 pub enum Error {
-    TypeError,
-    UndeclaredFunction,
-    UndeclaredVariable,
+    UndeclaredFunction(String),
+    UndeclaredVariable(String),
+    ArityMismatch {
+        function: String,
+        expected: usize,
+        got: usize,
+    },
+    UnknownCommand {
+        name: String,
+        suggestions: Vec<String>,
+    },
+    Syntax(String),
+    ArgumentMismatch {
+        function: String,
+        input_types: Vec<Type>,
+        output_type: Type,
+        position: usize,
+        provided: Type,
+    },
+    AscriptionMismatch {
+        expression: String,
+        actual: Type,
+        ascribed: Type,
+    },
+    UnknownType {
+        name: String,
+        suggestions: Vec<String>,
+    },
+    InvalidName(String),
+    Usage {
+        command: String,
+        expected: String,
+        problem: String,
+    },
+    UnknownOperator(String),
+    AmbiguousOperators {
+        left: String,
+        right: String,
+        precedence: u8,
+    },
+    UnknownField {
+        type_name: String,
+        field: String,
+        suggestions: Vec<String>,
+    },
+    NotARecord(Type),
+    CompositionMismatch {
+        outer: String,
+        inner: String,
+        produced: Type,
+        expected: Type,
+    },
+    TypeMismatch {
+        expected: Type,
+        actual: Type,
+    },
+    InfiniteType {
+        variable: String,
+        within: Type,
+    },
+    BranchMismatch {
+        then_branch: Type,
+        else_branch: Type,
+    },
+    CorruptSnapshot {
+        path: String,
+        expected: String,
+        actual: String,
+    },
+    MissingChecksum(String),
+    UnknownModule {
+        name: String,
+        suggestions: Vec<String>,
+    },
+    ImportCycle(Vec<String>),
+    UnknownSymbol {
+        name: String,
+        suggestions: Vec<String>,
+    },
+    InvalidManifest {
+        path: String,
+        line: usize,
+        message: String,
+    },
+    PreludeMismatch {
+        saved: String,
+        current: String,
+    },
+    NoInferenceCandidates(String),
+    InconsistentCalls {
+        function: String,
+        detail: String,
+    },
+    NoPendingCandidates,
+    NoOpenScope,
+    Redeclaration {
+        name: String,
+        previous: Type,
+    },
+    NameTaken(String),
+    ConfirmationRequired {
+        question: String,
+    },
+    EnvironmentFrozen,
+    LimitExceeded {
+        limit: &'static str,
+        max: usize,
+        actual: usize,
+    },
+    Io {
+        path: String,
+        message: String,
+    },
+    UnknownTemplate(String),
+    AssertionFailed {
+        expression: String,
+        expected: Type,
+        actual: Type,
+    },
+    /// An `assert_error` whose command succeeded, when `actual` is `None`,
+    /// or failed with another error.
+    ExpectedError {
+        command: String,
+        expected: String,
+        actual: Option<String>,
+    },
+    VerificationFailed {
+        pack: String,
+        total: usize,
+        failures: Vec<String>,
+    },
+    ImportFailed {
+        path: String,
+        errors: Vec<(usize, Error)>,
+    },
+    /// A host value that does not have the type declared for it.
+    ValueMismatch {
+        value: String,
+        expected: Type,
+    },
+    /// A declaration whose type mentions a type variable that no
+    /// parameter binds, such as `x :: a` or `f :: Int -> a`.
+    UnboundTypeVariable {
+        name: String,
+        declared: Type,
+        variable: String,
+    },
+    /// `error`, narrowed down to the smallest part of a larger expression
+    /// that fails on its own.
+    InSubexpression {
+        subexpression: String,
+        error: Box<Error>,
+    },
+}
+
+impl Error {
+    /// A stable name for the kind of error, the variant name, for
+    /// `assert_error` and JSON diagnostics.
+    pub fn code(&self) -> &'static str {
+        match self {
+            Error::UndeclaredFunction(..) => "UndeclaredFunction",
+            Error::UndeclaredVariable(..) => "UndeclaredVariable",
+            Error::ArityMismatch { .. } => "ArityMismatch",
+            Error::UnknownCommand { .. } => "UnknownCommand",
+            Error::Syntax(..) => "Syntax",
+            Error::ArgumentMismatch { .. } => "ArgumentMismatch",
+            Error::AscriptionMismatch { .. } => "AscriptionMismatch",
+            Error::UnknownType { .. } => "UnknownType",
+            Error::InvalidName(..) => "InvalidName",
+            Error::Usage { .. } => "Usage",
+            Error::UnknownOperator(..) => "UnknownOperator",
+            Error::AmbiguousOperators { .. } => "AmbiguousOperators",
+            Error::UnknownField { .. } => "UnknownField",
+            Error::NotARecord(..) => "NotARecord",
+            Error::CompositionMismatch { .. } => "CompositionMismatch",
+            Error::TypeMismatch { .. } => "TypeMismatch",
+            Error::InfiniteType { .. } => "InfiniteType",
+            Error::BranchMismatch { .. } => "BranchMismatch",
+            Error::CorruptSnapshot { .. } => "CorruptSnapshot",
+            Error::MissingChecksum(_) => "MissingChecksum",
+            Error::UnknownModule { .. } => "UnknownModule",
+            Error::ImportCycle(..) => "ImportCycle",
+            Error::UnknownSymbol { .. } => "UnknownSymbol",
+            Error::InvalidManifest { .. } => "InvalidManifest",
+            Error::PreludeMismatch { .. } => "PreludeMismatch",
+            Error::NoInferenceCandidates(..) => "NoInferenceCandidates",
+            Error::InconsistentCalls { .. } => "InconsistentCalls",
+            Error::NoPendingCandidates => "NoPendingCandidates",
+            Error::NoOpenScope => "NoOpenScope",
+            Error::Redeclaration { .. } => "Redeclaration",
+            Error::NameTaken(..) => "NameTaken",
+            Error::ConfirmationRequired { .. } => "ConfirmationRequired",
+            Error::EnvironmentFrozen => "EnvironmentFrozen",
+            Error::LimitExceeded { .. } => "LimitExceeded",
+            Error::Io { .. } => "Io",
+            Error::UnknownTemplate(..) => "UnknownTemplate",
+            Error::AssertionFailed { .. } => "AssertionFailed",
+            Error::VerificationFailed { .. } => "VerificationFailed",
+            Error::ImportFailed { .. } => "ImportFailed",
+            Error::ValueMismatch { .. } => "ValueMismatch",
+            Error::UnboundTypeVariable { .. } => "UnboundTypeVariable",
+            Error::InSubexpression { error, .. } => error.code(),
+            Error::ExpectedError { .. } => "ExpectedError",
+        }
+    }
 }
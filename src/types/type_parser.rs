@@ -0,0 +1,126 @@
+use crate::types::type_enum::Type;
+use crate::types::type_error::Error;
+
+/// Parses a whitespace-separated sequence of types. Constructors take their
+/// arguments prefix-style with a fixed arity, so `Int Either String Int` is
+/// two types; parentheses may group an argument for readability, as in
+/// `Either (Either Char Int) Bool`. Lowercase names are type variables,
+/// `(a, b)` is a tuple and `(a -> b)` a function. Any other name is looked
+/// up with `resolve`, which knows the user-declared types and aliases.
+pub fn parse_types(
+    source: &str,
+    resolve: &dyn Fn(&str) -> Option<Type>,
+) -> Result<Vec<Type>, Error> {
+    let tokens = tokenize(source)?;
+    let mut parser = Parser {
+        tokens,
+        position: 0,
+        resolve,
+    };
+    let mut types = Vec::new();
+    while parser.position < parser.tokens.len() {
+        types.push(parser.parse_one()?);
+    }
+    Ok(types)
+}
+
+fn tokenize(source: &str) -> Result<Vec<&str>, Error> {
+    let mut tokens = Vec::new();
+    let mut rest = source.trim_start();
+    while let Some(c) = rest.chars().next() {
+        let len = if rest.starts_with("->") {
+            2
+        } else if "[](),".contains(c) {
+            1
+        } else if c.is_alphanumeric() || c == '_' {
+            rest.find(|c: char| !(c.is_alphanumeric() || c == '_'))
+                .unwrap_or(rest.len())
+        } else {
+            return Err(Error::Syntax(format!("unexpected `{}` in a type", c)));
+        };
+        tokens.push(&rest[..len]);
+        rest = rest[len..].trim_start();
+    }
+    Ok(tokens)
+}
+
+struct Parser<'a> {
+    tokens: Vec<&'a str>,
+    position: usize,
+    resolve: &'a dyn Fn(&str) -> Option<Type>,
+}
+
+impl Parser<'_> {
+    fn expect(&mut self, close: &str) -> Result<(), Error> {
+        match self.tokens.get(self.position) {
+            Some(token) if *token == close => {
+                self.position += 1;
+                Ok(())
+            }
+            Some(token) => Err(Error::Syntax(format!(
+                "expected `{}` in a type, found `{}`",
+                close, token
+            ))),
+            None => Err(Error::Syntax(format!("missing `{}` in a type", close))),
+        }
+    }
+
+    /// A type optionally followed by `-> Type`, associating to the right.
+    /// Only allowed inside brackets, where the arrow cannot be mistaken for
+    /// one between a signature's parameters.
+    fn parse_arrow(&mut self) -> Result<Type, Error> {
+        let argument = self.parse_one()?;
+        if self.tokens.get(self.position) == Some(&"->") {
+            self.position += 1;
+            let result = self.parse_arrow()?;
+            return Ok(Type::Function(Box::new(argument), Box::new(result)));
+        }
+        Ok(argument)
+    }
+
+    fn parse_one(&mut self) -> Result<Type, Error> {
+        let Some(token) = self.tokens.get(self.position).copied() else {
+            return Err(Error::Syntax("a type ended early".to_string()));
+        };
+        self.position += 1;
+        match token {
+            "[" => {
+                let element = self.parse_arrow()?;
+                self.expect("]")?;
+                Ok(Type::List(Box::new(element)))
+            }
+            "(" => {
+                let mut elements = vec![self.parse_arrow()?];
+                while self.tokens.get(self.position) == Some(&",") {
+                    self.position += 1;
+                    elements.push(self.parse_arrow()?);
+                }
+                self.expect(")")?;
+                if elements.len() == 1 {
+                    Ok(elements.pop().unwrap())
+                } else {
+                    Ok(Type::Tuple(elements))
+                }
+            }
+            "]" | ")" | "," | "->" => {
+                Err(Error::Syntax(format!("unexpected `{}` in a type", token)))
+            }
+            "Either" => {
+                let left = self.parse_one()?;
+                let right = self.parse_one()?;
+                Ok(Type::Either(Box::new(left), Box::new(right)))
+            }
+            "Int" => Ok(Type::Int),
+            "Float" => Ok(Type::Float),
+            "Bool" => Ok(Type::Bool),
+            "String" => Ok(Type::String),
+            "Char" => Ok(Type::Char),
+            "Unit" => Ok(Type::Unit),
+            name if name.starts_with(|c: char| c.is_lowercase()) => Ok(Type::Var(name.to_string())),
+            name => (self.resolve)(name).ok_or_else(|| Error::UnknownType {
+                name: name.to_string(),
+                suggestions: Vec::new(),
+            }),
+        }
+    }
+}
@@ -0,0 +1,376 @@
+use crate::environment::Environment;
+use crate::inference::ast::Expr;
+use crate::inference::unify;
+use crate::parser::{self, show_expr};
+use crate::types::instantiate::{match_type, substitute};
+use crate::types::type_definition::TypeDefinition;
+use crate::types::type_enum::Type;
+use crate::types::type_error::Error;
+use std::collections::HashMap;
+use std::fmt;
+
+/// A runtime value passed between the host program and the calculator.
+/// Values carry no type of their own beyond their shape; [`Value::check`]
+/// compares that shape against a declared type at the boundary.
+#[derive(Debug, PartialEq, Clone)]
+pub enum Value {
+    Int(i64),
+    Float(f64),
+    Bool(bool),
+    String(String),
+    Char(char),
+    Unit,
+    List(Vec<Value>),
+    /// A value of a declared record type, with its fields by name.
+    Record {
+        type_name: String,
+        fields: Vec<(String, Value)>,
+    },
+    /// A function written in the expression language, such as `\x -> x`.
+    Closure(Expr),
+}
+
+impl Value {
+    /// Reads a literal as written in commands: `5`, `2.5`, `true`,
+    /// `"text"`, `'c'`, or a list of literals such as `[1, 2, 3]`.
+    pub fn from_literal(token: &str) -> Option<Value> {
+        let token = token.trim();
+        if let Some(inner) = token.strip_prefix('[').and_then(|t| t.strip_suffix(']')) {
+            if inner.trim().is_empty() {
+                return Some(Value::List(Vec::new()));
+            }
+            return split_elements(inner)?
+                .into_iter()
+                .map(Value::from_literal)
+                .collect::<Option<Vec<Value>>>()
+                .map(Value::List);
+        }
+        match parser::literal_type(token)? {
+            Type::Int => token.parse().ok().map(Value::Int),
+            Type::Float => token.parse().ok().map(Value::Float),
+            Type::Bool => Some(Value::Bool(token == "true")),
+            Type::String => Some(Value::String(unescape(&token[1..token.len() - 1]))),
+            Type::Char => unescape(&token[1..token.len() - 1])
+                .chars()
+                .next()
+                .map(Value::Char),
+            _ => None,
+        }
+    }
+
+    /// The type this value evidently has, when its shape alone tells: not
+    /// for an empty list, a list mixing types, or a closure that does not
+    /// typecheck in `env`.
+    pub fn type_of(&self, env: &Environment) -> Option<Type> {
+        match self {
+            Value::Int(_) => Some(Type::Int),
+            Value::Float(_) => Some(Type::Float),
+            Value::Bool(_) => Some(Type::Bool),
+            Value::String(_) => Some(Type::String),
+            Value::Char(_) => Some(Type::Char),
+            Value::Unit => Some(Type::Unit),
+            Value::List(elements) => {
+                let (first, rest) = elements.split_first()?;
+                let element_type = first.type_of(env)?;
+                rest.iter()
+                    .all(|element| element.type_of(env).as_ref() == Some(&element_type))
+                    .then(|| Type::List(Box::new(element_type)))
+            }
+            Value::Record { type_name, .. } => Some(Type::Named(type_name.clone())),
+            Value::Closure(expr) => unify::infer(expr, env).ok(),
+        }
+    }
+
+    /// Checks the value against `expected`. Scalars follow the ascription
+    /// coercions, so an `Int` fits a `Float`; a closure fits when the type
+    /// inferred for it is at least as general as `expected`.
+    pub fn check(&self, expected: &Type, env: &Environment) -> Result<(), Error> {
+        self.check_with(expected, env, &mut HashMap::new())
+    }
+
+    fn check_with(
+        &self,
+        expected: &Type,
+        env: &Environment,
+        bindings: &mut HashMap<String, Type>,
+    ) -> Result<(), Error> {
+        if self.fits(expected, env, bindings) {
+            Ok(())
+        } else {
+            Err(Error::ValueMismatch {
+                value: self.to_string(),
+                expected: substitute(expected, bindings),
+            })
+        }
+    }
+
+    fn fits(
+        &self,
+        expected: &Type,
+        env: &Environment,
+        bindings: &mut HashMap<String, Type>,
+    ) -> bool {
+        match (self, expected) {
+            (_, Type::Var(_)) => match self.type_of(env) {
+                Some(actual) => match_type(expected, &actual, bindings),
+                None => true,
+            },
+            (Value::List(elements), Type::List(element_type)) => elements
+                .iter()
+                .all(|element| element.fits(element_type, env, bindings)),
+            (Value::Record { type_name, fields }, Type::Named(name)) => {
+                let Some(TypeDefinition::Record(declared)) = env.type_definition(name) else {
+                    return false;
+                };
+                type_name == name
+                    && fields.len() == declared.len()
+                    && declared.iter().all(|(field, field_type)| {
+                        fields
+                            .iter()
+                            .find(|(name, _)| name == field)
+                            .is_some_and(|(_, value)| value.fits(field_type, env, bindings))
+                    })
+            }
+            (Value::Closure(expr), Type::Function(..)) => match unify::infer(expr, env) {
+                Ok(inferred) => match_type(
+                    &inferred,
+                    &substitute(expected, bindings),
+                    &mut HashMap::new(),
+                ),
+                Err(_) => false,
+            },
+            (Value::List(_) | Value::Record { .. } | Value::Closure(_), _) => false,
+            (scalar, expected) => scalar
+                .type_of(env)
+                .is_some_and(|actual| env.can_coerce(&actual, expected)),
+        }
+    }
+}
+
+/// Checks host-supplied arguments against the declared signature of
+/// `function` and returns the result type the host's answer must have,
+/// with the signature's type variables bound by the arguments.
+pub fn check_arguments(
+    function: &str,
+    arguments: &[Value],
+    env: &Environment,
+) -> Result<Type, Error> {
+    let (output_type, input_types) = env
+        .function(function)
+        .ok_or_else(|| Error::UndeclaredFunction(function.to_string()))?;
+    if arguments.len() != input_types.len() {
+        return Err(Error::ArityMismatch {
+            function: function.to_string(),
+            expected: input_types.len(),
+            got: arguments.len(),
+        });
+    }
+    let mut bindings = HashMap::new();
+    for (argument, input_type) in arguments.iter().zip(input_types) {
+        argument.check_with(input_type, env, &mut bindings)?;
+    }
+    Ok(substitute(output_type, &bindings))
+}
+
+/// Splits the inside of a list literal at its top-level commas.
+fn split_elements(inner: &str) -> Option<Vec<&str>> {
+    let mut elements = Vec::new();
+    let mut depth = 0usize;
+    let mut start = 0;
+    let mut rest = inner.char_indices();
+    while let Some((i, c)) = rest.next() {
+        match c {
+            '"' => {
+                let length = parser::string_length(&inner[i..])?;
+                // Skip to the closing quote.
+                for _ in inner[i + 1..i + length].chars() {
+                    rest.next();
+                }
+            }
+            '[' => depth += 1,
+            ']' => depth = depth.checked_sub(1)?,
+            ',' if depth == 0 => {
+                elements.push(&inner[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    elements.push(&inner[start..]);
+    Some(elements)
+}
+
+/// Resolves the backslash escapes of a string or character literal.
+fn unescape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        out.push(match (c, chars.clone().next()) {
+            ('\\', Some(escaped)) => {
+                chars.next();
+                match escaped {
+                    'n' => '\n',
+                    't' => '\t',
+                    other => other,
+                }
+            }
+            (c, _) => c,
+        });
+    }
+    out
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::Int(n) => write!(f, "{}", n),
+            Value::Float(x) => write!(f, "{:?}", x),
+            Value::Bool(b) => write!(f, "{}", b),
+            Value::String(text) => write!(f, "{:?}", text),
+            Value::Char(c) => write!(f, "{:?}", c),
+            Value::Unit => write!(f, "()"),
+            Value::List(elements) => {
+                let elements = elements
+                    .iter()
+                    .map(|element| element.to_string())
+                    .collect::<Vec<String>>();
+                write!(f, "[{}]", elements.join(", "))
+            }
+            Value::Record { type_name, fields } => {
+                let fields = fields
+                    .iter()
+                    .map(|(name, value)| format!("{}: {}", name, value))
+                    .collect::<Vec<String>>();
+                write!(f, "{} {{ {} }}", type_name, fields.join(", "))
+            }
+            Value::Closure(expr) => write!(f, "{}", show_expr(expr)),
+        }
+    }
+}
+
+impl From<i64> for Value {
+    fn from(n: i64) -> Self {
+        Value::Int(n)
+    }
+}
+
+impl From<f64> for Value {
+    fn from(x: f64) -> Self {
+        Value::Float(x)
+    }
+}
+
+impl From<bool> for Value {
+    fn from(b: bool) -> Self {
+        Value::Bool(b)
+    }
+}
+
+impl From<String> for Value {
+    fn from(text: String) -> Self {
+        Value::String(text)
+    }
+}
+
+impl From<&str> for Value {
+    fn from(text: &str) -> Self {
+        Value::String(text.to_string())
+    }
+}
+
+impl From<char> for Value {
+    fn from(c: char) -> Self {
+        Value::Char(c)
+    }
+}
+
+impl From<()> for Value {
+    fn from(_: ()) -> Self {
+        Value::Unit
+    }
+}
+
+impl<T: Into<Value>> From<Vec<T>> for Value {
+    fn from(elements: Vec<T>) -> Self {
+        Value::List(elements.into_iter().map(Into::into).collect())
+    }
+}
+
+/// The error of a failed conversion out of a [`Value`].
+fn mismatch(value: &Value, expected: Type) -> Error {
+    Error::ValueMismatch {
+        value: value.to_string(),
+        expected,
+    }
+}
+
+impl TryFrom<Value> for i64 {
+    type Error = Error;
+
+    fn try_from(value: Value) -> Result<Self, Error> {
+        match value {
+            Value::Int(n) => Ok(n),
+            other => Err(mismatch(&other, Type::Int)),
+        }
+    }
+}
+
+impl TryFrom<Value> for f64 {
+    type Error = Error;
+
+    /// An `Int` widens to a `Float`, as in ascriptions.
+    fn try_from(value: Value) -> Result<Self, Error> {
+        match value {
+            Value::Float(x) => Ok(x),
+            Value::Int(n) => Ok(n as f64),
+            other => Err(mismatch(&other, Type::Float)),
+        }
+    }
+}
+
+impl TryFrom<Value> for bool {
+    type Error = Error;
+
+    fn try_from(value: Value) -> Result<Self, Error> {
+        match value {
+            Value::Bool(b) => Ok(b),
+            other => Err(mismatch(&other, Type::Bool)),
+        }
+    }
+}
+
+impl TryFrom<Value> for String {
+    type Error = Error;
+
+    fn try_from(value: Value) -> Result<Self, Error> {
+        match value {
+            Value::String(text) => Ok(text),
+            other => Err(mismatch(&other, Type::String)),
+        }
+    }
+}
+
+impl TryFrom<Value> for char {
+    type Error = Error;
+
+    fn try_from(value: Value) -> Result<Self, Error> {
+        match value {
+            Value::Char(c) => Ok(c),
+            other => Err(mismatch(&other, Type::Char)),
+        }
+    }
+}
+
+impl<T: TryFrom<Value, Error = Error>> TryFrom<Value> for Vec<T> {
+    type Error = Error;
+
+    fn try_from(value: Value) -> Result<Self, Error> {
+        match value {
+            Value::List(elements) => elements.into_iter().map(T::try_from).collect(),
+            other => Err(mismatch(
+                &other,
+                Type::List(Box::new(Type::Var("a".to_string()))),
+            )),
+        }
+    }
+}